@@ -1,15 +1,19 @@
 extern crate proc_macro;
+mod monkey_fn;
+mod monkey_value;
+
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::DeriveInput;
 use syn::{self, Data};
 
-/// Generate `fn express_node()` uniformly for different types of `nodes`
+/// Implement `Node` and `Display` for a leaf expression with `token` and
+/// `value` fields.
 #[proc_macro_derive(DefaultExpressionNode)]
 pub fn expression_node(input: TokenStream) -> TokenStream {
     let ast: DeriveInput = syn::parse(input).unwrap();
     let id = ast.ident;
-    let Data::Struct(_) = ast.data else{
+    let Data::Struct(_) = ast.data else {
         panic!("MyDefault derive macro must use in struct");
     };
     quote!();
@@ -19,6 +23,10 @@ pub fn expression_node(input: TokenStream) -> TokenStream {
             fn token_literal(&self) -> String {
                 self.token.literal.to_string()
             }
+
+            fn span(&self) -> Span {
+                self.token.span
+            }
         }
 
         impl std::fmt::Display for #id {
@@ -26,24 +34,16 @@ pub fn expression_node(input: TokenStream) -> TokenStream {
                 write!(f, "{}", self.value)
             }
         }
-
-    impl Expression for #id {
-        fn expression_node(&self) {
-        }
-
-        fn as_any(&self) -> &dyn std::any::Any {
-            self
-        }
-    }
     }
     .into()
 }
 
+/// Implement `Node` for a statement with a `token` field.
 #[proc_macro_derive(DefaultStatementNode)]
 pub fn statement_node(input: TokenStream) -> TokenStream {
     let ast: DeriveInput = syn::parse(input).unwrap();
     let id = ast.ident;
-    let Data::Struct(_) = ast.data else{
+    let Data::Struct(_) = ast.data else {
         panic!("MyDefault derive macro must use in struct");
     };
     quote!();
@@ -53,17 +53,46 @@ pub fn statement_node(input: TokenStream) -> TokenStream {
             fn token_literal(&self) -> String {
                 self.token.literal.to_string()
             }
-        }
 
-
-    impl Statement for #id {
-        fn statement_node(&self) {
-        }
-
-        fn as_any(&self) -> &dyn std::any::Any {
-            self
+            fn span(&self) -> Span {
+                self.token.span
+            }
         }
     }
-    }
     .into()
 }
+
+/// Convert a struct or enum to and from a Monkey hash by implementing
+/// `FromValue` and `IntoValue`. Struct fields become string keys, unit
+/// variants become strings and struct variants become hashes tagged
+/// with a `"type"` key.
+///
+/// Fields accept `#[monkey(rename = "key")]`, `#[monkey(default)]` and
+/// `#[monkey(default = "path::to::fn")]`. `Option` fields may be missing.
+/// Type parameters must themselves implement `FromValue` and `IntoValue`.
+#[proc_macro_derive(MonkeyValue, attributes(monkey))]
+pub fn monkey_value(input: TokenStream) -> TokenStream {
+    let ast = syn::parse_macro_input!(input as DeriveInput);
+    monkey_value::expand(ast)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Turn a plain Rust function into a Monkey builtin. Next to the
+/// function it generates `<name>_builtin() -> Builtin`, which checks the
+/// argument count, converts arguments with `FromValue` and the result
+/// with `IntoValue`, so returning `Err` becomes a Monkey error. The
+/// function's `///` comments become the builtin's documentation.
+///
+/// The builtin takes the function's name unless given
+/// `#[monkey_fn(name = "other")]`.
+#[proc_macro_attribute]
+pub fn monkey_fn(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut args = monkey_fn::Args::default();
+    let parser = syn::meta::parser(|meta| args.parse(meta));
+    syn::parse_macro_input!(attr with parser);
+    let item = syn::parse_macro_input!(item as syn::ItemFn);
+    monkey_fn::expand(args, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,96 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::meta::ParseNestedMeta;
+use syn::{Expr, ExprLit, FnArg, ItemFn, Lit, LitStr, Meta};
+
+/// The most arguments `NativeFunction` is implemented for.
+const MAX_ARGUMENTS: usize = 6;
+
+/// Options read from `#[monkey_fn(...)]`.
+#[derive(Default)]
+pub struct Args {
+    name: Option<String>,
+}
+
+impl Args {
+    pub fn parse(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("name") {
+            let name: LitStr = meta.value()?.parse()?;
+            self.name = Some(name.value());
+            Ok(())
+        } else {
+            Err(meta.error("expected `name = \"...\"`"))
+        }
+    }
+}
+
+pub fn expand(args: Args, item: ItemFn) -> syn::Result<TokenStream> {
+    let sig = &item.sig;
+    if !sig.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &sig.generics,
+            "#[monkey_fn] functions cannot be generic",
+        ));
+    }
+    if let Some(asyncness) = &sig.asyncness {
+        return Err(syn::Error::new_spanned(
+            asyncness,
+            "#[monkey_fn] functions cannot be async",
+        ));
+    }
+    for input in &sig.inputs {
+        if let FnArg::Receiver(receiver) = input {
+            return Err(syn::Error::new_spanned(
+                receiver,
+                "#[monkey_fn] functions cannot take `self`",
+            ));
+        }
+    }
+    if sig.inputs.len() > MAX_ARGUMENTS {
+        return Err(syn::Error::new_spanned(
+            &sig.inputs,
+            format!("#[monkey_fn] functions take at most {MAX_ARGUMENTS} arguments"),
+        ));
+    }
+
+    let vis = &item.vis;
+    let id = &sig.ident;
+    let name = args.name.unwrap_or_else(|| id.to_string());
+    let constructor = format_ident!("{}_builtin", id);
+    let doc = doc_string(&item.attrs);
+    let constructor_doc = format!("The `{name}` builtin, calling [`{id}`].");
+
+    Ok(quote! {
+        #item
+
+        #[doc = #constructor_doc]
+        #vis fn #constructor() -> ::monkey::Builtin {
+            ::monkey::Builtin::from_fn(#name, #id).with_doc(#doc)
+        }
+    })
+}
+
+/// The `///` comments of the function, one line each with the leading
+/// space the comment syntax leaves behind removed.
+fn doc_string(attrs: &[syn::Attribute]) -> String {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(meta) => match &meta.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(doc), ..
+                }) => Some(doc.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .map(|line| {
+            line.strip_prefix(' ')
+                .unwrap_or(&line)
+                .trim_end()
+                .to_string()
+        })
+        .collect();
+    lines.join("\n").trim().to_string()
+}
@@ -0,0 +1,219 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_quote, Data, DeriveInput, Fields, FieldsNamed, Generics, Ident, LitStr, Path};
+
+/// Options read from `#[monkey(...)]` on a field or variant.
+#[derive(Default)]
+struct Attrs {
+    rename: Option<String>,
+    default: Option<Option<Path>>,
+}
+
+fn parse_attrs(attrs: &[syn::Attribute]) -> syn::Result<Attrs> {
+    let mut parsed = Attrs::default();
+    for attr in attrs {
+        if !attr.path().is_ident("monkey") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                let name: LitStr = meta.value()?.parse()?;
+                parsed.rename = Some(name.value());
+                Ok(())
+            } else if meta.path.is_ident("default") {
+                if meta.input.peek(syn::Token![=]) {
+                    let path: LitStr = meta.value()?.parse()?;
+                    parsed.default = Some(Some(path.parse()?));
+                } else {
+                    parsed.default = Some(None);
+                }
+                Ok(())
+            } else {
+                Err(meta.error("expected `rename = \"...\"` or `default`"))
+            }
+        })?;
+    }
+    Ok(parsed)
+}
+
+pub fn expand(ast: DeriveInput) -> syn::Result<TokenStream> {
+    let id = &ast.ident;
+    let (type_name, from_value, into_value) = match &ast.data {
+        Data::Struct(data) => {
+            let Fields::Named(fields) = &data.fields else {
+                return Err(syn::Error::new_spanned(
+                    id,
+                    "MonkeyValue can only be derived for structs with named fields",
+                ));
+            };
+            let (read, write, bindings) = named_fields(fields)?;
+            (
+                quote!("HASH".to_string()),
+                quote! {
+                    let ::monkey::Value::Hash(mut hash) = value else {
+                        return None;
+                    };
+                    Some(#id { #(#read)* })
+                },
+                quote! {
+                    let #id { #(#bindings),* } = self;
+                    let mut hash = ::std::collections::BTreeMap::new();
+                    #(#write)*
+                    ::monkey::Value::Hash(hash)
+                },
+            )
+        }
+        Data::Enum(data) => {
+            let mut from_string = Vec::new();
+            let mut from_hash = Vec::new();
+            let mut into_value = Vec::new();
+            for variant in &data.variants {
+                let variant_id = &variant.ident;
+                let name = parse_attrs(&variant.attrs)?
+                    .rename
+                    .unwrap_or_else(|| variant_id.to_string());
+                match &variant.fields {
+                    Fields::Unit => {
+                        from_string.push(quote!(#name => Some(#id::#variant_id),));
+                        into_value.push(quote! {
+                            #id::#variant_id => ::monkey::Value::String(#name.to_string()),
+                        });
+                    }
+                    Fields::Named(fields) => {
+                        let (read, write, bindings) = named_fields(fields)?;
+                        from_hash.push(quote!(#name => Some(#id::#variant_id { #(#read)* }),));
+                        into_value.push(quote! {
+                            #id::#variant_id { #(#bindings),* } => {
+                                let mut hash = ::std::collections::BTreeMap::new();
+                                hash.insert(
+                                    ::monkey::HashKey::String("type".to_string()),
+                                    ::monkey::Value::String(#name.to_string()),
+                                );
+                                #(#write)*
+                                ::monkey::Value::Hash(hash)
+                            }
+                        });
+                    }
+                    Fields::Unnamed(_) => {
+                        return Err(syn::Error::new_spanned(
+                            variant,
+                            "MonkeyValue does not support tuple variants",
+                        ))
+                    }
+                }
+            }
+            let type_name = match (from_string.is_empty(), from_hash.is_empty()) {
+                (false, true) => "STRING",
+                (true, false) => "HASH",
+                _ => "STRING or HASH",
+            };
+            (
+                quote!(#type_name.to_string()),
+                quote! {
+                    match value {
+                        ::monkey::Value::String(name) => match name.as_str() {
+                            #(#from_string)*
+                            _ => None,
+                        },
+                        ::monkey::Value::Hash(mut hash) => {
+                            let tag = hash.remove(&::monkey::HashKey::String("type".to_string()));
+                            let Some(::monkey::Value::String(tag)) = tag else {
+                                return None;
+                            };
+                            match tag.as_str() {
+                                #(#from_hash)*
+                                _ => None,
+                            }
+                        }
+                        _ => None,
+                    }
+                },
+                quote! {
+                    match self {
+                        #(#into_value)*
+                    }
+                },
+            )
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                id,
+                "MonkeyValue cannot be derived for unions",
+            ))
+        }
+    };
+
+    let from_generics = with_bound(&ast.generics, quote!(::monkey::FromValue));
+    let (impl_generics, ty_generics, where_clause) = from_generics.split_for_impl();
+    let into_generics = with_bound(&ast.generics, quote!(::monkey::IntoValue));
+    let (_, _, into_where_clause) = into_generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::monkey::FromValue for #id #ty_generics #where_clause {
+            fn type_name() -> String {
+                #type_name
+            }
+
+            #[allow(unused_mut)]
+            fn from_value(value: ::monkey::Value) -> Option<Self> {
+                #from_value
+            }
+        }
+
+        impl #impl_generics ::monkey::IntoValue for #id #ty_generics #into_where_clause {
+            fn into_value(self) -> ::monkey::Value {
+                #into_value
+            }
+        }
+    })
+}
+
+/// `generics` with `bound` on each type parameter, as the fields using
+/// them are converted through it.
+fn with_bound(generics: &Generics, bound: TokenStream) -> Generics {
+    let mut generics = generics.clone();
+    let params: Vec<Ident> = generics.type_params().map(|p| p.ident.clone()).collect();
+    let where_clause = generics.make_where_clause();
+    for param in params {
+        where_clause.predicates.push(parse_quote!(#param: #bound));
+    }
+    generics
+}
+
+/// Field initializers reading from `hash`, statements inserting the
+/// bound fields into `hash`, and the field bindings themselves.
+fn named_fields(
+    fields: &FieldsNamed,
+) -> syn::Result<(Vec<TokenStream>, Vec<TokenStream>, Vec<Ident>)> {
+    let mut read = Vec::new();
+    let mut write = Vec::new();
+    let mut bindings = Vec::new();
+    for field in &fields.named {
+        let field_id = field.ident.clone().unwrap();
+        let ty = &field.ty;
+        let attrs = parse_attrs(&field.attrs)?;
+        let key = attrs
+            .rename
+            .unwrap_or_else(|| field_id.to_string().trim_start_matches("r#").to_string());
+        // a missing key reads as `null`, which only an `Option` accepts,
+        // unless the field names a default
+        let missing = match attrs.default {
+            Some(Some(path)) => quote!(#path()),
+            Some(None) => quote!(::std::default::Default::default()),
+            None => quote!(<#ty as ::monkey::FromValue>::from_value(::monkey::Value::Null)?),
+        };
+        read.push(quote! {
+            #field_id: match hash.remove(&::monkey::HashKey::String(#key.to_string())) {
+                Some(value) => <#ty as ::monkey::FromValue>::from_value(value)?,
+                None => #missing,
+            },
+        });
+        write.push(quote! {
+            hash.insert(
+                ::monkey::HashKey::String(#key.to_string()),
+                ::monkey::IntoValue::into_value(#field_id),
+            );
+        });
+        bindings.push(field_id);
+    }
+    Ok((read, write, bindings))
+}
@@ -1,112 +1,508 @@
 use core::fmt;
 use monkey_macros::DefaultExpressionNode;
-use std::any::Any;
+use std::rc::Rc;
 
-use crate::Token;
+use crate::ast::node::Numbered;
+use crate::evaluator::limits::grow_stack;
+use crate::{BlockStatement, NodeId, Slot, Span, Statement, Token};
 
 pub trait Node {
     fn token_literal(&self) -> String;
+    /// Where the node's token sits in the source.
+    fn span(&self) -> Span;
 }
 
-pub trait Statement: Node + fmt::Display {
-    fn statement_node(&self);
-    fn as_any(&self) -> &dyn Any;
+/// Any expression. Each variant wraps the struct with the node's fields.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum Expression {
+    Identifier(Identifier),
+    Integer(IntegerLiteral),
+    #[cfg(feature = "bigint")]
+    BigInteger(BigIntegerLiteral),
+    String(StringLiteral),
+    Boolean(Boolean),
+    Prefix(PrefixExpression),
+    Infix(InfixExpression),
+    If(IfExpression),
+    Try(TryExpression),
+    Function(FunctionLiteral),
+    Call(CallExpression),
+    Array(ArrayLiteral),
+    Index(IndexExpression),
+    Hash(HashLiteral),
 }
 
-pub trait Expression: Node + fmt::Display {
-    fn expression_node(&self);
-    fn as_any(&self) -> &dyn Any;
+impl Expression {
+    pub fn id(&self) -> NodeId {
+        self.node().id()
+    }
+
+    pub(crate) fn id_mut(&mut self) -> &mut NodeId {
+        self.node_mut().id_mut()
+    }
+
+    fn node(&self) -> &dyn ExpressionNode {
+        match self {
+            Expression::Identifier(node) => node,
+            Expression::Integer(node) => node,
+            #[cfg(feature = "bigint")]
+            Expression::BigInteger(node) => node,
+            Expression::String(node) => node,
+            Expression::Boolean(node) => node,
+            Expression::Prefix(node) => node,
+            Expression::Infix(node) => node,
+            Expression::If(node) => node,
+            Expression::Try(node) => node,
+            Expression::Function(node) => node,
+            Expression::Call(node) => node,
+            Expression::Array(node) => node,
+            Expression::Index(node) => node,
+            Expression::Hash(node) => node,
+        }
+    }
+
+    fn node_mut(&mut self) -> &mut dyn ExpressionNode {
+        match self {
+            Expression::Identifier(node) => node,
+            Expression::Integer(node) => node,
+            #[cfg(feature = "bigint")]
+            Expression::BigInteger(node) => node,
+            Expression::String(node) => node,
+            Expression::Boolean(node) => node,
+            Expression::Prefix(node) => node,
+            Expression::Infix(node) => node,
+            Expression::If(node) => node,
+            Expression::Try(node) => node,
+            Expression::Function(node) => node,
+            Expression::Call(node) => node,
+            Expression::Array(node) => node,
+            Expression::Index(node) => node,
+            Expression::Hash(node) => node,
+        }
+    }
 }
 
+/// What every expression struct implements, so `Expression` can
+/// forward to whichever one it holds.
+trait ExpressionNode: Node + fmt::Display + Numbered {}
+
+impl<T: Node + fmt::Display + Numbered> ExpressionNode for T {}
+
+impl Node for Expression {
+    fn token_literal(&self) -> String {
+        self.node().token_literal()
+    }
+
+    fn span(&self) -> Span {
+        self.node().span()
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        grow_stack(|| fmt::Display::fmt(self.node(), f))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Program {
-    pub statements: Vec<Box<dyn Statement>>,
+    pub statements: Vec<Statement>,
+    /// The text the program was parsed from, which spans point into.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub source: Rc<str>,
+    /// The parent of each node, indexed by `NodeId`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) parents: Vec<Option<NodeId>>,
 }
 
 impl Node for Program {
     fn token_literal(&self) -> String {
-        if self.statements.len() > 0 {
+        if !self.statements.is_empty() {
             self.statements[0].token_literal()
         } else {
             "".to_string()
         }
     }
+
+    fn span(&self) -> Span {
+        match (self.statements.first(), self.statements.last()) {
+            (Some(first), Some(last)) => first.span().to(last.span()),
+            _ => Span::default(),
+        }
+    }
 }
 
 impl fmt::Display for Program {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for s in &self.statements {
-            write!(f, "{}", s.to_string())?;
+            write!(f, "{}", s)?;
         }
         Ok(())
     }
 }
 
-#[derive(DefaultExpressionNode, Debug, Clone)]
+#[derive(DefaultExpressionNode, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Identifier {
     pub token: Token,
+    pub id: NodeId,
     pub value: String,
+    /// Where the name's binding lives, once `annotate` has filled it in.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub slot: Option<Slot>,
+    /// The declared type of a `let` name or parameter, as in `x: int`.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub annotation: Option<TypeAnnotation>,
+}
+
+impl Identifier {
+    /// The name with its annotation, if it has one: `x: int`.
+    pub(crate) fn declaration(&self) -> String {
+        match &self.annotation {
+            Some(annotation) => format!("{}: {}", self.value, annotation),
+            None => self.value.clone(),
+        }
+    }
+}
+
+/// A type written out in the source, after a `let` name or parameter or
+/// a function's `->`. The parser takes any name; the type checker says
+/// which it knows.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TypeAnnotation {
+    pub kind: TypeAnnotationKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TypeAnnotationKind {
+    /// `int`, `string`, `bool`, `null` or `any`.
+    Named { name: String },
+    /// `[int]`
+    Array { element: Box<TypeAnnotation> },
+    /// `{string: int}`
+    Hash {
+        key: Box<TypeAnnotation>,
+        value: Box<TypeAnnotation>,
+    },
+    /// `fn(int, string) -> bool`
+    Function {
+        parameters: Vec<TypeAnnotation>,
+        return_type: Box<TypeAnnotation>,
+    },
+}
+
+impl fmt::Display for TypeAnnotation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            TypeAnnotationKind::Named { name } => write!(f, "{}", name),
+            TypeAnnotationKind::Array { element } => write!(f, "[{}]", element),
+            TypeAnnotationKind::Hash { key, value } => write!(f, "{{{}: {}}}", key, value),
+            TypeAnnotationKind::Function {
+                parameters,
+                return_type,
+            } => {
+                let parameters: Vec<String> = parameters.iter().map(|p| p.to_string()).collect();
+                write!(f, "fn({}) -> {}", parameters.join(", "), return_type)
+            }
+        }
+    }
 }
 
-#[derive(DefaultExpressionNode, Debug, Clone)]
+#[derive(DefaultExpressionNode, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct IntegerLiteral {
     pub token: Token,
+    pub id: NodeId,
     pub value: i64,
 }
 
-pub struct PrefixExpression {
+/// An integer literal too large for an `i64`, only produced with the
+/// `bigint` feature.
+#[cfg(feature = "bigint")]
+#[derive(DefaultExpressionNode, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BigIntegerLiteral {
     pub token: Token,
-    pub operator: String,
-    pub right: Option<Box<dyn Expression>>,
+    pub id: NodeId,
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "crate::ast::dump::as_string")
+    )]
+    pub value: num_bigint::BigInt,
 }
 
-impl Expression for PrefixExpression {
-    fn expression_node(&self) {}
-    fn as_any(&self) -> &dyn Any {
-        self
-    }
+#[derive(DefaultExpressionNode, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StringLiteral {
+    pub token: Token,
+    pub id: NodeId,
+    pub value: String,
+}
+
+#[derive(DefaultExpressionNode, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Boolean {
+    pub token: Token,
+    pub id: NodeId,
+    pub value: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PrefixExpression {
+    pub token: Token,
+    pub id: NodeId,
+    pub operator: String,
+    pub right: Box<Expression>,
 }
 
 impl Node for PrefixExpression {
     fn token_literal(&self) -> String {
         self.token.literal.to_string()
     }
+
+    fn span(&self) -> Span {
+        self.token.span
+    }
 }
 
 impl std::fmt::Display for PrefixExpression {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "({}{})", self.operator, self.right.as_ref().unwrap())
+        write!(f, "({}{})", self.operator, self.right)
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct InfixExpression {
     pub token: Token, // The operator token, e.g. +
-    pub left: Option<Box<dyn Expression>>,
+    pub id: NodeId,
+    pub left: Box<Expression>,
     pub operator: String,
-    pub right: Option<Box<dyn Expression>>,
+    pub right: Box<Expression>,
 }
 
-impl Expression for InfixExpression {
-    fn expression_node(&self) {}
+impl Node for InfixExpression {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
 
-    fn as_any(&self) -> &dyn Any {
-        self
+    fn span(&self) -> Span {
+        self.token.span
     }
 }
 
-impl Node for InfixExpression {
+impl fmt::Display for InfixExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({} {} {})", self.left, self.operator, self.right)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct IfExpression {
+    pub token: Token, // The 'if' token
+    pub id: NodeId,
+    pub condition: Box<Expression>,
+    pub consequence: BlockStatement,
+    pub alternative: Option<BlockStatement>,
+}
+
+impl Node for IfExpression {
     fn token_literal(&self) -> String {
         self.token.literal.to_string()
     }
+
+    fn span(&self) -> Span {
+        self.token.span
+    }
 }
 
-impl fmt::Display for InfixExpression {
+impl fmt::Display for IfExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "if{} {}", self.condition, self.consequence)?;
+        if let Some(alternative) = &self.alternative {
+            write!(f, "else {}", alternative)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TryExpression {
+    pub token: Token, // The 'try' token
+    pub id: NodeId,
+    pub body: BlockStatement,
+    /// Bound to the caught error inside `handler`.
+    pub parameter: Identifier,
+    pub handler: BlockStatement,
+}
+
+impl Node for TryExpression {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+
+    fn span(&self) -> Span {
+        self.token.span
+    }
+}
+
+impl fmt::Display for TryExpression {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "({} {} {})",
-            self.left.as_ref().unwrap(),
-            self.operator,
-            self.right.as_ref().unwrap()
+            "try {} catch ({}) {}",
+            self.body, self.parameter, self.handler
         )
     }
 }
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FunctionLiteral {
+    pub token: Token, // The 'fn' token
+    pub id: NodeId,
+    pub parameters: Vec<Identifier>,
+    /// The type after `->`, if declared.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub return_type: Option<TypeAnnotation>,
+    // shared with every function value created from this literal
+    pub body: Rc<BlockStatement>,
+}
+
+impl Node for FunctionLiteral {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+
+    fn span(&self) -> Span {
+        self.token.span
+    }
+}
+
+impl fmt::Display for FunctionLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let params: Vec<String> = self.parameters.iter().map(|p| p.declaration()).collect();
+        write!(f, "{}({})", self.token_literal(), params.join(", "))?;
+        if let Some(return_type) = &self.return_type {
+            write!(f, " -> {}", return_type)?;
+        }
+        write!(f, " {}", self.body)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CallExpression {
+    pub token: Token, // The '(' token
+    pub id: NodeId,
+    pub function: Box<Expression>, // Identifier or FunctionLiteral
+    pub arguments: Vec<Expression>,
+}
+
+impl Node for CallExpression {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+
+    /// From the callee through the opening parenthesis, so errors point
+    /// at what was called.
+    fn span(&self) -> Span {
+        self.function.span().to(self.token.span)
+    }
+}
+
+impl fmt::Display for CallExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let args: Vec<String> = self.arguments.iter().map(|a| a.to_string()).collect();
+        write!(f, "{}({})", self.function, args.join(", "))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ArrayLiteral {
+    pub token: Token, // the '[' token
+    pub id: NodeId,
+    pub elements: Vec<Expression>,
+}
+
+impl Node for ArrayLiteral {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+
+    fn span(&self) -> Span {
+        self.token.span
+    }
+}
+
+impl fmt::Display for ArrayLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let elements: Vec<String> = self.elements.iter().map(|e| e.to_string()).collect();
+        write!(f, "[{}]", elements.join(", "))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct IndexExpression {
+    pub token: Token, // the '[' token
+    pub id: NodeId,
+    pub left: Box<Expression>,
+    pub index: Box<Expression>,
+}
+
+impl Node for IndexExpression {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+
+    fn span(&self) -> Span {
+        self.token.span
+    }
+}
+
+impl fmt::Display for IndexExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}[{}])", self.left, self.index)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct HashLiteral {
+    pub token: Token, // the '{' token
+    pub id: NodeId,
+    pub pairs: Vec<(Expression, Expression)>,
+}
+
+impl Node for HashLiteral {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+
+    fn span(&self) -> Span {
+        self.token.span
+    }
+}
+
+impl fmt::Display for HashLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pairs: Vec<String> = self
+            .pairs
+            .iter()
+            .map(|(key, value)| format!("{}:{}", key, value))
+            .collect();
+        write!(f, "{{{}}}", pairs.join(", "))
+    }
+}
@@ -0,0 +1,284 @@
+//! Parse trees for other tools to read: a Lisp-style S-expression that
+//! is easy to eyeball and diff, and, with the `serde` feature, JSON with
+//! the kind, id, token and span of every node.
+
+use crate::evaluator::limits::grow_stack;
+use crate::{BlockStatement, Expression, Program, Statement};
+#[cfg(feature = "serde")]
+use crate::{Identifier, Lexer, NodeId, NodeRef, Span, TokenType};
+use std::fmt;
+
+/// Lists longer than this are broken over several lines.
+const WIDTH: usize = 80;
+
+enum Sexp {
+    Atom(String),
+    List(Vec<Sexp>),
+}
+
+impl Sexp {
+    fn atom(atom: impl fmt::Display) -> Sexp {
+        Sexp::Atom(atom.to_string())
+    }
+
+    fn list(head: &str, items: impl IntoIterator<Item = Sexp>) -> Sexp {
+        let mut list = vec![Sexp::atom(head)];
+        list.extend(items);
+        Sexp::List(list)
+    }
+
+    /// Write the expression on one line if it fits. Otherwise the head
+    /// and any atoms right after it, such as a `let` name, stay on the
+    /// first line and every other item goes on its own line, indented.
+    fn write(&self, out: &mut String, indent: usize) {
+        let items = match self {
+            Sexp::List(items) if self.width(WIDTH.saturating_sub(indent)).is_none() => items,
+            _ => return out.push_str(&self.to_string()),
+        };
+        out.push('(');
+        items[0].write(out, indent + 1);
+        let mut rest = items[1..].iter().peekable();
+        while let Some(atom) = rest.next_if(|item| matches!(item, Sexp::Atom(_))) {
+            out.push(' ');
+            out.push_str(&atom.to_string());
+        }
+        for item in rest {
+            out.push('\n');
+            out.push_str(&" ".repeat(indent + 2));
+            grow_stack(|| item.write(out, indent + 2));
+        }
+        out.push(')');
+    }
+
+    /// How wide the expression is on one line, if that's at most `room`.
+    /// Measuring stops as soon as it's too wide, so it doesn't cost more
+    /// the bigger the expression is.
+    fn width(&self, room: usize) -> Option<usize> {
+        let width = match self {
+            Sexp::Atom(atom) => atom.len(),
+            Sexp::List(items) => {
+                // the parentheses and the spaces between items
+                let mut width = items.len() + 1;
+                for item in items {
+                    width += item.width(room.checked_sub(width)?)?;
+                }
+                width
+            }
+        };
+        (width <= room).then_some(width)
+    }
+}
+
+impl fmt::Display for Sexp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Sexp::Atom(atom) => write!(f, "{}", atom),
+            Sexp::List(items) => {
+                write!(f, "(")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    grow_stack(|| item.fmt(f))?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+fn statement(stmt: &Statement) -> Sexp {
+    match stmt {
+        Statement::Let(stmt) => Sexp::list(
+            "let",
+            [Sexp::atom(stmt.name.declaration()), expression(&stmt.value)],
+        ),
+        Statement::Return(stmt) => Sexp::list("return", [expression(&stmt.return_value)]),
+        Statement::Throw(stmt) => Sexp::list("throw", [expression(&stmt.value)]),
+        Statement::Expression(stmt) => expression(&stmt.expression),
+        Statement::Block(block) => block_statement(block),
+    }
+}
+
+fn block_statement(block: &BlockStatement) -> Sexp {
+    Sexp::list("block", block.statements.iter().map(statement))
+}
+
+fn expression(exp: &Expression) -> Sexp {
+    grow_stack(|| match exp {
+        Expression::Identifier(ident) => Sexp::atom(&ident.value),
+        Expression::Integer(lit) => Sexp::atom(lit.value),
+        #[cfg(feature = "bigint")]
+        Expression::BigInteger(lit) => Sexp::atom(&lit.value),
+        Expression::String(lit) => Sexp::atom(format!("{:?}", lit.value)),
+        Expression::Boolean(lit) => Sexp::atom(lit.value),
+        Expression::Prefix(exp) => Sexp::list(&exp.operator, [expression(&exp.right)]),
+        Expression::Infix(exp) => Sexp::list(
+            &exp.operator,
+            [expression(&exp.left), expression(&exp.right)],
+        ),
+        Expression::If(exp) => {
+            let mut items = vec![
+                expression(&exp.condition),
+                block_statement(&exp.consequence),
+            ];
+            items.extend(exp.alternative.as_ref().map(block_statement));
+            Sexp::list("if", items)
+        }
+        Expression::Try(exp) => Sexp::list(
+            "try",
+            [
+                block_statement(&exp.body),
+                Sexp::list(
+                    "catch",
+                    [
+                        Sexp::atom(&exp.parameter.value),
+                        block_statement(&exp.handler),
+                    ],
+                ),
+            ],
+        ),
+        Expression::Function(func) => {
+            let params = func.parameters.iter().map(|p| Sexp::atom(p.declaration()));
+            let mut items = vec![Sexp::List(params.collect())];
+            if let Some(return_type) = &func.return_type {
+                items.extend([Sexp::atom("->"), Sexp::atom(return_type)]);
+            }
+            items.push(block_statement(&func.body));
+            Sexp::list("fn", items)
+        }
+        Expression::Call(call) => Sexp::list(
+            "call",
+            std::iter::once(expression(&call.function))
+                .chain(call.arguments.iter().map(expression)),
+        ),
+        Expression::Array(array) => Sexp::list("array", array.elements.iter().map(expression)),
+        Expression::Index(exp) => {
+            Sexp::list("index", [expression(&exp.left), expression(&exp.index)])
+        }
+        Expression::Hash(hash) => Sexp::list(
+            "hash",
+            hash.pairs
+                .iter()
+                .map(|(key, value)| Sexp::List(vec![expression(key), expression(value)])),
+        ),
+    })
+}
+
+impl Program {
+    /// The program as an S-expression, e.g. `(program (let x (+ 1 2)))`.
+    pub fn to_sexp(&self) -> String {
+        let mut out = String::new();
+        Sexp::list("program", self.statements.iter().map(statement)).write(&mut out, 0);
+        out
+    }
+
+    /// The program as pretty-printed JSON. Every node is an object whose
+    /// `kind` says what it is, whose `token` carries the span of the token
+    /// it's named by, and whose `span` covers all of it, from its first
+    /// token to its last, closing bracket or semicolon included.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        let mut json = serde_json::to_value(self).expect("the AST always serializes");
+        let spans = node_spans(self);
+        let mut pending = vec![&mut json];
+        while let Some(value) = pending.pop() {
+            match value {
+                serde_json::Value::Object(object) => {
+                    let id = object.get("id").and_then(serde_json::Value::as_u64);
+                    if let Some(&Some(span)) = id.and_then(|id| spans.get(id as usize)) {
+                        let span = serde_json::to_value(span).expect("spans always serialize");
+                        object.insert("span".to_string(), span);
+                    }
+                    pending.extend(object.values_mut());
+                }
+                serde_json::Value::Array(items) => pending.extend(items.iter_mut()),
+                _ => {}
+            }
+        }
+        serde_json::to_string_pretty(&json).expect("the AST always serializes")
+    }
+}
+
+/// The span of every node, indexed by id. The AST doesn't keep closing
+/// brackets, semicolons or the parentheses around a grouped operand, so
+/// they are found again among the tokens of the source.
+#[cfg(feature = "serde")]
+fn node_spans(program: &Program) -> Vec<Option<Span>> {
+    let mut lexer = Lexer::new(program.source.to_string());
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next_token();
+        if token.r#type == TokenType::EOF {
+            break;
+        }
+        tokens.push(token);
+    }
+    // the first token starting at or after `offset`
+    let next = |offset: usize| {
+        let i = tokens.partition_point(|token| token.span.start < offset);
+        tokens.get(i).map(|token| (i, token))
+    };
+
+    let index = program.index();
+    let mut spans: Vec<Option<Span>> = vec![None; program.node_count()];
+    // children are numbered after their parent, so they come first here
+    for id in (0..program.node_count()).rev() {
+        let Some(node) = index.get(NodeId(id as u32)) else {
+            continue;
+        };
+        let mut span = node.span();
+        if let NodeRef::Identifier(Identifier {
+            annotation: Some(annotation),
+            ..
+        }) = node
+        {
+            span = span.to(annotation.span);
+        }
+        for child in node.children() {
+            let Some(mut child) = spans.get(child.id().index()).copied().flatten() else {
+                continue;
+            };
+            // take in the parentheses around a grouped operand
+            while let (Some(before), Some((_, after))) = (
+                next(child.start)
+                    .and_then(|(i, _)| i.checked_sub(1))
+                    .map(|i| &tokens[i]),
+                next(child.end),
+            ) {
+                if before.r#type != TokenType::LPAREN || after.r#type != TokenType::RPAREN {
+                    break;
+                }
+                child = before.span.to(after.span);
+            }
+            span = span.to(child);
+        }
+        let closing = match node {
+            NodeRef::Expression(Expression::Call(_)) => Some(TokenType::RPAREN),
+            NodeRef::Expression(Expression::Index(_) | Expression::Array(_)) => {
+                Some(TokenType::RBRACKET)
+            }
+            NodeRef::Expression(Expression::Hash(_))
+            | NodeRef::Block(_)
+            | NodeRef::Statement(Statement::Block(_)) => Some(TokenType::RBRACE),
+            NodeRef::Statement(_) => Some(TokenType::SEMICOLON),
+            _ => None,
+        };
+        if let Some((_, token)) = next(span.end) {
+            if Some(token.r#type) == closing {
+                span = span.to(token.span);
+            }
+        }
+        spans[id] = Some(span);
+    }
+    spans
+}
+
+/// Serializes big integers as strings, since JSON numbers can't hold them.
+#[cfg(all(feature = "serde", feature = "bigint"))]
+pub(crate) fn as_string<T: fmt::Display, S: serde::Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
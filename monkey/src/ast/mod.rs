@@ -1,5 +1,12 @@
 pub mod ast;
+pub mod dump;
+pub mod node;
 pub mod statement;
+pub mod visit;
+pub mod visit_mut;
 
 pub use ast::*;
+pub use node::*;
 pub use statement::*;
+pub use visit::*;
+pub use visit_mut::*;
@@ -0,0 +1,328 @@
+use crate::{
+    walk_block_statement, walk_block_statement_mut, walk_expression, walk_expression_mut,
+    walk_statement, walk_statement_mut, ArrayLiteral, BlockStatement, Boolean, CallExpression,
+    Expression, ExpressionStatement, FunctionLiteral, HashLiteral, Identifier, IfExpression,
+    IndexExpression, InfixExpression, IntegerLiteral, LetStatement, Node, PrefixExpression,
+    Program, ReturnStatement, Span, Statement, StringLiteral, ThrowStatement, TryExpression,
+    Visitor, VisitorMut,
+};
+use std::fmt;
+use std::rc::Rc;
+
+/// Identifies a node within its `Program`, so analysis passes can keep
+/// what they find in side tables such as `HashMap<NodeId, T>`.
+///
+/// The tree is not stored in an arena: nodes still own their children,
+/// and each carries its id in a field. Nodes are numbered in preorder
+/// when the program is built, so the same source always gets the same
+/// ids, and the program keeps one table beside the tree, the parent of
+/// each id. Looking a node up by id walks the tree, see `Program::node`
+/// and `Program::index`.
+///
+/// Ids go stale when a `VisitorMut` pass adds, removes or moves nodes:
+/// new nodes have whatever id they were built with, usually `DUMMY`, and
+/// the ids and parent links of the rest describe the old tree until
+/// `Program::renumber` is called.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct NodeId(pub u32);
+
+impl NodeId {
+    /// The id of a node not numbered yet.
+    pub const DUMMY: NodeId = NodeId(u32::MAX);
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// Implemented by every node struct, giving `Statement` and `Expression`
+/// access to the id of whichever node they hold.
+pub(crate) trait Numbered {
+    fn id(&self) -> NodeId;
+    fn id_mut(&mut self) -> &mut NodeId;
+}
+
+macro_rules! numbered {
+    ($($node:ty),* $(,)?) => {
+        $(
+            impl Numbered for $node {
+                fn id(&self) -> NodeId {
+                    self.id
+                }
+
+                fn id_mut(&mut self) -> &mut NodeId {
+                    &mut self.id
+                }
+            }
+        )*
+    };
+}
+
+numbered!(
+    Identifier,
+    IntegerLiteral,
+    StringLiteral,
+    Boolean,
+    PrefixExpression,
+    InfixExpression,
+    IfExpression,
+    TryExpression,
+    FunctionLiteral,
+    CallExpression,
+    ArrayLiteral,
+    IndexExpression,
+    HashLiteral,
+    LetStatement,
+    ReturnStatement,
+    ThrowStatement,
+    ExpressionStatement,
+    BlockStatement,
+);
+
+#[cfg(feature = "bigint")]
+numbered!(crate::BigIntegerLiteral);
+
+/// A borrowed node of any kind, as returned by `Program::node`.
+///
+/// An identifier used as an expression is an `Expression`; `Identifier`
+/// is only for the places a name is bound: `let` names, parameters and
+/// `catch` parameters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NodeRef<'a> {
+    Statement(&'a Statement),
+    Block(&'a BlockStatement),
+    Expression(&'a Expression),
+    Identifier(&'a Identifier),
+}
+
+impl<'a> NodeRef<'a> {
+    pub fn id(&self) -> NodeId {
+        match self {
+            NodeRef::Statement(stmt) => stmt.id(),
+            NodeRef::Block(block) => block.id,
+            NodeRef::Expression(exp) => exp.id(),
+            NodeRef::Identifier(ident) => ident.id,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            NodeRef::Statement(stmt) => stmt.span(),
+            NodeRef::Block(block) => block.span(),
+            NodeRef::Expression(exp) => exp.span(),
+            NodeRef::Identifier(ident) => ident.span(),
+        }
+    }
+
+    /// The span from the start of the node's first token to the end of
+    /// its last, unlike `span`, which covers only the token the node is
+    /// named by. Closing brackets and braces are left out.
+    pub fn extent(&self) -> Span {
+        let mut span = self.span();
+        let mut pending = self.children();
+        while let Some(node) = pending.pop() {
+            span = span.to(node.span());
+            pending.extend(node.children());
+        }
+        span
+    }
+
+    /// The nodes directly below this one, in source order.
+    pub fn children(&self) -> Vec<NodeRef<'a>> {
+        let mut children = Children(Vec::new());
+        match *self {
+            NodeRef::Statement(Statement::Block(block)) | NodeRef::Block(block) => {
+                walk_block_statement(&mut children, block)
+            }
+            NodeRef::Statement(stmt) => walk_statement(&mut children, stmt),
+            NodeRef::Expression(Expression::Identifier(_)) | NodeRef::Identifier(_) => {}
+            NodeRef::Expression(exp) => walk_expression(&mut children, exp),
+        }
+        children.0
+    }
+}
+
+impl fmt::Display for NodeRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NodeRef::Statement(stmt) => write!(f, "{}", stmt),
+            NodeRef::Block(block) => write!(f, "{}", block),
+            NodeRef::Expression(exp) => write!(f, "{}", exp),
+            NodeRef::Identifier(ident) => write!(f, "{}", ident),
+        }
+    }
+}
+
+/// Collects the nodes one level down without descending further.
+struct Children<'a>(Vec<NodeRef<'a>>);
+
+impl<'a> Visitor<'a> for Children<'a> {
+    fn visit_statement(&mut self, stmt: &'a Statement) {
+        self.0.push(NodeRef::Statement(stmt));
+    }
+
+    fn visit_block_statement(&mut self, block: &'a BlockStatement) {
+        self.0.push(NodeRef::Block(block));
+    }
+
+    fn visit_expression(&mut self, exp: &'a Expression) {
+        self.0.push(NodeRef::Expression(exp));
+    }
+
+    fn visit_identifier(&mut self, ident: &'a Identifier) {
+        self.0.push(NodeRef::Identifier(ident));
+    }
+}
+
+/// Hands out ids in preorder and records each node's parent.
+#[derive(Default)]
+struct Numbering {
+    parents: Vec<Option<NodeId>>,
+    stack: Vec<NodeId>,
+}
+
+impl Numbering {
+    fn enter(&mut self, id: &mut NodeId) {
+        *id = NodeId(self.parents.len() as u32);
+        self.parents.push(self.stack.last().copied());
+        self.stack.push(*id);
+    }
+
+    fn leave(&mut self) {
+        self.stack.pop();
+    }
+}
+
+impl VisitorMut for Numbering {
+    fn visit_statement(&mut self, stmt: &mut Statement) {
+        // a block statement is numbered as a block
+        if let Statement::Block(_) = stmt {
+            return walk_statement_mut(self, stmt);
+        }
+        self.enter(stmt.id_mut());
+        walk_statement_mut(self, stmt);
+        self.leave();
+    }
+
+    fn visit_block_statement(&mut self, block: &mut BlockStatement) {
+        self.enter(&mut block.id);
+        walk_block_statement_mut(self, block);
+        self.leave();
+    }
+
+    fn visit_expression(&mut self, exp: &mut Expression) {
+        // an identifier is numbered as an identifier
+        if let Expression::Identifier(_) = exp {
+            return walk_expression_mut(self, exp);
+        }
+        self.enter(exp.id_mut());
+        walk_expression_mut(self, exp);
+        self.leave();
+    }
+
+    fn visit_identifier(&mut self, ident: &mut Identifier) {
+        self.enter(&mut ident.id);
+        self.leave();
+    }
+}
+
+impl Program {
+    /// Build a program and number its nodes.
+    pub fn new(statements: Vec<Statement>, source: Rc<str>) -> Program {
+        let mut program = Program {
+            statements,
+            source,
+            parents: Vec::new(),
+        };
+        program.renumber();
+        program
+    }
+
+    /// Number the nodes again, after a pass added, removed or moved some.
+    /// Nodes may get different ids than before, so side tables keyed by
+    /// the old ones, such as a `Resolution`, have to be built again.
+    pub fn renumber(&mut self) {
+        let mut numbering = Numbering::default();
+        for stmt in &mut self.statements {
+            numbering.visit_statement(stmt);
+        }
+        self.parents = numbering.parents;
+    }
+
+    /// How many nodes the program has; ids run from 0 to one less.
+    pub fn node_count(&self) -> usize {
+        self.parents.len()
+    }
+
+    /// The node directly containing `id`, or `None` for top-level
+    /// statements.
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.parents.get(id.index()).copied().flatten()
+    }
+
+    /// Look up a node by id. Follows the parent links up to the top
+    /// level and back down, searching the children of each node on the
+    /// way, so it suits the odd lookup; `index` is for looking up many.
+    pub fn node(&self, id: NodeId) -> Option<NodeRef<'_>> {
+        if id.index() >= self.parents.len() {
+            return None;
+        }
+        let mut path = vec![id];
+        while let Some(parent) = self.parent(*path.last().unwrap()) {
+            path.push(parent);
+        }
+        let mut ids = path.into_iter().rev();
+        let top = ids.next()?;
+        let mut node = self
+            .statements
+            .iter()
+            .map(NodeRef::Statement)
+            .find(|node| node.id() == top)?;
+        for id in ids {
+            node = node.children().into_iter().find(|node| node.id() == id)?;
+        }
+        Some(node)
+    }
+
+    /// Every node of the program by id. Building it visits each node once,
+    /// after which a lookup is a single index.
+    pub fn index(&self) -> NodeIndex<'_> {
+        let mut nodes = Vec::with_capacity(self.node_count());
+        let mut stack: Vec<NodeRef> = self
+            .statements
+            .iter()
+            .rev()
+            .map(NodeRef::Statement)
+            .collect();
+        while let Some(node) = stack.pop() {
+            stack.extend(node.children().into_iter().rev());
+            nodes.push(node);
+        }
+        NodeIndex { nodes }
+    }
+}
+
+/// The nodes of a program in preorder, which is the order they are
+/// numbered in, as returned by `Program::index`.
+#[derive(Debug, Clone)]
+pub struct NodeIndex<'a> {
+    nodes: Vec<NodeRef<'a>>,
+}
+
+impl<'a> NodeIndex<'a> {
+    /// The node numbered `id`, found the same way as by `Program::node`.
+    pub fn get(&self, id: NodeId) -> Option<NodeRef<'a>> {
+        // a tree changed since it was numbered may not match its ids
+        self.nodes
+            .get(id.index())
+            .copied()
+            .filter(|node| node.id() == id)
+    }
+}
@@ -1,57 +1,146 @@
-use crate::{Expression, Identifier, Node, Statement, Token};
+use crate::ast::node::Numbered;
+use crate::{Expression, Identifier, Node, NodeId, Span, Token};
 use monkey_macros::DefaultStatementNode;
+use std::fmt;
+
+/// Any statement. Each variant wraps the struct with the node's fields.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum Statement {
+    Let(LetStatement),
+    Return(ReturnStatement),
+    Throw(ThrowStatement),
+    Expression(ExpressionStatement),
+    Block(BlockStatement),
+}
+
+impl Statement {
+    pub fn id(&self) -> NodeId {
+        self.node().id()
+    }
+
+    pub(crate) fn id_mut(&mut self) -> &mut NodeId {
+        self.node_mut().id_mut()
+    }
+
+    fn node(&self) -> &dyn StatementNode {
+        match self {
+            Statement::Let(node) => node,
+            Statement::Return(node) => node,
+            Statement::Throw(node) => node,
+            Statement::Expression(node) => node,
+            Statement::Block(node) => node,
+        }
+    }
+
+    fn node_mut(&mut self) -> &mut dyn StatementNode {
+        match self {
+            Statement::Let(node) => node,
+            Statement::Return(node) => node,
+            Statement::Throw(node) => node,
+            Statement::Expression(node) => node,
+            Statement::Block(node) => node,
+        }
+    }
+}
+
+trait StatementNode: Node + fmt::Display + Numbered {}
+
+impl<T: Node + fmt::Display + Numbered> StatementNode for T {}
+
+impl Node for Statement {
+    fn token_literal(&self) -> String {
+        self.node().token_literal()
+    }
+
+    fn span(&self) -> Span {
+        self.node().span()
+    }
+}
 
-#[derive(DefaultStatementNode)]
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self.node(), f)
+    }
+}
+
+#[derive(DefaultStatementNode, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct LetStatement {
     pub token: Token,
-    pub name: Option<Identifier>,
-    pub value: Option<Box<dyn Expression>>,
+    pub id: NodeId,
+    pub name: Identifier,
+    pub value: Expression,
 }
 
-#[derive(DefaultStatementNode)]
+#[derive(DefaultStatementNode, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ReturnStatement {
     pub token: Token,
-    pub return_value: Option<Box<dyn Expression>>,
+    pub id: NodeId,
+    pub return_value: Expression,
+}
+
+#[derive(DefaultStatementNode, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ThrowStatement {
+    pub token: Token,
+    pub id: NodeId,
+    pub value: Expression,
 }
 
-#[derive(DefaultStatementNode)]
+#[derive(DefaultStatementNode, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ExpressionStatement {
     pub token: Token,
-    pub expression: Option<Box<dyn Expression>>,
+    pub id: NodeId,
+    pub expression: Expression,
 }
 
-use std::fmt;
+#[derive(DefaultStatementNode, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BlockStatement {
+    pub token: Token, // the { token
+    pub id: NodeId,
+    pub statements: Vec<Statement>,
+}
 
 impl fmt::Display for LetStatement {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{} ", self.token_literal())?;
-        if let Some(ident) = &self.name {
-            write!(f, "{}", ident.value)?;
-        }
-        write!(f, " = ")?;
-        if let Some(value) = &self.value {
-            write!(f, "{}", value.to_string())?;
-        }
-        write!(f, ";")
+        write!(
+            f,
+            "{} {} = {};",
+            self.token_literal(),
+            self.name.declaration(),
+            self.value
+        )
     }
 }
 
 impl fmt::Display for ReturnStatement {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{} ", self.token_literal())?;
-        if let Some(return_value) = &self.return_value {
-            write!(f, "{}", return_value.to_string())?;
-        }
-        write!(f, ";")
+        write!(f, "{} {};", self.token_literal(), self.return_value)
+    }
+}
+
+impl fmt::Display for ThrowStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {};", self.token_literal(), self.value)
     }
 }
 
 impl fmt::Display for ExpressionStatement {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if let Some(expression) = &self.expression {
-            write!(f, "{}", expression.to_string())
-        } else {
-            Ok(())
+        write!(f, "{}", self.expression)
+    }
+}
+
+impl fmt::Display for BlockStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for s in &self.statements {
+            write!(f, "{}", s)?;
         }
+        Ok(())
     }
 }
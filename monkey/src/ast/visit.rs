@@ -0,0 +1,217 @@
+//! Read-only traversal of the AST.
+//!
+//! Implement `Visitor`, override the methods for the nodes you care
+//! about, and call the matching `walk_*` function from an override to
+//! keep descending into its children. Identifiers are visited both where
+//! they are bound (`let` names, parameters) and where they are used.
+
+use crate::evaluator::limits::grow_stack;
+#[cfg(feature = "bigint")]
+use crate::BigIntegerLiteral;
+use crate::{
+    ArrayLiteral, BlockStatement, Boolean, CallExpression, Expression, ExpressionStatement,
+    FunctionLiteral, HashLiteral, Identifier, IfExpression, IndexExpression, InfixExpression,
+    IntegerLiteral, LetStatement, PrefixExpression, Program, ReturnStatement, Statement,
+    StringLiteral, ThrowStatement, TryExpression,
+};
+
+pub trait Visitor<'ast>: Sized {
+    fn visit_program(&mut self, program: &'ast Program) {
+        walk_program(self, program)
+    }
+
+    fn visit_statement(&mut self, stmt: &'ast Statement) {
+        walk_statement(self, stmt)
+    }
+
+    fn visit_let_statement(&mut self, stmt: &'ast LetStatement) {
+        walk_let_statement(self, stmt)
+    }
+
+    fn visit_return_statement(&mut self, stmt: &'ast ReturnStatement) {
+        walk_return_statement(self, stmt)
+    }
+
+    fn visit_throw_statement(&mut self, stmt: &'ast ThrowStatement) {
+        walk_throw_statement(self, stmt)
+    }
+
+    fn visit_expression_statement(&mut self, stmt: &'ast ExpressionStatement) {
+        walk_expression_statement(self, stmt)
+    }
+
+    fn visit_block_statement(&mut self, block: &'ast BlockStatement) {
+        walk_block_statement(self, block)
+    }
+
+    fn visit_expression(&mut self, exp: &'ast Expression) {
+        walk_expression(self, exp)
+    }
+
+    fn visit_identifier(&mut self, _ident: &'ast Identifier) {}
+
+    fn visit_integer_literal(&mut self, _lit: &'ast IntegerLiteral) {}
+
+    #[cfg(feature = "bigint")]
+    fn visit_big_integer_literal(&mut self, _lit: &'ast BigIntegerLiteral) {}
+
+    fn visit_string_literal(&mut self, _lit: &'ast StringLiteral) {}
+
+    fn visit_boolean(&mut self, _lit: &'ast Boolean) {}
+
+    fn visit_prefix_expression(&mut self, exp: &'ast PrefixExpression) {
+        walk_prefix_expression(self, exp)
+    }
+
+    fn visit_infix_expression(&mut self, exp: &'ast InfixExpression) {
+        walk_infix_expression(self, exp)
+    }
+
+    fn visit_if_expression(&mut self, exp: &'ast IfExpression) {
+        walk_if_expression(self, exp)
+    }
+
+    fn visit_try_expression(&mut self, exp: &'ast TryExpression) {
+        walk_try_expression(self, exp)
+    }
+
+    fn visit_function_literal(&mut self, func: &'ast FunctionLiteral) {
+        walk_function_literal(self, func)
+    }
+
+    fn visit_call_expression(&mut self, call: &'ast CallExpression) {
+        walk_call_expression(self, call)
+    }
+
+    fn visit_array_literal(&mut self, array: &'ast ArrayLiteral) {
+        walk_array_literal(self, array)
+    }
+
+    fn visit_index_expression(&mut self, exp: &'ast IndexExpression) {
+        walk_index_expression(self, exp)
+    }
+
+    fn visit_hash_literal(&mut self, hash: &'ast HashLiteral) {
+        walk_hash_literal(self, hash)
+    }
+}
+
+pub fn walk_program<'ast, V: Visitor<'ast>>(visitor: &mut V, program: &'ast Program) {
+    for stmt in &program.statements {
+        visitor.visit_statement(stmt);
+    }
+}
+
+pub fn walk_statement<'ast, V: Visitor<'ast>>(visitor: &mut V, stmt: &'ast Statement) {
+    match stmt {
+        Statement::Let(stmt) => visitor.visit_let_statement(stmt),
+        Statement::Return(stmt) => visitor.visit_return_statement(stmt),
+        Statement::Throw(stmt) => visitor.visit_throw_statement(stmt),
+        Statement::Expression(stmt) => visitor.visit_expression_statement(stmt),
+        Statement::Block(block) => visitor.visit_block_statement(block),
+    }
+}
+
+pub fn walk_let_statement<'ast, V: Visitor<'ast>>(visitor: &mut V, stmt: &'ast LetStatement) {
+    visitor.visit_identifier(&stmt.name);
+    visitor.visit_expression(&stmt.value);
+}
+
+pub fn walk_return_statement<'ast, V: Visitor<'ast>>(visitor: &mut V, stmt: &'ast ReturnStatement) {
+    visitor.visit_expression(&stmt.return_value);
+}
+
+pub fn walk_throw_statement<'ast, V: Visitor<'ast>>(visitor: &mut V, stmt: &'ast ThrowStatement) {
+    visitor.visit_expression(&stmt.value);
+}
+
+pub fn walk_expression_statement<'ast, V: Visitor<'ast>>(
+    visitor: &mut V,
+    stmt: &'ast ExpressionStatement,
+) {
+    visitor.visit_expression(&stmt.expression);
+}
+
+pub fn walk_block_statement<'ast, V: Visitor<'ast>>(visitor: &mut V, block: &'ast BlockStatement) {
+    for stmt in &block.statements {
+        visitor.visit_statement(stmt);
+    }
+}
+
+pub fn walk_expression<'ast, V: Visitor<'ast>>(visitor: &mut V, exp: &'ast Expression) {
+    grow_stack(|| match exp {
+        Expression::Identifier(ident) => visitor.visit_identifier(ident),
+        Expression::Integer(lit) => visitor.visit_integer_literal(lit),
+        #[cfg(feature = "bigint")]
+        Expression::BigInteger(lit) => visitor.visit_big_integer_literal(lit),
+        Expression::String(lit) => visitor.visit_string_literal(lit),
+        Expression::Boolean(lit) => visitor.visit_boolean(lit),
+        Expression::Prefix(exp) => visitor.visit_prefix_expression(exp),
+        Expression::Infix(exp) => visitor.visit_infix_expression(exp),
+        Expression::If(exp) => visitor.visit_if_expression(exp),
+        Expression::Try(exp) => visitor.visit_try_expression(exp),
+        Expression::Function(func) => visitor.visit_function_literal(func),
+        Expression::Call(call) => visitor.visit_call_expression(call),
+        Expression::Array(array) => visitor.visit_array_literal(array),
+        Expression::Index(exp) => visitor.visit_index_expression(exp),
+        Expression::Hash(hash) => visitor.visit_hash_literal(hash),
+    })
+}
+
+pub fn walk_prefix_expression<'ast, V: Visitor<'ast>>(
+    visitor: &mut V,
+    exp: &'ast PrefixExpression,
+) {
+    visitor.visit_expression(&exp.right);
+}
+
+pub fn walk_infix_expression<'ast, V: Visitor<'ast>>(visitor: &mut V, exp: &'ast InfixExpression) {
+    visitor.visit_expression(&exp.left);
+    visitor.visit_expression(&exp.right);
+}
+
+pub fn walk_if_expression<'ast, V: Visitor<'ast>>(visitor: &mut V, exp: &'ast IfExpression) {
+    visitor.visit_expression(&exp.condition);
+    visitor.visit_block_statement(&exp.consequence);
+    if let Some(alternative) = &exp.alternative {
+        visitor.visit_block_statement(alternative);
+    }
+}
+
+pub fn walk_try_expression<'ast, V: Visitor<'ast>>(visitor: &mut V, exp: &'ast TryExpression) {
+    visitor.visit_block_statement(&exp.body);
+    visitor.visit_identifier(&exp.parameter);
+    visitor.visit_block_statement(&exp.handler);
+}
+
+pub fn walk_function_literal<'ast, V: Visitor<'ast>>(visitor: &mut V, func: &'ast FunctionLiteral) {
+    for param in &func.parameters {
+        visitor.visit_identifier(param);
+    }
+    visitor.visit_block_statement(&func.body);
+}
+
+pub fn walk_call_expression<'ast, V: Visitor<'ast>>(visitor: &mut V, call: &'ast CallExpression) {
+    visitor.visit_expression(&call.function);
+    for arg in &call.arguments {
+        visitor.visit_expression(arg);
+    }
+}
+
+pub fn walk_array_literal<'ast, V: Visitor<'ast>>(visitor: &mut V, array: &'ast ArrayLiteral) {
+    for element in &array.elements {
+        visitor.visit_expression(element);
+    }
+}
+
+pub fn walk_index_expression<'ast, V: Visitor<'ast>>(visitor: &mut V, exp: &'ast IndexExpression) {
+    visitor.visit_expression(&exp.left);
+    visitor.visit_expression(&exp.index);
+}
+
+pub fn walk_hash_literal<'ast, V: Visitor<'ast>>(visitor: &mut V, hash: &'ast HashLiteral) {
+    for (key, value) in &hash.pairs {
+        visitor.visit_expression(key);
+        visitor.visit_expression(value);
+    }
+}
@@ -0,0 +1,218 @@
+//! In-place traversal of the AST, for passes that rewrite it.
+//!
+//! Implement `VisitorMut`, override the methods for the nodes you care
+//! about, and call the matching `walk_*_mut` function from an override to
+//! keep descending into its children. Identifiers are visited both where
+//! they are bound (`let` names, parameters) and where they are used.
+//!
+//! A pass that adds, removes or moves nodes leaves their `NodeId`s stale;
+//! call `Program::renumber` afterwards.
+
+use crate::evaluator::limits::grow_stack;
+#[cfg(feature = "bigint")]
+use crate::BigIntegerLiteral;
+use crate::{
+    ArrayLiteral, BlockStatement, Boolean, CallExpression, Expression, ExpressionStatement,
+    FunctionLiteral, HashLiteral, Identifier, IfExpression, IndexExpression, InfixExpression,
+    IntegerLiteral, LetStatement, PrefixExpression, Program, ReturnStatement, Statement,
+    StringLiteral, ThrowStatement, TryExpression,
+};
+use std::rc::Rc;
+
+pub trait VisitorMut: Sized {
+    fn visit_program(&mut self, program: &mut Program) {
+        walk_program_mut(self, program)
+    }
+
+    fn visit_statement(&mut self, stmt: &mut Statement) {
+        walk_statement_mut(self, stmt)
+    }
+
+    fn visit_let_statement(&mut self, stmt: &mut LetStatement) {
+        walk_let_statement_mut(self, stmt)
+    }
+
+    fn visit_return_statement(&mut self, stmt: &mut ReturnStatement) {
+        walk_return_statement_mut(self, stmt)
+    }
+
+    fn visit_throw_statement(&mut self, stmt: &mut ThrowStatement) {
+        walk_throw_statement_mut(self, stmt)
+    }
+
+    fn visit_expression_statement(&mut self, stmt: &mut ExpressionStatement) {
+        walk_expression_statement_mut(self, stmt)
+    }
+
+    fn visit_block_statement(&mut self, block: &mut BlockStatement) {
+        walk_block_statement_mut(self, block)
+    }
+
+    fn visit_expression(&mut self, exp: &mut Expression) {
+        walk_expression_mut(self, exp)
+    }
+
+    fn visit_identifier(&mut self, _ident: &mut Identifier) {}
+
+    fn visit_integer_literal(&mut self, _lit: &mut IntegerLiteral) {}
+
+    #[cfg(feature = "bigint")]
+    fn visit_big_integer_literal(&mut self, _lit: &mut BigIntegerLiteral) {}
+
+    fn visit_string_literal(&mut self, _lit: &mut StringLiteral) {}
+
+    fn visit_boolean(&mut self, _lit: &mut Boolean) {}
+
+    fn visit_prefix_expression(&mut self, exp: &mut PrefixExpression) {
+        walk_prefix_expression_mut(self, exp)
+    }
+
+    fn visit_infix_expression(&mut self, exp: &mut InfixExpression) {
+        walk_infix_expression_mut(self, exp)
+    }
+
+    fn visit_if_expression(&mut self, exp: &mut IfExpression) {
+        walk_if_expression_mut(self, exp)
+    }
+
+    fn visit_try_expression(&mut self, exp: &mut TryExpression) {
+        walk_try_expression_mut(self, exp)
+    }
+
+    fn visit_function_literal(&mut self, func: &mut FunctionLiteral) {
+        walk_function_literal_mut(self, func)
+    }
+
+    fn visit_call_expression(&mut self, call: &mut CallExpression) {
+        walk_call_expression_mut(self, call)
+    }
+
+    fn visit_array_literal(&mut self, array: &mut ArrayLiteral) {
+        walk_array_literal_mut(self, array)
+    }
+
+    fn visit_index_expression(&mut self, exp: &mut IndexExpression) {
+        walk_index_expression_mut(self, exp)
+    }
+
+    fn visit_hash_literal(&mut self, hash: &mut HashLiteral) {
+        walk_hash_literal_mut(self, hash)
+    }
+}
+
+pub fn walk_program_mut<V: VisitorMut>(visitor: &mut V, program: &mut Program) {
+    for stmt in &mut program.statements {
+        visitor.visit_statement(stmt);
+    }
+}
+
+pub fn walk_statement_mut<V: VisitorMut>(visitor: &mut V, stmt: &mut Statement) {
+    match stmt {
+        Statement::Let(stmt) => visitor.visit_let_statement(stmt),
+        Statement::Return(stmt) => visitor.visit_return_statement(stmt),
+        Statement::Throw(stmt) => visitor.visit_throw_statement(stmt),
+        Statement::Expression(stmt) => visitor.visit_expression_statement(stmt),
+        Statement::Block(block) => visitor.visit_block_statement(block),
+    }
+}
+
+pub fn walk_let_statement_mut<V: VisitorMut>(visitor: &mut V, stmt: &mut LetStatement) {
+    visitor.visit_identifier(&mut stmt.name);
+    visitor.visit_expression(&mut stmt.value);
+}
+
+pub fn walk_return_statement_mut<V: VisitorMut>(visitor: &mut V, stmt: &mut ReturnStatement) {
+    visitor.visit_expression(&mut stmt.return_value);
+}
+
+pub fn walk_throw_statement_mut<V: VisitorMut>(visitor: &mut V, stmt: &mut ThrowStatement) {
+    visitor.visit_expression(&mut stmt.value);
+}
+
+pub fn walk_expression_statement_mut<V: VisitorMut>(
+    visitor: &mut V,
+    stmt: &mut ExpressionStatement,
+) {
+    visitor.visit_expression(&mut stmt.expression);
+}
+
+pub fn walk_block_statement_mut<V: VisitorMut>(visitor: &mut V, block: &mut BlockStatement) {
+    for stmt in &mut block.statements {
+        visitor.visit_statement(stmt);
+    }
+}
+
+pub fn walk_expression_mut<V: VisitorMut>(visitor: &mut V, exp: &mut Expression) {
+    grow_stack(|| match exp {
+        Expression::Identifier(ident) => visitor.visit_identifier(ident),
+        Expression::Integer(lit) => visitor.visit_integer_literal(lit),
+        #[cfg(feature = "bigint")]
+        Expression::BigInteger(lit) => visitor.visit_big_integer_literal(lit),
+        Expression::String(lit) => visitor.visit_string_literal(lit),
+        Expression::Boolean(lit) => visitor.visit_boolean(lit),
+        Expression::Prefix(exp) => visitor.visit_prefix_expression(exp),
+        Expression::Infix(exp) => visitor.visit_infix_expression(exp),
+        Expression::If(exp) => visitor.visit_if_expression(exp),
+        Expression::Try(exp) => visitor.visit_try_expression(exp),
+        Expression::Function(func) => visitor.visit_function_literal(func),
+        Expression::Call(call) => visitor.visit_call_expression(call),
+        Expression::Array(array) => visitor.visit_array_literal(array),
+        Expression::Index(exp) => visitor.visit_index_expression(exp),
+        Expression::Hash(hash) => visitor.visit_hash_literal(hash),
+    })
+}
+
+pub fn walk_prefix_expression_mut<V: VisitorMut>(visitor: &mut V, exp: &mut PrefixExpression) {
+    visitor.visit_expression(&mut exp.right);
+}
+
+pub fn walk_infix_expression_mut<V: VisitorMut>(visitor: &mut V, exp: &mut InfixExpression) {
+    visitor.visit_expression(&mut exp.left);
+    visitor.visit_expression(&mut exp.right);
+}
+
+pub fn walk_if_expression_mut<V: VisitorMut>(visitor: &mut V, exp: &mut IfExpression) {
+    visitor.visit_expression(&mut exp.condition);
+    visitor.visit_block_statement(&mut exp.consequence);
+    if let Some(alternative) = &mut exp.alternative {
+        visitor.visit_block_statement(alternative);
+    }
+}
+
+pub fn walk_try_expression_mut<V: VisitorMut>(visitor: &mut V, exp: &mut TryExpression) {
+    visitor.visit_block_statement(&mut exp.body);
+    visitor.visit_identifier(&mut exp.parameter);
+    visitor.visit_block_statement(&mut exp.handler);
+}
+
+pub fn walk_function_literal_mut<V: VisitorMut>(visitor: &mut V, func: &mut FunctionLiteral) {
+    for param in &mut func.parameters {
+        visitor.visit_identifier(param);
+    }
+    visitor.visit_block_statement(Rc::make_mut(&mut func.body));
+}
+
+pub fn walk_call_expression_mut<V: VisitorMut>(visitor: &mut V, call: &mut CallExpression) {
+    visitor.visit_expression(&mut call.function);
+    for arg in &mut call.arguments {
+        visitor.visit_expression(arg);
+    }
+}
+
+pub fn walk_array_literal_mut<V: VisitorMut>(visitor: &mut V, array: &mut ArrayLiteral) {
+    for element in &mut array.elements {
+        visitor.visit_expression(element);
+    }
+}
+
+pub fn walk_index_expression_mut<V: VisitorMut>(visitor: &mut V, exp: &mut IndexExpression) {
+    visitor.visit_expression(&mut exp.left);
+    visitor.visit_expression(&mut exp.index);
+}
+
+pub fn walk_hash_literal_mut<V: VisitorMut>(visitor: &mut V, hash: &mut HashLiteral) {
+    for (key, value) in &mut hash.pairs {
+        visitor.visit_expression(key);
+        visitor.visit_expression(value);
+    }
+}
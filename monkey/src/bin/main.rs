@@ -1,6 +1,256 @@
 use monkey::repl::start;
+use monkey::{format_source, lint, Lexer, LintConfig, Parser, Severity, RULES};
+use std::fs;
+use std::io::{self, Read};
+use std::process::ExitCode;
 
-fn main() {
-    println!("welcome into Monkey repl!");
-    start();
+const USAGE: &str = "\
+usage: monkey                                start the REPL
+       monkey parse [--format sexp|json] [FILE]
+                                             print the parse tree of FILE, or of stdin
+       monkey fmt [--check] [FILE...]        format FILEs in place, or stdin to stdout;
+                                             with --check, only report what isn't formatted
+       monkey lint [--config FILE] [FILE...]
+                                             check FILEs, or stdin, against the lint rules;
+                                             the config defaults to .monkeylint if it exists
+       monkey lint --rules                   list the lint rules
+       monkey lsp                            run the language server over stdin and stdout";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        None => {
+            println!("welcome into Monkey repl! type :help for commands");
+            start();
+            ExitCode::SUCCESS
+        }
+        Some("parse") => parse(&args[1..]),
+        Some("fmt") => fmt(&args[1..]),
+        Some("lint") => lint_files(&args[1..]),
+        Some("lsp") => lsp(&args[1..]),
+        Some("-h" | "--help" | "help") => {
+            println!("{}", USAGE);
+            ExitCode::SUCCESS
+        }
+        Some(command) => usage_error(&format!("unknown command `{}`", command)),
+    }
+}
+
+fn parse(args: &[String]) -> ExitCode {
+    let mut format = "sexp";
+    let mut path = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => match args.next() {
+                Some(value) => format = value,
+                None => return usage_error("`--format` needs a value"),
+            },
+            _ if path.is_none() => path = Some(arg.as_str()),
+            _ => return usage_error(&format!("unexpected argument `{}`", arg)),
+        }
+    }
+    if format != "sexp" && format != "json" {
+        return usage_error(&format!("unknown format `{}`", format));
+    }
+
+    let source = match read_source(path) {
+        Ok(source) => source,
+        Err(message) => return failure(&message),
+    };
+    let mut lexer = Lexer::new(source);
+    let mut parser = Parser::new(&mut lexer);
+    let program = parser.parse_program().unwrap();
+    if !parser.errors().is_empty() {
+        for error in parser.errors() {
+            eprintln!("error: {}", error);
+        }
+        return ExitCode::FAILURE;
+    }
+
+    match format {
+        "sexp" => println!("{}", program.to_sexp()),
+        #[cfg(feature = "serde")]
+        _ => println!("{}", program.to_json()),
+        #[cfg(not(feature = "serde"))]
+        _ => return failure("JSON output needs monkey built with the `serde` feature"),
+    }
+    ExitCode::SUCCESS
+}
+
+fn fmt(args: &[String]) -> ExitCode {
+    let mut check = false;
+    let mut paths = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--check" => check = true,
+            flag if flag.starts_with('-') => {
+                return usage_error(&format!("unknown flag `{}`", flag))
+            }
+            path => paths.push(path),
+        }
+    }
+
+    if paths.is_empty() {
+        let source = match read_source(None) {
+            Ok(source) => source,
+            Err(message) => return failure(&message),
+        };
+        return match format_source(&source) {
+            Ok(formatted) if check && formatted != source => failure("stdin is not formatted"),
+            Ok(_) if check => ExitCode::SUCCESS,
+            Ok(formatted) => {
+                print!("{}", formatted);
+                ExitCode::SUCCESS
+            }
+            Err(errors) => failure(&errors.join("\n")),
+        };
+    }
+
+    let mut ok = true;
+    for path in paths {
+        let source = match read_source(Some(path)) {
+            Ok(source) => source,
+            Err(message) => {
+                eprintln!("error: {}", message);
+                ok = false;
+                continue;
+            }
+        };
+        let formatted = match format_source(&source) {
+            Ok(formatted) => formatted,
+            Err(errors) => {
+                for error in errors {
+                    eprintln!("error: {}: {}", path, error);
+                }
+                ok = false;
+                continue;
+            }
+        };
+        if formatted == source {
+            continue;
+        }
+        if check {
+            eprintln!("{} is not formatted", path);
+            ok = false;
+        } else if let Err(err) = fs::write(path, formatted) {
+            eprintln!("error: could not write {}: {}", path, err);
+            ok = false;
+        }
+    }
+    if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn lint_files(args: &[String]) -> ExitCode {
+    let mut config_path = None;
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--rules" => {
+                for rule in RULES {
+                    println!(
+                        "{:<22} {:<8} {}",
+                        rule.id,
+                        rule.default.to_string(),
+                        rule.description
+                    );
+                }
+                return ExitCode::SUCCESS;
+            }
+            "--config" => match args.next() {
+                Some(value) => config_path = Some(value.as_str()),
+                None => return usage_error("`--config` needs a value"),
+            },
+            flag if flag.starts_with('-') && flag != "-" => {
+                return usage_error(&format!("unknown flag `{}`", flag))
+            }
+            path => paths.push(path),
+        }
+    }
+
+    let config = match config_path {
+        Some(path) => fs::read_to_string(path)
+            .map_err(|err| format!("could not read {}: {}", path, err))
+            .and_then(|text| LintConfig::parse(&text).map_err(|err| format!("{}: {}", path, err))),
+        None => match fs::read_to_string(".monkeylint") {
+            Ok(text) => LintConfig::parse(&text).map_err(|err| format!(".monkeylint: {}", err)),
+            Err(_) => Ok(LintConfig::default()),
+        },
+    };
+    let config = match config {
+        Ok(config) => config,
+        Err(message) => return failure(&message),
+    };
+
+    if paths.is_empty() {
+        paths.push("-");
+    }
+    let mut ok = true;
+    for path in paths {
+        let source = match read_source(Some(path)) {
+            Ok(source) => source,
+            Err(message) => {
+                eprintln!("error: {}", message);
+                ok = false;
+                continue;
+            }
+        };
+        let name = if path == "-" { "<stdin>" } else { path };
+        for diagnostic in lint(&source, &config) {
+            eprintln!("{}\n", diagnostic.render_in(name, &source));
+            ok &= diagnostic.severity != Severity::Error;
+        }
+    }
+    if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn lsp(args: &[String]) -> ExitCode {
+    if let Some(arg) = args.first() {
+        return usage_error(&format!("unexpected argument `{}`", arg));
+    }
+    #[cfg(feature = "lsp")]
+    {
+        let stdin = io::stdin();
+        match monkey::run_server(stdin.lock(), io::stdout()) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(err) => failure(&format!("language server: {}", err)),
+        }
+    }
+    #[cfg(not(feature = "lsp"))]
+    failure("the language server needs monkey built with the `lsp` feature")
+}
+
+/// The contents of `path`, or of stdin when there is no path or it is `-`.
+fn read_source(path: Option<&str>) -> Result<String, String> {
+    match path {
+        None | Some("-") => {
+            let mut source = String::new();
+            io::stdin()
+                .read_to_string(&mut source)
+                .map_err(|err| format!("could not read stdin: {}", err))?;
+            Ok(source)
+        }
+        Some(path) => {
+            fs::read_to_string(path).map_err(|err| format!("could not read {}: {}", path, err))
+        }
+    }
+}
+
+fn failure(message: &str) -> ExitCode {
+    eprintln!("error: {}", message);
+    ExitCode::FAILURE
+}
+
+fn usage_error(message: &str) -> ExitCode {
+    eprintln!("error: {}\n{}", message, USAGE);
+    ExitCode::from(2)
 }
@@ -0,0 +1,236 @@
+use crate::{io_builtins, Capability, ErrorKind, NativeFunction, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+pub type BuiltinFn = dyn Fn(Vec<Value>) -> Value;
+
+/// Works out which capability a call needs from its arguments.
+pub type RequiresFn = dyn Fn(&[Value]) -> Option<Capability>;
+
+/// How many arguments a builtin takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+    Exact(usize),
+    AtLeast(usize),
+}
+
+impl Arity {
+    pub fn accepts(&self, count: usize) -> bool {
+        match *self {
+            Arity::Exact(n) => count == n,
+            Arity::AtLeast(n) => count >= n,
+        }
+    }
+}
+
+impl fmt::Display for Arity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Arity::Exact(n) => write!(f, "{}", n),
+            Arity::AtLeast(n) => write!(f, "at least {}", n),
+        }
+    }
+}
+
+/// A function implemented in Rust and callable from Monkey code.
+pub struct Builtin {
+    pub name: String,
+    pub arity: Arity,
+    pub doc: String,
+    func: Box<BuiltinFn>,
+    requires: Option<Box<RequiresFn>>,
+}
+
+impl Builtin {
+    pub fn new(name: &str, arity: Arity, func: impl Fn(Vec<Value>) -> Value + 'static) -> Builtin {
+        Builtin {
+            name: name.to_string(),
+            arity,
+            doc: String::new(),
+            func: Box::new(func),
+            requires: None,
+        }
+    }
+
+    /// Wrap an ordinary Rust closure, see `NativeFunction`.
+    pub fn from_fn<Args, F: NativeFunction<Args>>(name: &str, func: F) -> Builtin {
+        let builtin_name = name.to_string();
+        Builtin::new(name, Arity::Exact(F::ARITY), move |args| {
+            func.call(&builtin_name, args)
+        })
+    }
+
+    pub fn with_doc(mut self, doc: &str) -> Builtin {
+        self.doc = doc.to_string();
+        self
+    }
+
+    /// Declare what the builtin needs permission for. The evaluator
+    /// checks it against its `Capabilities` before every call.
+    pub fn requires(
+        mut self,
+        requires: impl Fn(&[Value]) -> Option<Capability> + 'static,
+    ) -> Builtin {
+        self.requires = Some(Box::new(requires));
+        self
+    }
+
+    /// The capability a call with `args` needs, if any.
+    pub fn required_capability(&self, args: &[Value]) -> Option<Capability> {
+        match &self.requires {
+            Some(requires) if self.arity.accepts(args.len()) => requires(args),
+            _ => None,
+        }
+    }
+
+    /// Check the argument count, then run the function.
+    pub fn call(&self, args: Vec<Value>) -> Value {
+        if !self.arity.accepts(args.len()) {
+            return Value::error(
+                ErrorKind::WrongArgumentCount,
+                format!(
+                    "wrong number of arguments to `{}`: want={}, got={}",
+                    self.name,
+                    self.arity,
+                    args.len()
+                ),
+            );
+        }
+        (self.func)(args)
+    }
+}
+
+/// The builtin functions visible to a program, looked up by name once
+/// no binding in the environment matches.
+#[derive(Clone)]
+pub struct Builtins {
+    functions: HashMap<String, Rc<Builtin>>,
+}
+
+impl Default for Builtins {
+    fn default() -> Self {
+        Builtins::new()
+    }
+}
+
+impl Builtins {
+    /// The standard set: `len`, `first`, `last`, `rest` and `push`, plus
+    /// the I/O builtins from `io_builtins`.
+    pub fn new() -> Builtins {
+        let mut builtins = Builtins::empty();
+        for builtin in [
+            Builtin::new("len", Arity::Exact(1), len)
+                .with_doc("Number of characters in a string or elements in an array."),
+            Builtin::new("first", Arity::Exact(1), first)
+                .with_doc("First element of an array, or null if it is empty."),
+            Builtin::new("last", Arity::Exact(1), last)
+                .with_doc("Last element of an array, or null if it is empty."),
+            Builtin::new("rest", Arity::Exact(1), rest)
+                .with_doc("A new array without the first element, or null if it is empty."),
+            Builtin::new("push", Arity::Exact(2), push)
+                .with_doc("A new array with the value appended."),
+        ]
+        .into_iter()
+        .chain(io_builtins())
+        {
+            builtins.insert(builtin);
+        }
+        builtins
+    }
+
+    pub fn empty() -> Builtins {
+        Builtins {
+            functions: HashMap::new(),
+        }
+    }
+
+    /// Add a builtin, replacing any existing one with the same name.
+    pub fn insert(&mut self, builtin: Builtin) {
+        self.functions
+            .insert(builtin.name.clone(), Rc::new(builtin));
+    }
+
+    pub fn register(
+        &mut self,
+        name: &str,
+        arity: Arity,
+        func: impl Fn(Vec<Value>) -> Value + 'static,
+    ) {
+        self.insert(Builtin::new(name, arity, func));
+    }
+
+    /// Register an ordinary Rust closure, converting its arguments and
+    /// result with `FromValue` and `IntoValue`.
+    pub fn register_fn<Args, F: NativeFunction<Args>>(&mut self, name: &str, func: F) {
+        self.insert(Builtin::from_fn(name, func));
+    }
+
+    pub fn get(&self, name: &str) -> Option<Value> {
+        self.functions.get(name).cloned().map(Value::Builtin)
+    }
+
+    pub fn doc(&self, name: &str) -> Option<&str> {
+        self.functions.get(name).map(|builtin| builtin.doc.as_str())
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.functions.keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+/// The error for an argument of the wrong type.
+pub fn argument_error(name: &str, expected: &str, got: &Value) -> Value {
+    argument_type_error(name, expected, got.type_name())
+}
+
+/// `argument_error` for a value of which only the type name is at hand.
+pub(crate) fn argument_type_error(name: &str, expected: &str, got: &str) -> Value {
+    Value::error(
+        ErrorKind::InvalidArgument,
+        format!("argument to `{}` must be {}, got {}", name, expected, got),
+    )
+}
+
+fn len(args: Vec<Value>) -> Value {
+    match &args[0] {
+        Value::String(value) => Value::Integer(value.chars().count() as i64),
+        Value::Array(elements) => Value::Integer(elements.len() as i64),
+        other => argument_error("len", "STRING or ARRAY", other),
+    }
+}
+
+fn first(args: Vec<Value>) -> Value {
+    match &args[0] {
+        Value::Array(elements) => elements.first().cloned().unwrap_or(Value::Null),
+        other => argument_error("first", "ARRAY", other),
+    }
+}
+
+fn last(args: Vec<Value>) -> Value {
+    match &args[0] {
+        Value::Array(elements) => elements.last().cloned().unwrap_or(Value::Null),
+        other => argument_error("last", "ARRAY", other),
+    }
+}
+
+fn rest(args: Vec<Value>) -> Value {
+    match &args[0] {
+        Value::Array(elements) if elements.is_empty() => Value::Null,
+        Value::Array(elements) => Value::Array(elements[1..].to_vec()),
+        other => argument_error("rest", "ARRAY", other),
+    }
+}
+
+fn push(mut args: Vec<Value>) -> Value {
+    let value = args.pop().unwrap();
+    match args.pop().unwrap() {
+        Value::Array(mut elements) => {
+            elements.push(value);
+            Value::Array(elements)
+        }
+        other => argument_error("push", "ARRAY", &other),
+    }
+}
@@ -0,0 +1,146 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// How much of one kind of resource scripts may reach.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Access<T> {
+    #[default]
+    Denied,
+    /// Only these names, or for paths anything beneath these directories.
+    Only(Vec<T>),
+    All,
+}
+
+/// Something a builtin needs permission for, given the arguments it
+/// was called with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Capability {
+    Stdout,
+    Read(PathBuf),
+    Write(PathBuf),
+    Env(String),
+    Clock,
+    Random,
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Capability::Stdout => write!(f, "write to stdout"),
+            Capability::Read(path) => write!(f, "read {}", path.display()),
+            Capability::Write(path) => write!(f, "write {}", path.display()),
+            Capability::Env(name) => write!(f, "read environment variable {}", name),
+            Capability::Clock => write!(f, "read the clock"),
+            Capability::Random => write!(f, "generate random numbers"),
+        }
+    }
+}
+
+/// What the I/O builtins are allowed to do. Calls outside of it fail
+/// with a permission error instead of running.
+///
+/// The default allows everything, as suits trusted tooling such as the
+/// repl. Untrusted scripts should start from `Capabilities::none()`:
+///
+/// ```
+/// use monkey::{Access, Capabilities};
+///
+/// let capabilities = Capabilities {
+///     stdout: true,
+///     read: Access::Only(vec!["scripts".into()]),
+///     ..Capabilities::none()
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    pub stdout: bool,
+    pub read: Access<PathBuf>,
+    pub write: Access<PathBuf>,
+    pub env: Access<String>,
+    pub clock: bool,
+    pub random: bool,
+}
+
+/// Everything, the same as `Capabilities::all()`: files anywhere may be
+/// read and written. Embedders running untrusted code must not rely on
+/// the default.
+impl Default for Capabilities {
+    fn default() -> Self {
+        Capabilities::all()
+    }
+}
+
+impl Capabilities {
+    pub fn all() -> Capabilities {
+        Capabilities {
+            stdout: true,
+            read: Access::All,
+            write: Access::All,
+            env: Access::All,
+            clock: true,
+            random: true,
+        }
+    }
+
+    pub fn none() -> Capabilities {
+        Capabilities {
+            stdout: false,
+            read: Access::Denied,
+            write: Access::Denied,
+            env: Access::Denied,
+            clock: false,
+            random: false,
+        }
+    }
+
+    pub fn allows(&self, capability: &Capability) -> bool {
+        match capability {
+            Capability::Stdout => self.stdout,
+            Capability::Read(path) => allows_path(&self.read, path),
+            Capability::Write(path) => allows_path(&self.write, path),
+            Capability::Env(name) => match &self.env {
+                Access::Denied => false,
+                Access::Only(names) => names.contains(name),
+                Access::All => true,
+            },
+            Capability::Clock => self.clock,
+            Capability::Random => self.random,
+        }
+    }
+}
+
+/// Paths are compared after resolving symlinks and `..`, so a script
+/// cannot climb out of an allowed directory.
+fn allows_path(access: &Access<PathBuf>, path: &Path) -> bool {
+    match access {
+        Access::Denied => false,
+        Access::All => true,
+        Access::Only(roots) => {
+            let Some(path) = resolve(path) else {
+                return false;
+            };
+            roots
+                .iter()
+                .filter_map(|root| root.canonicalize().ok())
+                .any(|root| path.starts_with(root))
+        }
+    }
+}
+
+/// The canonical form of `path`, which need not exist yet as long as
+/// its directory does.
+fn resolve(path: &Path) -> Option<PathBuf> {
+    if let Ok(path) = path.canonicalize() {
+        return Some(path);
+    }
+    // Something is there that can't be resolved, like a symlink to a file
+    // that doesn't exist yet. Writing would follow it wherever it points.
+    if path.symlink_metadata().is_ok() {
+        return None;
+    }
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    Some(parent.canonicalize().ok()?.join(path.file_name()?))
+}
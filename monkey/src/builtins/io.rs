@@ -0,0 +1,101 @@
+use crate::{argument_error, Arity, Builtin, Capability, ErrorKind, Value};
+use std::collections::hash_map::RandomState;
+use std::env;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The builtins reaching outside the interpreter. Each declares the
+/// capability it needs, see `Capabilities`.
+pub fn io_builtins() -> Vec<Builtin> {
+    vec![
+        Builtin::new("puts", Arity::AtLeast(0), puts)
+            .with_doc("Print each argument on its own line.")
+            .requires(|_| Some(Capability::Stdout)),
+        Builtin::new("read_file", Arity::Exact(1), read_file)
+            .with_doc("The contents of a file as a string.")
+            .requires(|args| path_arg(args).map(Capability::Read)),
+        Builtin::new("write_file", Arity::Exact(2), write_file)
+            .with_doc("Replace the contents of a file with a string.")
+            .requires(|args| path_arg(args).map(Capability::Write)),
+        Builtin::new("env", Arity::Exact(1), env_var)
+            .with_doc("The value of an environment variable, or null if it is unset.")
+            .requires(|args| match &args[0] {
+                Value::String(name) => Some(Capability::Env(name.clone())),
+                _ => None,
+            }),
+        Builtin::new("clock", Arity::Exact(0), clock)
+            .with_doc("Milliseconds since the Unix epoch.")
+            .requires(|_| Some(Capability::Clock)),
+        Builtin::new("random", Arity::Exact(1), random)
+            .with_doc("A random integer from 0 up to but not including the argument.")
+            .requires(|_| Some(Capability::Random)),
+    ]
+}
+
+/// The path a file builtin was given; anything else is left for the
+/// builtin itself to reject.
+fn path_arg(args: &[Value]) -> Option<PathBuf> {
+    match &args[0] {
+        Value::String(path) => Some(PathBuf::from(path)),
+        _ => None,
+    }
+}
+
+fn puts(args: Vec<Value>) -> Value {
+    for arg in args {
+        println!("{}", arg);
+    }
+    Value::Null
+}
+
+fn read_file(args: Vec<Value>) -> Value {
+    match &args[0] {
+        Value::String(path) => match fs::read_to_string(path) {
+            Ok(contents) => Value::String(contents),
+            Err(err) => Value::error(ErrorKind::Io, format!("could not read {}: {}", path, err)),
+        },
+        other => argument_error("read_file", "STRING", other),
+    }
+}
+
+fn write_file(args: Vec<Value>) -> Value {
+    match (&args[0], &args[1]) {
+        (Value::String(path), Value::String(contents)) => match fs::write(path, contents) {
+            Ok(()) => Value::Null,
+            Err(err) => Value::error(ErrorKind::Io, format!("could not write {}: {}", path, err)),
+        },
+        (Value::String(_), other) | (other, _) => argument_error("write_file", "STRING", other),
+    }
+}
+
+fn env_var(args: Vec<Value>) -> Value {
+    match &args[0] {
+        Value::String(name) => env::var(name).map(Value::String).unwrap_or(Value::Null),
+        other => argument_error("env", "STRING", other),
+    }
+}
+
+fn clock(_: Vec<Value>) -> Value {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    Value::Integer(elapsed.as_millis() as i64)
+}
+
+/// Not suitable for anything security related: the randomness comes
+/// from the keys std picks for its hash maps.
+fn random(args: Vec<Value>) -> Value {
+    match &args[0] {
+        Value::Integer(bound) if *bound > 0 => {
+            let bits = RandomState::new().build_hasher().finish();
+            Value::Integer((bits % *bound as u64) as i64)
+        }
+        Value::Integer(_) => Value::error(
+            ErrorKind::InvalidArgument,
+            "argument to `random` must be positive",
+        ),
+        other => argument_error("random", "INTEGER", other),
+    }
+}
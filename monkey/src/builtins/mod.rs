@@ -0,0 +1,9 @@
+pub mod builtins;
+pub mod capabilities;
+pub mod io;
+pub mod native;
+
+pub use builtins::*;
+pub use capabilities::*;
+pub use io::*;
+pub use native::*;
@@ -0,0 +1,48 @@
+use crate::{argument_type_error, FromValue, IntoValue, Value};
+
+/// A Rust closure that can be registered as a builtin, with its
+/// arguments unpacked through `FromValue` and its result packed back
+/// through `IntoValue`. Implemented for closures of up to six arguments.
+pub trait NativeFunction<Args>: 'static {
+    const ARITY: usize;
+
+    /// Convert `args` and call the closure. The argument count has
+    /// already been checked against `ARITY`.
+    fn call(&self, name: &str, args: Vec<Value>) -> Value;
+}
+
+macro_rules! impl_native_function {
+    ($arity:expr $(, $arg:ident)*) => {
+        impl<F, R $(, $arg)*> NativeFunction<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> R + 'static,
+            R: IntoValue,
+            $($arg: FromValue,)*
+        {
+            const ARITY: usize = $arity;
+
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            fn call(&self, name: &str, args: Vec<Value>) -> Value {
+                let mut args = args.into_iter();
+                $(
+                    let value = args.next().unwrap();
+                    // all an error needs of the value, so it isn't cloned
+                    let got = value.type_name();
+                    let $arg = match $arg::from_value(value) {
+                        Some(arg) => arg,
+                        None => return argument_type_error(name, &$arg::type_name(), got),
+                    };
+                )*
+                self($($arg),*).into_value()
+            }
+        }
+    };
+}
+
+impl_native_function!(0);
+impl_native_function!(1, A);
+impl_native_function!(2, A, B);
+impl_native_function!(3, A, B, C);
+impl_native_function!(4, A, B, C, D);
+impl_native_function!(5, A, B, C, D, E);
+impl_native_function!(6, A, B, C, D, E, G);
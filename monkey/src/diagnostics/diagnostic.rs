@@ -0,0 +1,126 @@
+use crate::Span;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A message about a piece of source code, printed with the offending
+/// line and a caret under the span:
+///
+/// ```text
+/// error: type mismatch: INTEGER + BOOLEAN
+///  --> 1:3
+///   |
+/// 1 | 1 + true
+///   |   ^
+///   = in `add` called at 3:1
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// The rule that produced it, such as `unused-binding`, shown after
+    /// the severity and used to configure or silence it.
+    pub code: Option<&'static str>,
+    pub message: String,
+    pub span: Option<Span>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Error,
+            code: None,
+            message: message.into(),
+            span: None,
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn warning(message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Warning,
+            ..Diagnostic::error(message)
+        }
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Diagnostic {
+        self.code = Some(code);
+        self
+    }
+
+    pub fn with_span(mut self, span: Span) -> Diagnostic {
+        self.span = Some(span);
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Diagnostic {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Format the diagnostic against the source its span points into.
+    pub fn render(&self, source: &str) -> String {
+        self.render_location(None, source)
+    }
+
+    /// Like `render`, with the location prefixed by the file `source`
+    /// was read from: `--> main.mk:1:3`.
+    pub fn render_in(&self, path: &str, source: &str) -> String {
+        self.render_location(Some(path), source)
+    }
+
+    fn render_location(&self, path: Option<&str>, source: &str) -> String {
+        let mut out = match self.code {
+            Some(code) => format!("{}[{}]: {}", self.severity, code, self.message),
+            None => format!("{}: {}", self.severity, self.message),
+        };
+        let mut gutter = String::new();
+        if let Some(span) = self.span {
+            let (line, column) = line_column(source, span.start);
+            let text = source.lines().nth(line - 1).unwrap_or("");
+            gutter = " ".repeat(line.to_string().len());
+            // the caret covers the span, but no further than its first line
+            let width = source
+                .get(span.start..span.end)
+                .map_or(1, |s| s.lines().next().unwrap_or("").chars().count())
+                .max(1);
+            let path = path.map_or(String::new(), |path| format!("{}:", path));
+            out += &format!("\n{}--> {}{}:{}", gutter, path, line, column);
+            out += &format!("\n{} |", gutter);
+            out += &format!("\n{} | {}", line, text);
+            out += &format!(
+                "\n{} | {}{}",
+                gutter,
+                " ".repeat(column - 1),
+                "^".repeat(width)
+            );
+        }
+        for note in &self.notes {
+            out += &format!("\n{} = {}", gutter, note);
+        }
+        out
+    }
+}
+
+/// The 1-based line and column of byte `offset`, counting columns in
+/// characters.
+pub fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let offset = offset.min(source.len());
+    let before = source.get(..offset).unwrap_or(source);
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
@@ -0,0 +1,84 @@
+//! Integer arithmetic. Results that don't fit an `i64` are an
+//! `INTEGER_OVERFLOW` error, unless the `bigint` feature is enabled: then
+//! they become `Value::BigInt`, and shrink back to `Value::Integer` as soon
+//! as they fit again, so scripts never see the difference.
+
+use crate::{ErrorKind, Value};
+#[cfg(feature = "bigint")]
+use num_bigint::BigInt;
+#[cfg(feature = "bigint")]
+use num_traits::Zero;
+
+pub(crate) fn negate(value: i64) -> Value {
+    match value.checked_neg() {
+        Some(value) => Value::Integer(value),
+        #[cfg(not(feature = "bigint"))]
+        None => Value::error(
+            ErrorKind::IntegerOverflow,
+            format!("integer overflow: -({})", value),
+        ),
+        #[cfg(feature = "bigint")]
+        None => integer(-BigInt::from(value)),
+    }
+}
+
+pub(crate) fn integer_infix(operator: &str, left: i64, right: i64) -> Value {
+    let result = match operator {
+        "+" => left.checked_add(right),
+        "-" => left.checked_sub(right),
+        "*" => left.checked_mul(right),
+        "/" if right == 0 => return division_by_zero(),
+        "/" => left.checked_div(right),
+        "<" => return Value::Boolean(left < right),
+        ">" => return Value::Boolean(left > right),
+        "==" => return Value::Boolean(left == right),
+        "!=" => return Value::Boolean(left != right),
+        _ => return unknown_operator(operator),
+    };
+    match result {
+        Some(value) => Value::Integer(value),
+        #[cfg(not(feature = "bigint"))]
+        None => Value::error(
+            ErrorKind::IntegerOverflow,
+            format!("integer overflow: {} {} {}", left, operator, right),
+        ),
+        #[cfg(feature = "bigint")]
+        None => big_infix(operator, BigInt::from(left), BigInt::from(right)),
+    }
+}
+
+#[cfg(feature = "bigint")]
+pub(crate) fn big_infix(operator: &str, left: BigInt, right: BigInt) -> Value {
+    match operator {
+        "+" => integer(left + right),
+        "-" => integer(left - right),
+        "*" => integer(left * right),
+        "/" if right.is_zero() => division_by_zero(),
+        "/" => integer(left / right),
+        "<" => Value::Boolean(left < right),
+        ">" => Value::Boolean(left > right),
+        "==" => Value::Boolean(left == right),
+        "!=" => Value::Boolean(left != right),
+        _ => unknown_operator(operator),
+    }
+}
+
+/// The value for `value`, as a plain `Integer` whenever it fits.
+#[cfg(feature = "bigint")]
+pub(crate) fn integer(value: BigInt) -> Value {
+    match i64::try_from(&value) {
+        Ok(value) => Value::Integer(value),
+        Err(_) => Value::BigInt(value),
+    }
+}
+
+fn division_by_zero() -> Value {
+    Value::error(ErrorKind::DivisionByZero, "division by zero")
+}
+
+fn unknown_operator(operator: &str) -> Value {
+    Value::error(
+        ErrorKind::UnknownOperator,
+        format!("unknown operator: INTEGER {} INTEGER", operator),
+    )
+}
@@ -0,0 +1,550 @@
+use crate::evaluator::{arithmetic, limits};
+use crate::{
+    BlockStatement, Builtins, CancelToken, Capabilities, Environment, ErrorKind, Expression, Frame,
+    Function, Halt, HashLiteral, Identifier, IfExpression, Limit, LimitExceeded, Limits, Location,
+    Node, Program, RuntimeError, Span, Statement, TryExpression, Usage, Value,
+};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Reading the clock on every step would dominate cheap expressions,
+/// so the deadline is only checked this often.
+const CLOCK_CHECK_INTERVAL: u64 = 256;
+
+/// Tree-walking evaluator. Holds what a program can reach besides its
+/// own bindings, such as the builtin functions, and the limits it runs
+/// under.
+#[derive(Default)]
+pub struct Evaluator {
+    pub builtins: Builtins,
+    pub limits: Limits,
+    /// What the I/O builtins may do.
+    pub capabilities: Capabilities,
+    /// Checked at every step; cancelling it stops the evaluation.
+    pub cancel: CancelToken,
+    /// Wall-clock time each evaluation may take.
+    pub timeout: Option<Duration>,
+    usage: Usage,
+    /// The source of the code being evaluated, for error locations.
+    source: Rc<str>,
+    deadline: Option<Instant>,
+    halted: Option<Halt>,
+}
+
+impl Evaluator {
+    pub fn new() -> Evaluator {
+        Evaluator {
+            builtins: Builtins::new(),
+            ..Evaluator::default()
+        }
+    }
+
+    /// Start counting steps, memory and time from zero again. Done by
+    /// `eval_program`; hosts calling `apply_function` directly do it
+    /// themselves.
+    pub fn reset_usage(&mut self) {
+        self.usage = Usage::default();
+        self.deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        self.halted = None;
+    }
+
+    /// Why the last evaluation was stopped, if it was. Its error value
+    /// says the same, but this tells it apart from ordinary errors.
+    pub fn halted(&self) -> Option<Halt> {
+        self.halted
+    }
+
+    pub fn limit_exceeded(&self) -> Option<LimitExceeded> {
+        match self.halted {
+            Some(Halt::Limit(exceeded)) => Some(exceeded),
+            _ => None,
+        }
+    }
+
+    fn halt(&mut self, halt: Halt) -> Value {
+        self.halted = Some(halt);
+        Value::error(halt.kind(), halt.to_string())
+    }
+
+    fn exceed(&mut self, limit: Limit, max: u64) -> Value {
+        self.halt(Halt::Limit(LimitExceeded { limit, max }))
+    }
+
+    /// Count one evaluation step and check for cancellation. Once the
+    /// evaluation is halted every further step fails too, so nothing
+    /// can carry on past it.
+    fn step(&mut self) -> Result<(), Value> {
+        if let Some(halt) = self.halted {
+            return Err(Value::error(halt.kind(), halt.to_string()));
+        }
+        if self.cancel.is_cancelled() {
+            return Err(self.halt(Halt::Cancelled));
+        }
+        self.usage.steps += 1;
+        if let (Some(timeout), Some(deadline)) = (self.timeout, self.deadline) {
+            if self.usage.steps % CLOCK_CHECK_INTERVAL == 0 && Instant::now() >= deadline {
+                return Err(self.halt(Halt::Timeout(timeout)));
+            }
+        }
+        match self.limits.max_steps {
+            Some(max) if self.usage.steps > max => Err(self.exceed(Limit::Steps, max)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Account for a newly created value against the length and memory
+    /// limits.
+    fn charge(&mut self, value: Value) -> Value {
+        if let Some(max) = self.limits.max_length {
+            if limits::length(&value) > max {
+                return self.exceed(Limit::Length, max as u64);
+            }
+        }
+        self.usage.memory = self
+            .usage
+            .memory
+            .saturating_add(limits::shallow_size(&value));
+        match self.limits.max_memory {
+            Some(max) if self.usage.memory > max => self.exceed(Limit::Memory, max as u64),
+            _ => value,
+        }
+    }
+
+    pub fn eval_program(&mut self, program: &Program, env: &Rc<RefCell<Environment>>) -> Value {
+        self.reset_usage();
+        self.source = Rc::clone(&program.source);
+        let mut result = Value::Null;
+        for stmt in &program.statements {
+            result = self.eval_statement(stmt, env);
+            match result {
+                Value::ReturnValue(value) => return *value,
+                Value::Error(_) => return result,
+                _ => {}
+            }
+        }
+        result
+    }
+
+    fn eval_block_statement(
+        &mut self,
+        block: &BlockStatement,
+        env: &Rc<RefCell<Environment>>,
+    ) -> Value {
+        let mut result = Value::Null;
+        for stmt in &block.statements {
+            result = self.eval_statement(stmt, env);
+            // leave the `ReturnValue` wrapped so enclosing blocks stop too
+            if matches!(result, Value::ReturnValue(_) | Value::Error(_)) {
+                return result;
+            }
+        }
+        result
+    }
+
+    fn eval_statement(&mut self, stmt: &Statement, env: &Rc<RefCell<Environment>>) -> Value {
+        if let Err(err) = self.step() {
+            return err;
+        }
+        match stmt {
+            Statement::Expression(stmt) => self.eval_expression(&stmt.expression, env),
+            Statement::Let(stmt) => {
+                let value = self.eval_expression(&stmt.value, env);
+                if value.is_error() {
+                    return value;
+                }
+                let name = &stmt.name.value;
+                env.borrow_mut()
+                    .set(name.clone(), name_function(value, name));
+                Value::Null
+            }
+            Statement::Return(stmt) => {
+                let value = self.eval_expression(&stmt.return_value, env);
+                if value.is_error() {
+                    return value;
+                }
+                Value::ReturnValue(Box::new(value))
+            }
+            Statement::Throw(stmt) => {
+                let value = self.eval_expression(&stmt.value, env);
+                if value.is_error() {
+                    return value;
+                }
+                let mut err = RuntimeError::thrown(value);
+                err.location = Some(self.location(stmt.span()));
+                Value::Error(Box::new(err))
+            }
+            Statement::Block(block) => self.eval_block_statement(block, env),
+        }
+    }
+
+    /// Evaluate `exp`. An error coming out of it without a location yet
+    /// was raised right here, so it gets the expression's span.
+    ///
+    /// Every nested call and expression passes through here, so this is
+    /// where the native stack is grown when it runs low: how deep a script
+    /// may go is up to `Limits::max_depth`, not the host thread's stack.
+    pub fn eval_expression(&mut self, exp: &Expression, env: &Rc<RefCell<Environment>>) -> Value {
+        let mut value = limits::grow_stack(|| self.eval_expression_node(exp, env));
+        if let Value::Error(err) = &mut value {
+            if err.location.is_none() {
+                err.location = Some(self.location(exp.span()));
+            }
+        }
+        value
+    }
+
+    fn location(&self, span: Span) -> Location {
+        Location {
+            span,
+            source: Rc::clone(&self.source),
+        }
+    }
+
+    fn eval_expression_node(&mut self, exp: &Expression, env: &Rc<RefCell<Environment>>) -> Value {
+        if let Err(err) = self.step() {
+            return err;
+        }
+        match exp {
+            Expression::Integer(lit) => Value::Integer(lit.value),
+            #[cfg(feature = "bigint")]
+            Expression::BigInteger(lit) => self.charge(Value::BigInt(lit.value.clone())),
+            Expression::Boolean(lit) => Value::Boolean(lit.value),
+            Expression::String(lit) => self.charge(Value::String(lit.value.clone())),
+            Expression::Identifier(ident) => self.eval_identifier(ident, env),
+            Expression::Prefix(prefix) => {
+                let right = self.eval_expression(&prefix.right, env);
+                if right.is_error() {
+                    return right;
+                }
+                eval_prefix_expression(&prefix.operator, right)
+            }
+            Expression::Infix(infix) => {
+                let left = self.eval_expression(&infix.left, env);
+                if left.is_error() {
+                    return left;
+                }
+                let right = self.eval_expression(&infix.right, env);
+                if right.is_error() {
+                    return right;
+                }
+                let value = eval_infix_expression(&infix.operator, left, right);
+                self.charge(value)
+            }
+            Expression::If(ie) => self.eval_if_expression(ie, env),
+            Expression::Try(te) => self.eval_try_expression(te, env),
+            Expression::Function(func) => Value::Function(Rc::new(Function {
+                name: None,
+                parameters: func.parameters.clone(),
+                body: Rc::clone(&func.body),
+                env: Rc::clone(env),
+                source: Rc::clone(&self.source),
+            })),
+            Expression::Call(call) => {
+                let function = self.eval_expression(&call.function, env);
+                if function.is_error() {
+                    return function;
+                }
+                let args = match self.eval_expressions(&call.arguments, env) {
+                    Ok(args) => args,
+                    Err(err) => return err,
+                };
+                let mut value = self.apply_function(function, args);
+                if let Value::Error(err) = &mut value {
+                    // the frame the callee pushed does not know where it was called
+                    if let Some(frame) = err.trace.last_mut() {
+                        if frame.call_site.is_none() {
+                            frame.call_site = Some(self.location(call.span()));
+                        }
+                    }
+                }
+                value
+            }
+            Expression::Array(array) => match self.eval_expressions(&array.elements, env) {
+                Ok(elements) => self.charge(Value::Array(elements)),
+                Err(err) => err,
+            },
+            Expression::Hash(hash) => {
+                let value = self.eval_hash_literal(hash, env);
+                self.charge(value)
+            }
+            Expression::Index(ie) => {
+                let left = self.eval_expression(&ie.left, env);
+                if left.is_error() {
+                    return left;
+                }
+                let index = self.eval_expression(&ie.index, env);
+                if index.is_error() {
+                    return index;
+                }
+                eval_index_expression(left, index)
+            }
+        }
+    }
+
+    /// Evaluate left to right, stopping at the first error.
+    fn eval_expressions(
+        &mut self,
+        exps: &[Expression],
+        env: &Rc<RefCell<Environment>>,
+    ) -> Result<Vec<Value>, Value> {
+        let mut values = Vec::with_capacity(exps.len());
+        for exp in exps {
+            let value = self.eval_expression(exp, env);
+            if value.is_error() {
+                return Err(value);
+            }
+            values.push(value);
+        }
+        Ok(values)
+    }
+
+    fn eval_hash_literal(&mut self, hash: &HashLiteral, env: &Rc<RefCell<Environment>>) -> Value {
+        let mut pairs = BTreeMap::new();
+        for (key_node, value_node) in &hash.pairs {
+            let key = self.eval_expression(key_node, env);
+            if key.is_error() {
+                return key;
+            }
+            let Some(hash_key) = key.hash_key() else {
+                return Value::error(
+                    ErrorKind::TypeMismatch,
+                    format!("unusable as hash key: {}", key.type_name()),
+                );
+            };
+            let value = self.eval_expression(value_node, env);
+            if value.is_error() {
+                return value;
+            }
+            pairs.insert(hash_key, value);
+        }
+        Value::Hash(pairs)
+    }
+
+    fn eval_identifier(&self, ident: &Identifier, env: &Rc<RefCell<Environment>>) -> Value {
+        // a resolved name is found without searching the scopes in
+        // between, unless a conditional `let` hasn't bound it there
+        let env = env.borrow();
+        let value = ident
+            .slot
+            .and_then(|slot| env.get_at(slot.depth, &ident.value))
+            .or_else(|| env.get(&ident.value));
+        if let Some(value) = value {
+            return value;
+        }
+        match self.builtins.get(&ident.value) {
+            Some(builtin) => builtin,
+            None => Value::error(
+                ErrorKind::UndefinedIdentifier,
+                format!("identifier not found: {}", ident.value),
+            ),
+        }
+    }
+
+    fn eval_if_expression(&mut self, ie: &IfExpression, env: &Rc<RefCell<Environment>>) -> Value {
+        let condition = self.eval_expression(&ie.condition, env);
+        if condition.is_error() {
+            return condition;
+        }
+        if is_truthy(&condition) {
+            self.eval_block_statement(&ie.consequence, env)
+        } else if let Some(alternative) = &ie.alternative {
+            self.eval_block_statement(alternative, env)
+        } else {
+            Value::Null
+        }
+    }
+
+    /// Run the body, and the handler if the body fails. Halts such as
+    /// an exceeded limit cannot be caught.
+    fn eval_try_expression(&mut self, te: &TryExpression, env: &Rc<RefCell<Environment>>) -> Value {
+        let err = match self.eval_block_statement(&te.body, env) {
+            Value::Error(err) if self.halted.is_none() => err,
+            result => return result,
+        };
+        let mut handler_env = Environment::new_enclosed(Rc::clone(env));
+        handler_env.set(te.parameter.value.clone(), err.to_caught());
+        self.eval_block_statement(&te.handler, &Rc::new(RefCell::new(handler_env)))
+    }
+
+    pub fn apply_function(&mut self, function: Value, args: Vec<Value>) -> Value {
+        let function = match function {
+            Value::Function(function) => function,
+            Value::Builtin(builtin) => {
+                if let Some(capability) = builtin.required_capability(&args) {
+                    if !self.capabilities.allows(&capability) {
+                        return Value::error(
+                            ErrorKind::PermissionDenied,
+                            format!("permission denied: {}", capability),
+                        );
+                    }
+                }
+                let value = builtin.call(args);
+                return self.charge(value);
+            }
+            other => {
+                return Value::error(
+                    ErrorKind::NotCallable,
+                    format!("not a function: {}", other.type_name()),
+                )
+            }
+        };
+        if function.parameters.len() != args.len() {
+            return Value::error(
+                ErrorKind::WrongArgumentCount,
+                format!(
+                    "wrong number of arguments: want={}, got={}",
+                    function.parameters.len(),
+                    args.len()
+                ),
+            );
+        }
+
+        if let Some(max) = self.limits.max_depth {
+            if self.usage.depth >= max {
+                return self.exceed(Limit::Depth, max as u64);
+            }
+        }
+
+        let mut env = Environment::new_enclosed(Rc::clone(&function.env));
+        for (param, arg) in function.parameters.iter().zip(args) {
+            env.set(param.value.clone(), arg);
+        }
+        self.usage.depth += 1;
+        let caller_source = std::mem::replace(&mut self.source, Rc::clone(&function.source));
+        let result = self.eval_block_statement(&function.body, &Rc::new(RefCell::new(env)));
+        self.source = caller_source;
+        self.usage.depth -= 1;
+        match result {
+            Value::ReturnValue(value) => *value,
+            Value::Error(mut err) => {
+                err.trace.push(Frame {
+                    function: function
+                        .name
+                        .as_deref()
+                        .unwrap_or("<anonymous>")
+                        .to_string(),
+                    call_site: None,
+                });
+                Value::Error(err)
+            }
+            value => value,
+        }
+    }
+}
+
+fn eval_prefix_expression(operator: &str, right: Value) -> Value {
+    match (operator, right) {
+        ("!", right) => Value::Boolean(!is_truthy(&right)),
+        ("-", Value::Integer(value)) => arithmetic::negate(value),
+        #[cfg(feature = "bigint")]
+        ("-", Value::BigInt(value)) => arithmetic::integer(-value),
+        (_, right) => Value::error(
+            ErrorKind::UnknownOperator,
+            format!("unknown operator: {}{}", operator, right.type_name()),
+        ),
+    }
+}
+
+fn eval_infix_expression(operator: &str, left: Value, right: Value) -> Value {
+    match (left, right) {
+        (Value::Integer(left), Value::Integer(right)) => {
+            arithmetic::integer_infix(operator, left, right)
+        }
+        #[cfg(feature = "bigint")]
+        (Value::BigInt(left), Value::BigInt(right)) => arithmetic::big_infix(operator, left, right),
+        #[cfg(feature = "bigint")]
+        (Value::Integer(left), Value::BigInt(right)) => {
+            arithmetic::big_infix(operator, left.into(), right)
+        }
+        #[cfg(feature = "bigint")]
+        (Value::BigInt(left), Value::Integer(right)) => {
+            arithmetic::big_infix(operator, left, right.into())
+        }
+        (Value::Boolean(left), Value::Boolean(right)) => match operator {
+            "==" => Value::Boolean(left == right),
+            "!=" => Value::Boolean(left != right),
+            _ => Value::error(
+                ErrorKind::UnknownOperator,
+                format!("unknown operator: BOOLEAN {} BOOLEAN", operator),
+            ),
+        },
+        (Value::String(left), Value::String(right)) => match operator {
+            "+" => Value::String(left + &right),
+            "==" => Value::Boolean(left == right),
+            "!=" => Value::Boolean(left != right),
+            _ => Value::error(
+                ErrorKind::UnknownOperator,
+                format!("unknown operator: STRING {} STRING", operator),
+            ),
+        },
+        (left, right) if left.type_name() != right.type_name() => Value::error(
+            ErrorKind::TypeMismatch,
+            format!(
+                "type mismatch: {} {} {}",
+                left.type_name(),
+                operator,
+                right.type_name()
+            ),
+        ),
+        (left, right) => match operator {
+            "==" => Value::Boolean(left == right),
+            "!=" => Value::Boolean(left != right),
+            _ => Value::error(
+                ErrorKind::UnknownOperator,
+                format!(
+                    "unknown operator: {} {} {}",
+                    left.type_name(),
+                    operator,
+                    right.type_name()
+                ),
+            ),
+        },
+    }
+}
+
+fn eval_index_expression(left: Value, index: Value) -> Value {
+    match (left, index) {
+        (Value::Array(elements), Value::Integer(i)) => {
+            match usize::try_from(i).ok().and_then(|i| elements.get(i)) {
+                Some(element) => element.clone(),
+                None => Value::error(
+                    ErrorKind::IndexOutOfRange,
+                    format!("index out of range: {}, length is {}", i, elements.len()),
+                ),
+            }
+        }
+        #[cfg(feature = "bigint")]
+        (Value::Array(elements), Value::BigInt(i)) => Value::error(
+            ErrorKind::IndexOutOfRange,
+            format!("index out of range: {}, length is {}", i, elements.len()),
+        ),
+        (Value::Hash(pairs), index) => match index.hash_key() {
+            Some(key) => pairs.get(&key).cloned().unwrap_or(Value::Null),
+            None => Value::error(
+                ErrorKind::TypeMismatch,
+                format!("unusable as hash key: {}", index.type_name()),
+            ),
+        },
+        (left, _) => Value::error(
+            ErrorKind::TypeMismatch,
+            format!("index operator not supported: {}", left.type_name()),
+        ),
+    }
+}
+
+fn is_truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Boolean(false))
+}
+
+/// Give a function its first name, so traces can tell it apart. A
+/// function already shared elsewhere keeps the name it has.
+fn name_function(mut value: Value, name: &str) -> Value {
+    if let Value::Function(function) = &mut value {
+        if let Some(function) = Rc::get_mut(function) {
+            function.name.get_or_insert_with(|| name.to_string());
+        }
+    }
+    value
+}
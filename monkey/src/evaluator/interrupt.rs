@@ -0,0 +1,61 @@
+use crate::{ErrorKind, LimitExceeded};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Handle for stopping an evaluation from another thread. Clones share
+/// the same flag; the evaluator checks it at every step.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+
+    /// Ask the evaluation to stop. It stays cancelled until `reset`.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn reset(&self) {
+        self.cancelled.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Why an evaluation was stopped before it finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Halt {
+    Limit(LimitExceeded),
+    Cancelled,
+    Timeout(Duration),
+}
+
+impl Halt {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Halt::Limit(_) => ErrorKind::LimitExceeded,
+            Halt::Cancelled => ErrorKind::Cancelled,
+            Halt::Timeout(_) => ErrorKind::Timeout,
+        }
+    }
+}
+
+impl fmt::Display for Halt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Halt::Limit(exceeded) => write!(f, "{}", exceeded),
+            Halt::Cancelled => write!(f, "evaluation cancelled"),
+            Halt::Timeout(timeout) => write!(f, "evaluation timed out after {:?}", timeout),
+        }
+    }
+}
+
+impl std::error::Error for Halt {}
@@ -0,0 +1,114 @@
+use crate::{HashKey, Value};
+use std::fmt;
+use std::mem;
+
+/// Bounds on what one evaluation may use, for running untrusted code.
+/// `None` leaves that resource unbounded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Statements and expressions evaluated.
+    pub max_steps: Option<u64>,
+    /// Nested function calls.
+    pub max_depth: Option<usize>,
+    /// Bytes of strings, arrays and hashes created, roughly.
+    pub max_memory: Option<usize>,
+    /// Characters of a string or elements of an array or hash.
+    pub max_length: Option<usize>,
+}
+
+impl Default for Limits {
+    /// Only the call depth is bounded, so runaway recursion reports an
+    /// error instead of overflowing the host's stack.
+    fn default() -> Self {
+        Limits {
+            max_depth: Some(Limits::DEFAULT_MAX_DEPTH),
+            ..Limits::none()
+        }
+    }
+}
+
+impl Limits {
+    pub const DEFAULT_MAX_DEPTH: usize = 256;
+
+    pub fn none() -> Limits {
+        Limits {
+            max_steps: None,
+            max_depth: None,
+            max_memory: None,
+            max_length: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Steps,
+    Depth,
+    Memory,
+    Length,
+}
+
+/// Which limit an evaluation ran into, and what it was set to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LimitExceeded {
+    pub limit: Limit,
+    pub max: u64,
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let what = match self.limit {
+            Limit::Steps => "evaluation steps",
+            Limit::Depth => "nested calls",
+            Limit::Memory => "bytes allocated",
+            Limit::Length => "elements in a string, array or hash",
+        };
+        write!(f, "limit exceeded: more than {} {}", self.max, what)
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+/// What the current evaluation has used so far.
+#[derive(Debug, Default)]
+pub(crate) struct Usage {
+    pub steps: u64,
+    pub depth: usize,
+    pub memory: usize,
+}
+
+/// When less than this much native stack is left, recursion continues on
+/// a new segment of `STACK_SEGMENT` bytes. The red zone covers the frames
+/// between one nested expression and the next, unoptimized builds included.
+const STACK_RED_ZONE: usize = 128 * 1024;
+const STACK_SEGMENT: usize = 1024 * 1024;
+
+/// Run `f`, first moving to a fresh stack segment if the current one is
+/// running low. Recursion over nested expressions goes through here, so
+/// how deep it may go doesn't depend on the host thread's stack size.
+pub(crate) fn grow_stack<R>(f: impl FnOnce() -> R) -> R {
+    stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT, f)
+}
+
+/// Approximate heap size of `value` itself. Nested values were counted
+/// when they were created, so only the outer allocation is included.
+pub(crate) fn shallow_size(value: &Value) -> usize {
+    match value {
+        Value::String(s) => s.len(),
+        #[cfg(feature = "bigint")]
+        Value::BigInt(n) => (n.bits() as usize + 7) / 8,
+        Value::Array(elements) => elements.len() * mem::size_of::<Value>(),
+        Value::Hash(pairs) => pairs.len() * mem::size_of::<(HashKey, Value)>(),
+        _ => 0,
+    }
+}
+
+/// Number of characters or elements in `value`, for `max_length`.
+pub(crate) fn length(value: &Value) -> usize {
+    match value {
+        Value::String(s) => s.chars().count(),
+        Value::Array(elements) => elements.len(),
+        Value::Hash(pairs) => pairs.len(),
+        _ => 0,
+    }
+}
@@ -0,0 +1,8 @@
+pub(crate) mod arithmetic;
+pub mod evaluator;
+pub mod interrupt;
+pub mod limits;
+
+pub use evaluator::*;
+pub use interrupt::*;
+pub use limits::*;
@@ -0,0 +1,347 @@
+use crate::evaluator::limits::grow_stack;
+use crate::{
+    BlockStatement, Comment, Expression, Lexer, Node, Parser, Precedence, Program, Statement,
+    TokenType,
+};
+
+const INDENT: &str = "    ";
+
+/// Format `source` the canonical way: four-space indentation, one
+/// statement per line, blocks always broken over lines, and only the
+/// parentheses the grouping needs.
+///
+/// Comments are kept. Those between statements stay where they are, at
+/// most one blank line is kept between statements, and a comment inside
+/// an expression moves to just after its statement.
+///
+/// Returns the parser's errors when `source` doesn't parse.
+pub fn format_source(source: &str) -> Result<String, Vec<String>> {
+    let mut lexer = Lexer::new(source.to_string());
+    let mut parser = Parser::new(&mut lexer);
+    let program = parser.parse_program().unwrap();
+    if !parser.errors().is_empty() {
+        return Err(parser.errors().to_vec());
+    }
+    let mut formatter = Formatter {
+        source,
+        comments: lexer.comments(),
+        out: String::new(),
+        indent: 0,
+    };
+    formatter.program(&program);
+    Ok(formatter.out)
+}
+
+struct Formatter<'a> {
+    source: &'a str,
+    /// The comments not written yet.
+    comments: &'a [Comment],
+    out: String,
+    indent: usize,
+}
+
+impl Formatter<'_> {
+    fn program(&mut self, program: &Program) {
+        self.statements(&program.statements, self.source.len(), false);
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+    }
+
+    /// Write `statements`, one per line, along with the comments before
+    /// `end`.
+    fn statements(&mut self, statements: &[Statement], end: usize, in_block: bool) {
+        let mut first = true;
+        for (i, stmt) in statements.iter().enumerate() {
+            let start = stmt.span().start;
+            self.comments_before(start, &mut first);
+            self.item_break(start, first);
+            first = false;
+            self.statement(stmt);
+            if needs_semicolon(stmt, statements.get(i + 1), in_block) {
+                self.out.push(';');
+            }
+        }
+        self.comments_before(end, &mut first);
+    }
+
+    fn comments_before(&mut self, offset: usize, first: &mut bool) {
+        while let Some((comment, rest)) = self.comments.split_first() {
+            if comment.span.start >= offset {
+                break;
+            }
+            self.comments = rest;
+            if self.is_trailing(comment) && !self.out.is_empty() {
+                // stays at the end of the line it was on
+                self.out.push(' ');
+            } else {
+                self.item_break(comment.span.start, *first);
+                *first = false;
+            }
+            self.out.push_str(&comment.text);
+        }
+    }
+
+    /// Start a new line for the statement or comment at `offset`, after a
+    /// blank line if the source had one there.
+    fn item_break(&mut self, offset: usize, first: bool) {
+        if self.out.is_empty() {
+            return;
+        }
+        if !first && self.blank_line_before(offset) {
+            self.out.push('\n');
+        }
+        self.out.push('\n');
+        self.out.push_str(&INDENT.repeat(self.indent));
+    }
+
+    /// Whether code comes before `comment` on its line.
+    fn is_trailing(&self, comment: &Comment) -> bool {
+        let before = &self.source[..comment.span.start];
+        !before.rsplit('\n').next().unwrap_or("").trim().is_empty()
+    }
+
+    fn blank_line_before(&self, offset: usize) -> bool {
+        let before = &self.source[..offset];
+        let whitespace = &before[before.trim_end().len()..];
+        whitespace.matches('\n').count() > 1
+    }
+
+    fn statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Let(stmt) => {
+                self.out.push_str("let ");
+                self.out.push_str(&stmt.name.declaration());
+                self.out.push_str(" = ");
+                self.expression(&stmt.value);
+            }
+            Statement::Return(stmt) => {
+                self.out.push_str("return ");
+                self.expression(&stmt.return_value);
+            }
+            Statement::Throw(stmt) => {
+                self.out.push_str("throw ");
+                self.expression(&stmt.value);
+            }
+            Statement::Expression(stmt) => self.expression(&stmt.expression),
+            Statement::Block(block) => self.block(block),
+        }
+    }
+
+    fn block(&mut self, block: &BlockStatement) {
+        self.out.push('{');
+        self.indent += 1;
+        let end = self.block_end(block);
+        self.statements(&block.statements, end, true);
+        self.indent -= 1;
+        if !self.out.ends_with('{') {
+            self.out.push('\n');
+            self.out.push_str(&INDENT.repeat(self.indent));
+        }
+        self.out.push('}');
+    }
+
+    /// The offset of the `}` closing `block`, found by lexing forward
+    /// from its `{`.
+    fn block_end(&self, block: &BlockStatement) -> usize {
+        let start = block.token.span.start;
+        let mut lexer = Lexer::new(self.source[start..].to_string());
+        let mut depth = 0;
+        loop {
+            let tok = lexer.next_token();
+            match tok.r#type {
+                TokenType::LBRACE => depth += 1,
+                TokenType::RBRACE if depth == 1 => return start + tok.span.start,
+                TokenType::RBRACE => depth -= 1,
+                TokenType::EOF => return self.source.len(),
+                _ => {}
+            }
+        }
+    }
+
+    fn expression(&mut self, exp: &Expression) {
+        grow_stack(|| match exp {
+            Expression::Identifier(ident) => self.out.push_str(&ident.value),
+            Expression::Integer(lit) => self.out.push_str(&lit.value.to_string()),
+            #[cfg(feature = "bigint")]
+            Expression::BigInteger(lit) => self.out.push_str(&lit.value.to_string()),
+            Expression::String(lit) => {
+                self.out.push('"');
+                self.out.push_str(&lit.value);
+                self.out.push('"');
+            }
+            Expression::Boolean(lit) => self.out.push_str(&lit.value.to_string()),
+            Expression::Prefix(exp) => {
+                self.out.push_str(&exp.operator);
+                match &*exp.right {
+                    // `--1` and `!!x` read like operators of their own
+                    Expression::Prefix(right) if right.operator == exp.operator => {
+                        self.out.push('(');
+                        self.expression(&exp.right);
+                        self.out.push(')');
+                    }
+                    right => self.operand(right, Precedence::Prefix, false),
+                }
+            }
+            Expression::Infix(exp) => {
+                let precedence = Precedence::of(exp.token.r#type);
+                self.operand(&exp.left, precedence, false);
+                self.out.push(' ');
+                self.out.push_str(&exp.operator);
+                self.out.push(' ');
+                self.operand(&exp.right, precedence, true);
+            }
+            Expression::If(exp) => {
+                self.out.push_str("if (");
+                self.expression(&exp.condition);
+                self.out.push_str(") ");
+                self.block(&exp.consequence);
+                if let Some(alternative) = &exp.alternative {
+                    self.out.push_str(" else ");
+                    self.block(alternative);
+                }
+            }
+            Expression::Try(exp) => {
+                self.out.push_str("try ");
+                self.block(&exp.body);
+                self.out.push_str(" catch (");
+                self.out.push_str(&exp.parameter.value);
+                self.out.push_str(") ");
+                self.block(&exp.handler);
+            }
+            Expression::Function(func) => {
+                let params: Vec<String> = func.parameters.iter().map(|p| p.declaration()).collect();
+                self.out.push_str("fn(");
+                self.out.push_str(&params.join(", "));
+                self.out.push_str(") ");
+                if let Some(return_type) = &func.return_type {
+                    self.out.push_str(&format!("-> {} ", return_type));
+                }
+                self.block(&func.body);
+            }
+            Expression::Call(call) => {
+                self.operand(&call.function, Precedence::Call, false);
+                self.out.push('(');
+                self.list(&call.arguments);
+                self.out.push(')');
+            }
+            Expression::Array(array) => {
+                self.out.push('[');
+                self.list(&array.elements);
+                self.out.push(']');
+            }
+            Expression::Index(exp) => {
+                // indexing chains with calls either way round, so it
+                // binds like a call here
+                self.operand(&exp.left, Precedence::Call, false);
+                self.out.push('[');
+                self.expression(&exp.index);
+                self.out.push(']');
+            }
+            Expression::Hash(hash) => {
+                self.out.push('{');
+                for (i, (key, value)) in hash.pairs.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.expression(key);
+                    self.out.push_str(": ");
+                    self.expression(value);
+                }
+                self.out.push('}');
+            }
+        })
+    }
+
+    fn list(&mut self, expressions: &[Expression]) {
+        for (i, exp) in expressions.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.expression(exp);
+        }
+    }
+
+    /// Write an operand of an operator binding at `precedence`, in
+    /// parentheses if it would otherwise bind differently.
+    fn operand(&mut self, exp: &Expression, precedence: Precedence, right: bool) {
+        if needs_parens(exp, precedence, right) {
+            self.out.push('(');
+            self.expression(exp);
+            self.out.push(')');
+        } else {
+            self.expression(exp);
+        }
+    }
+}
+
+/// How tightly `exp` holds together, or `None` for expressions that are
+/// never split up, such as literals.
+fn binding(exp: &Expression) -> Option<Precedence> {
+    match exp {
+        Expression::Prefix(_) => Some(Precedence::Prefix),
+        Expression::Infix(exp) => Some(Precedence::of(exp.token.r#type)),
+        Expression::Call(_) => Some(Precedence::Call),
+        Expression::Index(_) => Some(Precedence::Index),
+        _ => None,
+    }
+}
+
+/// Operators are left-associative, so a right operand needs parentheses
+/// at the same precedence and a left one only below it.
+fn needs_parens(exp: &Expression, precedence: Precedence, right: bool) -> bool {
+    match binding(exp) {
+        Some(binding) if right => binding <= precedence,
+        Some(binding) => binding < precedence,
+        None => false,
+    }
+}
+
+/// Statements end in `;` except a block's last expression, whose value
+/// the block takes, and expressions ending in a block, unless the next
+/// statement would otherwise continue them.
+fn needs_semicolon(stmt: &Statement, next: Option<&Statement>, in_block: bool) -> bool {
+    match (stmt, next) {
+        (Statement::Block(_), _) => false,
+        (Statement::Expression(stmt), None) => !in_block && !ends_with_block(&stmt.expression),
+        (Statement::Expression(stmt), Some(next)) => {
+            !ends_with_block(&stmt.expression) || starts_with_operator(next)
+        }
+        _ => true,
+    }
+}
+
+fn ends_with_block(exp: &Expression) -> bool {
+    match exp {
+        Expression::If(_) | Expression::Try(_) | Expression::Function(_) => true,
+        Expression::Prefix(exp) => ends_with_block(&exp.right),
+        Expression::Infix(exp) => ends_with_block(&exp.right),
+        _ => false,
+    }
+}
+
+/// Whether `stmt` is written starting with a token that could also
+/// continue the expression before it: `(`, `[` or `-`.
+fn starts_with_operator(stmt: &Statement) -> bool {
+    fn starts(exp: &Expression) -> bool {
+        match exp {
+            Expression::Prefix(exp) => exp.operator == "-",
+            Expression::Array(_) => true,
+            Expression::Infix(exp) => {
+                let precedence = Precedence::of(exp.token.r#type);
+                needs_parens(&exp.left, precedence, false) || starts(&exp.left)
+            }
+            Expression::Call(call) => {
+                needs_parens(&call.function, Precedence::Call, false) || starts(&call.function)
+            }
+            Expression::Index(exp) => {
+                needs_parens(&exp.left, Precedence::Call, false) || starts(&exp.left)
+            }
+            _ => false,
+        }
+    }
+    match stmt {
+        Statement::Expression(stmt) => starts(&stmt.expression),
+        _ => false,
+    }
+}
@@ -0,0 +1,256 @@
+use crate::{
+    annotate, check_types, resolve, Builtin, CancelToken, Capabilities, Diagnostic, Environment,
+    ErrorKind, Evaluator, Halt, IntoValue, Lexer, LimitExceeded, Limits, NativeFunction, Parser,
+    RuntimeError, Severity, Value,
+};
+use std::cell::RefCell;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Duration;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The source did not parse; one message per problem found.
+    Parse(Vec<String>),
+    /// The source has type errors, so none of it was run.
+    Type {
+        diagnostics: Vec<Diagnostic>,
+        source: String,
+    },
+    /// Evaluation produced an error value.
+    Runtime(RuntimeError),
+    /// Evaluation was stopped by one of the interpreter's `Limits`.
+    LimitExceeded(LimitExceeded),
+    /// Evaluation was stopped through the interpreter's `CancelToken`.
+    Cancelled,
+    /// Evaluation ran longer than the interpreter's timeout.
+    Timeout(Duration),
+    /// A script file could not be read.
+    Io { path: PathBuf, source: io::Error },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Parse(errors) => write!(f, "{}", errors.join("\n")),
+            Error::Type { diagnostics, .. } => {
+                let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+                write!(f, "{}", messages.join("\n"))
+            }
+            Error::Runtime(err) => write!(f, "{}", err),
+            Error::LimitExceeded(exceeded) => write!(f, "{}", exceeded),
+            Error::Cancelled => write!(f, "{}", Halt::Cancelled),
+            Error::Timeout(timeout) => write!(f, "{}", Halt::Timeout(*timeout)),
+            Error::Io { path, source } => {
+                write!(f, "could not read {}: {}", path.display(), source)
+            }
+        }
+    }
+}
+
+impl Error {
+    /// The error as shown to users: runtime and type errors as
+    /// diagnostics pointing into the source, everything else as its
+    /// message.
+    pub fn render(&self) -> String {
+        match self {
+            Error::Runtime(err) => err.render(),
+            Error::Type {
+                diagnostics,
+                source,
+            } => {
+                let rendered: Vec<String> = diagnostics.iter().map(|d| d.render(source)).collect();
+                rendered.join("\n")
+            }
+            err => err.to_string(),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Runtime(err) => Some(err),
+            Error::Io { source, .. } => Some(source),
+            Error::LimitExceeded(exceeded) => Some(exceeded),
+            _ => None,
+        }
+    }
+}
+
+/// Entry point for host applications: evaluates Monkey code against a
+/// global environment that persists between calls.
+///
+/// ```
+/// use monkey::{Interpreter, Value};
+///
+/// let mut interpreter = Interpreter::new();
+/// interpreter.set_global("base", Value::Integer(40));
+/// interpreter.eval_str("let add = fn(x) { base + x };").unwrap();
+/// let result = interpreter.call_function("add", vec![Value::Integer(2)]);
+/// assert_eq!(result.unwrap(), Value::Integer(42));
+/// ```
+#[derive(Default)]
+pub struct Interpreter {
+    env: Rc<RefCell<Environment>>,
+    pub evaluator: Evaluator,
+}
+
+impl Interpreter {
+    /// An interpreter with no limits whose builtins may do anything,
+    /// including reading and writing any file: only for trusted code.
+    pub fn new() -> Interpreter {
+        Interpreter {
+            env: Rc::new(RefCell::new(Environment::new())),
+            evaluator: Evaluator::new(),
+        }
+    }
+
+    /// An interpreter for untrusted code, see `Limits`.
+    pub fn with_limits(limits: Limits) -> Interpreter {
+        let mut interpreter = Interpreter::new();
+        interpreter.evaluator.limits = limits;
+        interpreter
+    }
+
+    /// An interpreter whose I/O builtins may only do what `capabilities`
+    /// allows.
+    pub fn with_capabilities(capabilities: Capabilities) -> Interpreter {
+        let mut interpreter = Interpreter::new();
+        interpreter.evaluator.capabilities = capabilities;
+        interpreter
+    }
+
+    /// Stop every evaluation that runs longer than `timeout`.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.evaluator.timeout = timeout;
+    }
+
+    /// A handle that stops the running evaluation when cancelled, from
+    /// any thread:
+    ///
+    /// ```
+    /// use monkey::{Error, Interpreter};
+    ///
+    /// let mut interpreter = Interpreter::new();
+    /// let token = interpreter.cancel_token();
+    /// std::thread::spawn(move || token.cancel()).join().unwrap();
+    /// let result = interpreter.eval_str("1 + 1");
+    /// assert!(matches!(result, Err(Error::Cancelled)));
+    /// ```
+    ///
+    /// The token stays cancelled until it is reset.
+    pub fn cancel_token(&self) -> CancelToken {
+        self.evaluator.cancel.clone()
+    }
+
+    /// The global environment, shared with every function defined in it.
+    pub fn env(&self) -> &Rc<RefCell<Environment>> {
+        &self.env
+    }
+
+    /// Parse, type check and evaluate `input`, returning the value of the
+    /// last statement. Code with type errors is not run at all.
+    pub fn eval_str(&mut self, input: &str) -> Result<Value, Error> {
+        let mut lexer = Lexer::new(input.to_string());
+        let mut parser = Parser::new(&mut lexer);
+        let mut program = parser.parse_program().unwrap();
+        if !parser.errors().is_empty() {
+            return Err(Error::Parse(parser.errors().to_vec()));
+        }
+        let resolution = resolve(&program);
+        let diagnostics: Vec<Diagnostic> = check_types(&program, &resolution)
+            .diagnostics()
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .cloned()
+            .collect();
+        if !diagnostics.is_empty() {
+            return Err(Error::Type {
+                diagnostics,
+                source: input.to_string(),
+            });
+        }
+        annotate(&mut program, &resolution);
+        let value = self.evaluator.eval_program(&program, &self.env);
+        self.result_of(value)
+    }
+
+    pub fn eval_file(&mut self, path: impl AsRef<Path>) -> Result<Value, Error> {
+        let path = path.as_ref();
+        let codes = fs::read_to_string(path).map_err(|source| Error::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        self.eval_str(&codes)
+    }
+
+    pub fn set_global(&mut self, name: &str, value: impl IntoValue) {
+        self.env
+            .borrow_mut()
+            .set(name.to_string(), value.into_value());
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        self.env.borrow().get(name)
+    }
+
+    /// Expose a Rust closure to scripts as a builtin. Arguments are
+    /// checked and converted with `FromValue`, the result with `IntoValue`:
+    ///
+    /// ```
+    /// use monkey::{Interpreter, Value};
+    ///
+    /// let mut interpreter = Interpreter::new();
+    /// interpreter.register_fn("repeat", |s: String, n: i64| s.repeat(n as usize));
+    /// let result = interpreter.eval_str(r#"repeat("ab", 3)"#).unwrap();
+    /// assert_eq!(result, Value::String("ababab".to_string()));
+    /// ```
+    pub fn register_fn<Args, F: NativeFunction<Args>>(&mut self, name: &str, func: F) {
+        self.evaluator.builtins.register_fn(name, func);
+    }
+
+    /// Add a builtin made elsewhere, e.g. by a `#[monkey_fn]` function.
+    pub fn register_builtin(&mut self, builtin: Builtin) {
+        self.evaluator.builtins.insert(builtin);
+    }
+
+    /// Call a global function or builtin by name.
+    pub fn call_function(&mut self, name: &str, args: Vec<Value>) -> Result<Value, Error> {
+        let function = match self
+            .get_global(name)
+            .or_else(|| self.evaluator.builtins.get(name))
+        {
+            Some(function) => function,
+            None => {
+                return Err(Error::Runtime(RuntimeError::new(
+                    ErrorKind::UndefinedIdentifier,
+                    format!("identifier not found: {}", name),
+                )))
+            }
+        };
+        self.evaluator.reset_usage();
+        let value = self.evaluator.apply_function(function, args);
+        self.result_of(value)
+    }
+
+    /// Drop every global binding.
+    pub fn reset(&mut self) {
+        *self.env.borrow_mut() = Environment::new();
+    }
+
+    fn result_of(&self, value: Value) -> Result<Value, Error> {
+        match value {
+            Value::Error(err) => match self.evaluator.halted() {
+                Some(Halt::Limit(exceeded)) => Err(Error::LimitExceeded(exceeded)),
+                Some(Halt::Cancelled) => Err(Error::Cancelled),
+                Some(Halt::Timeout(timeout)) => Err(Error::Timeout(timeout)),
+                None => Err(Error::Runtime(*err)),
+            },
+            value => Ok(value),
+        }
+    }
+}
@@ -1,10 +1,11 @@
-use crate::{Token, TokenType};
+use crate::{Comment, Span, Token, TokenType};
 
 pub struct Lexer {
     input: String,
     position: i32,
     read_position: i32,
     ch: Option<char>, //current char
+    comments: Vec<Comment>,
 }
 
 impl Lexer {
@@ -14,68 +15,71 @@ impl Lexer {
             position: 0,
             read_position: 0,
             ch: None,
+            comments: Vec::new(),
         };
         lexer.read_char();
         lexer
     }
 
     pub fn read_char(&mut self) -> &Lexer {
-        if self.read_position >= self.input.len() as i32 {
-            self.ch = None
-        } else {
-            //TODO:better index char
-            let char_indices = self.input.char_indices();
-            for c in char_indices {
-                if c.0 == self.read_position as usize {
-                    self.ch = Option::from(c.1);
-                    break;
-                }
-            }
-        }
+        self.ch = self
+            .input
+            .get(self.read_position as usize..)
+            .and_then(|rest| rest.chars().next());
         self.position = self.read_position;
-        self.read_position += 1;
+        // step over the whole utf-8 sequence so positions stay on char boundaries
+        self.read_position += self.ch.map_or(1, |ch| ch.len_utf8()) as i32;
         self
     }
 
     // peek forward char
-    pub fn peek_char(&mut self) -> Option<char> {
-        if self.read_position >= self.input.len() as i32 {
-            None
-        } else {
-            //TODO:better index char
-            let char_indices = self.input.char_indices();
-            let mut res = None;
-            for c in char_indices {
-                if c.0 == self.read_position as usize {
-                    res = Some(c.1);
-                }
-            }
-            res
-        }
+    pub fn peek_char(&self) -> Option<char> {
+        self.input
+            .get(self.read_position as usize..)
+            .and_then(|rest| rest.chars().next())
+    }
+
+    /// Byte offset of the current char, i.e. just past the last token read.
+    pub fn position(&self) -> usize {
+        self.position as usize
     }
 
+    /// Skip whitespace and comments, keeping the comments in `comments`.
     pub fn skip_white_space(&mut self) {
-        if self.ch != None {
-            let mut ch = self.ch.unwrap();
-            while ch == ' ' || ch == '\t' || ch == '\n' || ch == '\r' {
-                self.read_char();
-                if self.ch != None {
-                    ch = self.ch.unwrap();
-                } else {
-                    break;
+        loop {
+            match self.ch {
+                Some(' ' | '\t' | '\n' | '\r') => {
+                    self.read_char();
                 }
+                Some('/') if self.peek_char() == Some('/') => self.read_comment(),
+                _ => break,
             }
         }
     }
 
+    fn read_comment(&mut self) {
+        let start = self.offset();
+        while !matches!(self.ch, Some('\n') | None) {
+            self.read_char();
+        }
+        let text = self.input[start..self.offset()].trim_end_matches('\r');
+        self.comments.push(Comment {
+            text: text.to_string(),
+            span: Span::new(start, start + text.len()),
+        });
+    }
+
+    /// The comments skipped so far, in source order.
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
+
     pub fn is_letter(&self) -> bool {
-        let ch = self.ch.unwrap();
-        'a' <= ch && ch <= 'z' || 'A' <= ch && ch <= 'Z' || ch == '_'
+        matches!(self.ch, Some(ch) if ch.is_ascii_alphabetic() || ch == '_')
     }
 
     pub fn is_digit(&self) -> bool {
-        let ch = self.ch.unwrap();
-        '0' <= ch && ch <= '9'
+        matches!(self.ch, Some(ch) if ch.is_ascii_digit())
     }
 
     pub fn read_identifier(&mut self) -> String {
@@ -95,18 +99,53 @@ impl Lexer {
         String::from(&self.input[prev_position as usize..(self.position) as usize])
     }
 
+    // read up to the closing quote, or the end of input if there is none
+    pub fn read_string(&mut self) -> String {
+        let prev_position = self.position + 1;
+        loop {
+            self.read_char();
+            if self.ch == Some('"') || self.ch.is_none() {
+                break;
+            }
+        }
+        String::from(&self.input[prev_position as usize..self.position as usize])
+    }
+
+    /// The current position, kept within the input once past its end.
+    fn offset(&self) -> usize {
+        (self.position as usize).min(self.input.len())
+    }
+
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
     pub fn next_token(&mut self) -> Token {
-        let tok: Token;
         self.skip_white_space();
+        let start = self.offset();
+        let mut tok = self.read_token();
+        tok.span = Span::new(start, self.offset());
+        tok
+    }
+
+    fn read_token(&mut self) -> Token {
+        let tok: Token;
         match self.ch {
-            Some('-') => tok = Token::new(TokenType::MINUS, self.ch.unwrap().to_string()),
+            Some('-') => {
+                if self.peek_char() == Some('>') {
+                    self.read_char();
+                    tok = Token::new(TokenType::ARROW, "->".to_string());
+                } else {
+                    tok = Token::new(TokenType::MINUS, self.ch.unwrap().to_string())
+                }
+            }
             Some('<') => tok = Token::new(TokenType::LT, self.ch.unwrap().to_string()),
             Some('>') => tok = Token::new(TokenType::GT, self.ch.unwrap().to_string()),
             Some('*') => tok = Token::new(TokenType::ASTERISK, self.ch.unwrap().to_string()),
             Some('/') => tok = Token::new(TokenType::SLASH, self.ch.unwrap().to_string()),
             Some('!') => {
                 if self.peek_char() == Some('=') {
-                    let ch = self.ch.clone();
+                    let ch = self.ch;
                     self.read_char();
                     let literal: String = format!("{}{}", ch.unwrap(), self.ch.unwrap());
                     tok = Token::new(TokenType::NotEq, literal);
@@ -116,7 +155,7 @@ impl Lexer {
             }
             Some('=') => {
                 if self.peek_char() == Some('=') {
-                    let ch = self.ch.clone();
+                    let ch = self.ch;
                     self.read_char();
                     let literal: String = format!("{}{}", ch.unwrap(), self.ch.unwrap());
                     tok = Token::new(TokenType::EQ, literal);
@@ -128,9 +167,20 @@ impl Lexer {
             Some('(') => tok = Token::new(TokenType::LPAREN, self.ch.unwrap().to_string()),
             Some(')') => tok = Token::new(TokenType::RPAREN, self.ch.unwrap().to_string()),
             Some(',') => tok = Token::new(TokenType::COMMA, self.ch.unwrap().to_string()),
+            Some(':') => tok = Token::new(TokenType::COLON, self.ch.unwrap().to_string()),
             Some('+') => tok = Token::new(TokenType::PLUS, self.ch.unwrap().to_string()),
             Some('{') => tok = Token::new(TokenType::LBRACE, self.ch.unwrap().to_string()),
             Some('}') => tok = Token::new(TokenType::RBRACE, self.ch.unwrap().to_string()),
+            Some('[') => tok = Token::new(TokenType::LBRACKET, self.ch.unwrap().to_string()),
+            Some(']') => tok = Token::new(TokenType::RBRACKET, self.ch.unwrap().to_string()),
+            Some('"') => {
+                let value = self.read_string();
+                tok = match self.ch {
+                    Some(_) => Token::new(TokenType::STRING, value),
+                    // no closing quote: the parser reports it as unterminated
+                    None => Token::new(TokenType::ILLEGAL, format!("\"{}", value)),
+                };
+            }
             None => {
                 tok = Token::new(TokenType::EOF, "".to_string());
             }
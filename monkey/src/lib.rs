@@ -1,11 +1,38 @@
+#![allow(clippy::module_inception)]
+
 pub mod ast;
+pub mod builtins;
+pub mod diagnostics;
+pub mod evaluator;
+pub mod formatter;
+pub mod interpreter;
 pub mod lexer;
+pub mod lint;
+#[cfg(feature = "lsp")]
+pub mod lsp;
 pub mod parser;
 pub mod repl;
+pub mod resolver;
+pub mod syntax;
 pub mod token;
+pub mod types;
+pub mod value;
 
 pub use ast::*;
+pub use builtins::*;
+pub use diagnostics::*;
+pub use evaluator::*;
+pub use formatter::*;
+pub use interpreter::*;
 pub use lexer::*;
+pub use lint::*;
+#[cfg(feature = "lsp")]
+pub use lsp::*;
+pub use monkey_macros::{monkey_fn, MonkeyValue};
 pub use parser::*;
 pub use repl::*;
+pub use resolver::*;
+pub use syntax::*;
 pub use token::*;
+pub use types::*;
+pub use value::*;
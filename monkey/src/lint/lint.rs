@@ -0,0 +1,229 @@
+//! Running the lint rules over a file, configured per rule and silenced
+//! by `// monkey-allow(rule)` comments.
+
+use super::rules::check_rules;
+use crate::{
+    check_types, line_column, parse_syntax, resolve, Builtins, Diagnostic, Lexer, Severity,
+    TokenType,
+};
+use std::collections::HashMap;
+
+/// A check `lint` runs, known by its id in configuration, in
+/// `monkey-allow` comments and in the diagnostics it reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rule {
+    pub id: &'static str,
+    pub description: &'static str,
+    /// How it is reported unless configured otherwise.
+    pub default: Severity,
+}
+
+/// Every rule, those from name resolution first and type checking last.
+pub const RULES: &[Rule] = &[
+    Rule {
+        id: "use-before-definition",
+        description: "a name used before the `let` that defines it",
+        default: Severity::Error,
+    },
+    Rule {
+        id: "undefined-name",
+        description: "a name that is neither bound nor a builtin",
+        default: Severity::Error,
+    },
+    Rule {
+        id: "shadowing",
+        description: "a binding that hides one in an enclosing function",
+        default: Severity::Warning,
+    },
+    Rule {
+        id: "unused-binding",
+        description: "a binding that is never used",
+        default: Severity::Warning,
+    },
+    Rule {
+        id: "constant-condition",
+        description: "an `if` whose condition is always true or always false",
+        default: Severity::Warning,
+    },
+    Rule {
+        id: "bool-comparison",
+        description: "comparing with `true` or `false`",
+        default: Severity::Warning,
+    },
+    Rule {
+        id: "unreachable-code",
+        description: "statements after a `return` or `throw`",
+        default: Severity::Warning,
+    },
+    Rule {
+        id: "self-assignment",
+        description: "`let x = x;`",
+        default: Severity::Warning,
+    },
+    Rule {
+        id: "suspicious-negation",
+        description: "`!!x`, and `!a == b` where `!(a == b)` may be meant",
+        default: Severity::Warning,
+    },
+    Rule {
+        id: "duplicate-hash-key",
+        description: "a hash literal giving the same key twice",
+        default: Severity::Warning,
+    },
+    Rule {
+        id: "type-mismatch",
+        description: "a value whose type doesn't fit where it is used",
+        default: Severity::Error,
+    },
+    Rule {
+        id: "unknown-type",
+        description: "an annotation naming a type that doesn't exist",
+        default: Severity::Error,
+    },
+];
+
+/// The rule called `id`.
+pub fn rule(id: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|rule| rule.id == id)
+}
+
+/// How severe each rule is, where that differs from its default. `None`
+/// turns a rule off.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LintConfig {
+    levels: HashMap<&'static str, Option<Severity>>,
+}
+
+impl LintConfig {
+    /// Read a configuration file: one `rule = level` per line, where the
+    /// level is `allow`, `warning` or `error`. Blank lines and lines
+    /// starting with `#` are skipped.
+    ///
+    /// ```text
+    /// # scripts may leave things unused
+    /// unused-binding = allow
+    /// constant-condition = error
+    /// ```
+    pub fn parse(text: &str) -> Result<LintConfig, String> {
+        let mut config = LintConfig::default();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (id, level) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected `rule = level`", i + 1))?;
+            let (id, level) = (id.trim(), level.trim());
+            let rule = rule(id).ok_or_else(|| format!("line {}: unknown rule `{}`", i + 1, id))?;
+            let level = match level {
+                "allow" => None,
+                "warning" => Some(Severity::Warning),
+                "error" => Some(Severity::Error),
+                _ => {
+                    return Err(format!(
+                        "line {}: unknown level `{}`, expected allow, warning or error",
+                        i + 1,
+                        level
+                    ))
+                }
+            };
+            config.levels.insert(rule.id, level);
+        }
+        Ok(config)
+    }
+
+    /// Report `rule` as `level`, or not at all for `None`.
+    pub fn set(&mut self, rule: &'static Rule, level: Option<Severity>) {
+        self.levels.insert(rule.id, level);
+    }
+
+    /// How `rule` is reported, or `None` if it is off.
+    pub fn level(&self, rule: &Rule) -> Option<Severity> {
+        self.levels
+            .get(rule.id)
+            .copied()
+            .unwrap_or(Some(rule.default))
+    }
+}
+
+/// Check `source` against every rule `config` leaves on. A file that
+/// doesn't parse gets only its parse errors.
+///
+/// A `// monkey-allow(rule, ...)` comment silences the rules it names
+/// on its own line, or on the next line when it has a line to itself.
+pub fn lint(source: &str, config: &LintConfig) -> Vec<Diagnostic> {
+    let tree = parse_syntax(source);
+    let program = match tree.to_program() {
+        Ok(program) => program,
+        Err(_) if !tree.errors().is_empty() => return tree.errors().to_vec(),
+        // whatever building the AST still rejects is an error too
+        Err(messages) => return messages.into_iter().map(Diagnostic::error).collect(),
+    };
+    let resolution = resolve(&program);
+    let types = check_types(&program, &resolution);
+    let (allowed, mut diagnostics) = allow_comments(source);
+
+    let found = resolution
+        .diagnostics(&program, &Builtins::new())
+        .into_iter()
+        .chain(check_rules(&program, &resolution))
+        .chain(types.diagnostics().iter().cloned());
+    for mut diagnostic in found {
+        let rule = match diagnostic.code.and_then(rule) {
+            Some(rule) => rule,
+            None => {
+                diagnostics.push(diagnostic);
+                continue;
+            }
+        };
+        let line = diagnostic
+            .span
+            .map(|span| line_column(source, span.start).0);
+        if line.map_or(false, |line| allowed.contains(&(line, rule.id))) {
+            continue;
+        }
+        if let Some(severity) = config.level(rule) {
+            diagnostic.severity = severity;
+            diagnostics.push(diagnostic);
+        }
+    }
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span.map(|span| span.start));
+    diagnostics
+}
+
+/// The `(line, rule)` pairs `monkey-allow` comments silence, and
+/// warnings about the rules they name that don't exist.
+fn allow_comments(source: &str) -> (Vec<(usize, &'static str)>, Vec<Diagnostic>) {
+    let mut lexer = Lexer::new(source.to_string());
+    while lexer.next_token().r#type != TokenType::EOF {}
+
+    let mut allowed = Vec::new();
+    let mut unknown = Vec::new();
+    for comment in lexer.comments() {
+        let rules = match comment.text[2..]
+            .trim()
+            .strip_prefix("monkey-allow(")
+            .and_then(|rest| rest.split_once(')'))
+        {
+            Some((rules, _)) => rules,
+            None => continue,
+        };
+        let (line, _) = line_column(source, comment.span.start);
+        let line_start = source[..comment.span.start]
+            .rfind('\n')
+            .map_or(0, |i| i + 1);
+        let own_line = source[line_start..comment.span.start].trim().is_empty();
+        let line = if own_line { line + 1 } else { line };
+        for id in rules.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+            match rule(id) {
+                Some(rule) => allowed.push((line, rule.id)),
+                None => unknown.push(
+                    Diagnostic::warning(format!("unknown lint rule `{}`", id))
+                        .with_span(comment.span),
+                ),
+            }
+        }
+    }
+    (allowed, unknown)
+}
@@ -0,0 +1,4 @@
+pub mod lint;
+mod rules;
+
+pub use lint::*;
@@ -0,0 +1,232 @@
+//! The lint rules beyond name resolution. Each reports warnings with
+//! its rule id as the code; `lint` applies the configured severity.
+
+use crate::{
+    line_column, walk_expression, walk_let_statement, BlockStatement, Diagnostic, Expression,
+    HashLiteral, IfExpression, InfixExpression, LetStatement, Node, NodeRef, Program, Resolution,
+    Span, Statement, Visitor,
+};
+
+/// Run every rule over `program`.
+pub(crate) fn check_rules(program: &Program, resolution: &Resolution) -> Vec<Diagnostic> {
+    let mut checker = Checker {
+        program,
+        resolution,
+        diagnostics: Vec::new(),
+    };
+    checker.statements(&program.statements);
+    checker.visit_program(program);
+    checker.diagnostics
+}
+
+struct Checker<'a> {
+    program: &'a Program,
+    resolution: &'a Resolution,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Checker<'_> {
+    fn report(&mut self, code: &'static str, span: Span, message: String) -> &mut Diagnostic {
+        self.diagnostics
+            .push(Diagnostic::warning(message).with_code(code).with_span(span));
+        self.diagnostics.last_mut().expect("just pushed")
+    }
+
+    fn location(&self, span: Span) -> String {
+        let (line, column) = line_column(&self.program.source, span.start);
+        format!("{}:{}", line, column)
+    }
+
+    /// `unreachable-code`: whatever follows a `return` or `throw` in the
+    /// same list of statements.
+    fn statements(&mut self, statements: &[Statement]) {
+        let exit = statements
+            .iter()
+            .position(|stmt| matches!(stmt, Statement::Return(_) | Statement::Throw(_)));
+        let (exit, rest) = match exit {
+            Some(i) if i + 1 < statements.len() => (&statements[i], &statements[i + 1..]),
+            _ => return,
+        };
+        let span = NodeRef::Statement(&rest[0])
+            .extent()
+            .to(NodeRef::Statement(&rest[rest.len() - 1]).extent());
+        let keyword = exit.token_literal();
+        let note = format!(
+            "it follows the `{}` at {}",
+            keyword,
+            self.location(exit.span())
+        );
+        self.report("unreachable-code", span, "unreachable code".to_string())
+            .notes
+            .push(note);
+    }
+
+    /// `constant-condition`
+    fn if_expression(&mut self, exp: &IfExpression) {
+        let value = match constant(&exp.condition) {
+            Some(value) => value,
+            None => return,
+        };
+        let span = NodeRef::Expression(&exp.condition).extent();
+        let diagnostic = self.report(
+            "constant-condition",
+            span,
+            format!("this condition is always {}", value),
+        );
+        if !value {
+            diagnostic
+                .notes
+                .push("so the block after it never runs".to_string());
+        } else if exp.alternative.is_some() {
+            diagnostic
+                .notes
+                .push("so the `else` block never runs".to_string());
+        }
+    }
+
+    /// `bool-comparison`, and the comparison half of `suspicious-negation`.
+    fn infix_expression(&mut self, exp: &InfixExpression) {
+        // Only worked out when there is something to report: on a long
+        // chain like `1 + 2 + ... + n` doing it for every operator would
+        // take quadratic time.
+        let span = || {
+            NodeRef::Expression(&exp.left)
+                .extent()
+                .to(NodeRef::Expression(&exp.right).extent())
+        };
+        let equality = exp.operator == "==" || exp.operator == "!=";
+        let literal = match (&*exp.left, &*exp.right) {
+            (Expression::Boolean(lit), _) | (_, Expression::Boolean(lit)) if equality => {
+                Some(lit.value)
+            }
+            _ => None,
+        };
+        if let Some(value) = literal {
+            let plain = (exp.operator == "==") == value;
+            let note = if plain {
+                "use the other side as it is"
+            } else {
+                "negate the other side with `!` instead"
+            };
+            self.report(
+                "bool-comparison",
+                span(),
+                format!("comparing with `{}` is redundant", value),
+            )
+            .notes
+            .push(note.to_string());
+        }
+
+        let comparison = equality || exp.operator == "<" || exp.operator == ">";
+        if let Expression::Prefix(prefix) = &*exp.left {
+            if comparison && prefix.operator == "!" {
+                let note = format!(
+                    "`!` binds tighter than `{}`; write `!(a {} b)` to negate the comparison",
+                    exp.operator, exp.operator
+                );
+                self.report(
+                    "suspicious-negation",
+                    span(),
+                    "`!` negates only the left-hand side".to_string(),
+                )
+                .notes
+                .push(note);
+            }
+        }
+    }
+
+    /// `duplicate-hash-key`
+    fn hash_literal(&mut self, hash: &HashLiteral) {
+        let mut seen: Vec<(String, Span)> = Vec::new();
+        for (key, _) in &hash.pairs {
+            let text = match key {
+                Expression::Integer(lit) => lit.value.to_string(),
+                Expression::String(lit) => format!("{:?}", lit.value),
+                Expression::Boolean(lit) => lit.value.to_string(),
+                _ => continue,
+            };
+            let span = key.span();
+            match seen.iter().find(|(seen, _)| *seen == text) {
+                Some(&(_, first)) => {
+                    let note = format!("it is first given at {}", self.location(first));
+                    self.report(
+                        "duplicate-hash-key",
+                        span,
+                        format!("duplicate key `{}` in hash literal", text),
+                    )
+                    .notes
+                    .push(note);
+                }
+                None => seen.push((text, span)),
+            }
+        }
+    }
+}
+
+impl<'a> Visitor<'a> for Checker<'_> {
+    fn visit_block_statement(&mut self, block: &'a BlockStatement) {
+        self.statements(&block.statements);
+        crate::walk_block_statement(self, block);
+    }
+
+    /// `self-assignment`
+    fn visit_let_statement(&mut self, stmt: &'a LetStatement) {
+        if let Expression::Identifier(value) = &stmt.value {
+            if value.value == stmt.name.value && self.resolution.binding_of(value.id).is_some() {
+                self.report(
+                    "self-assignment",
+                    stmt.name.span().to(value.span()),
+                    format!("`{}` is assigned to itself", value.value),
+                );
+            }
+        }
+        walk_let_statement(self, stmt);
+    }
+
+    fn visit_expression(&mut self, exp: &'a Expression) {
+        match exp {
+            Expression::If(exp) => self.if_expression(exp),
+            Expression::Infix(exp) => self.infix_expression(exp),
+            Expression::Hash(hash) => self.hash_literal(hash),
+            // `suspicious-negation`: one report for a whole chain of `!`s
+            Expression::Prefix(prefix) if prefix.operator == "!" => {
+                let mut operand = &*prefix.right;
+                while let Expression::Prefix(inner) = operand {
+                    if inner.operator != "!" {
+                        break;
+                    }
+                    operand = &inner.right;
+                }
+                if !std::ptr::eq(operand, &*prefix.right) {
+                    self.report(
+                        "suspicious-negation",
+                        NodeRef::Expression(exp).extent(),
+                        "`!` is applied more than once".to_string(),
+                    )
+                    .notes
+                    .push("`!!x` is just whether `x` is truthy".to_string());
+                    return self.visit_expression(operand);
+                }
+            }
+            _ => {}
+        }
+        walk_expression(self, exp);
+    }
+}
+
+/// The value of a condition that doesn't depend on anything, going by
+/// the evaluator's rule that all but `false` and `null` are true.
+fn constant(exp: &Expression) -> Option<bool> {
+    match exp {
+        Expression::Boolean(lit) => Some(lit.value),
+        Expression::Integer(_)
+        | Expression::String(_)
+        | Expression::Array(_)
+        | Expression::Hash(_)
+        | Expression::Function(_) => Some(true),
+        #[cfg(feature = "bigint")]
+        Expression::BigInteger(_) => Some(true),
+        Expression::Prefix(exp) if exp.operator == "!" => constant(&exp.right).map(|value| !value),
+        _ => None,
+    }
+}
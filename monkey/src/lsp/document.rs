@@ -0,0 +1,274 @@
+//! What the language server knows about one open file. Everything here
+//! works in byte offsets; the server converts to and from LSP positions.
+
+use crate::{
+    check_types, format_source, lint, parse_syntax, resolve, walk_let_statement, BindingId,
+    BindingKind, Builtins, Diagnostic, Expression, Identifier, LetStatement, LintConfig, Node,
+    NodeRef, Program, Resolution, Span, Statement, SyntaxKind, TypeCheck, Visitor,
+};
+use std::collections::HashMap;
+
+/// A line and column as LSP counts them: from 0, with columns in UTF-16
+/// code units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    pub character: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Variable,
+}
+
+/// A `let` binding, with the bindings made inside its value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// The whole `let` statement.
+    pub span: Span,
+    /// Just the name.
+    pub name_span: Span,
+    pub children: Vec<Symbol>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hover {
+    /// Markdown.
+    pub contents: String,
+    pub span: Span,
+}
+
+pub struct Document {
+    text: String,
+    /// `None` while the text doesn't parse.
+    program: Option<Program>,
+    resolution: Resolution,
+    types: TypeCheck,
+    diagnostics: Vec<Diagnostic>,
+    /// The extent of each `let` statement, by where it starts.
+    let_spans: HashMap<usize, Span>,
+}
+
+impl Document {
+    pub fn new(text: impl Into<String>) -> Document {
+        let text = text.into();
+        let tree = parse_syntax(&text);
+        let program = tree.to_program().ok();
+        let resolution = program.as_ref().map(resolve).unwrap_or_default();
+        let types = program
+            .as_ref()
+            .map(|program| check_types(program, &resolution))
+            .unwrap_or_default();
+        let diagnostics = lint(&text, &LintConfig::default());
+        let let_spans = tree
+            .root()
+            .descendants()
+            .into_iter()
+            .filter(|node| node.kind() == SyntaxKind::LetStatement)
+            .map(|node| (node.span().start, node.span()))
+            .collect();
+        Document {
+            text,
+            program,
+            resolution,
+            types,
+            diagnostics,
+            let_spans,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Parse errors, or once the document parses, what the lint rules
+    /// find with their default configuration.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// The document formatted, or `None` if it doesn't parse.
+    pub fn format(&self) -> Option<String> {
+        format_source(&self.text).ok()
+    }
+
+    /// The binding the identifier at `offset` refers to, with that
+    /// identifier's span.
+    fn binding_at(&self, offset: usize) -> Option<(BindingId, Span)> {
+        let ident = self.identifier_at(offset)?;
+        Some((self.resolution.binding_of(ident.id)?, ident.span()))
+    }
+
+    fn identifier_at(&self, offset: usize) -> Option<&Identifier> {
+        let mut identifiers = Identifiers(Vec::new());
+        identifiers.visit_program(self.program.as_ref()?);
+        identifiers.0.into_iter().find(|ident| {
+            let span = ident.span();
+            span.start <= offset && offset <= span.end
+        })
+    }
+
+    /// Where the identifier at `offset` is bound.
+    pub fn definition(&self, offset: usize) -> Option<Span> {
+        let (binding, _) = self.binding_at(offset)?;
+        Some(self.resolution.binding(binding).span)
+    }
+
+    /// Every use of the binding the identifier at `offset` refers to, in
+    /// source order, led by the binding itself if `include_declaration`.
+    pub fn references(&self, offset: usize, include_declaration: bool) -> Vec<Span> {
+        let (binding, _) = match self.binding_at(offset) {
+            Some(found) => found,
+            None => return Vec::new(),
+        };
+        let program = self.program.as_ref().expect("resolved without a program");
+        let nodes = program.index();
+        let declaration = include_declaration.then(|| self.resolution.binding(binding).span);
+        declaration
+            .into_iter()
+            .chain(
+                self.resolution
+                    .references(binding)
+                    .iter()
+                    .filter_map(|&id| nodes.get(id))
+                    .map(|node| node.span()),
+            )
+            .collect()
+    }
+
+    pub fn hover(&self, offset: usize) -> Option<Hover> {
+        let ident = self.identifier_at(offset)?;
+        let span = ident.span();
+        let binding = match self.resolution.binding_of(ident.id) {
+            Some(binding) => self.resolution.binding(binding),
+            None => {
+                let builtins = Builtins::new();
+                let doc = builtins.doc(&ident.value)?;
+                return Some(Hover {
+                    contents: format!("```monkey\nbuiltin {}\n```\n{}", ident.value, doc),
+                    span,
+                });
+            }
+        };
+        let program = self.program.as_ref()?;
+        let mut contents = match binding.kind {
+            BindingKind::Let => {
+                let stmt = match program.node(binding.owner)? {
+                    NodeRef::Statement(Statement::Let(stmt)) => stmt,
+                    _ => return None,
+                };
+                format!("```monkey\n{}\n```", self.let_summary(stmt))
+            }
+            BindingKind::Parameter => format!("```monkey\nparameter {}\n```", binding.name),
+            BindingKind::Catch => format!("```monkey\ncatch {}\n```", binding.name),
+        };
+        // as the type checker found it, the same as lint reports against
+        if let Some(ty) = self.types.type_of(binding.ident) {
+            contents += &format!("\n`{}`", ty);
+        }
+        Some(Hover { contents, span })
+    }
+
+    /// The first line of a `let` statement, without its semicolon.
+    fn let_summary(&self, stmt: &LetStatement) -> String {
+        let span = self.let_span(stmt);
+        let text = self.text[span.start..span.end].trim_end();
+        let text = text.strip_suffix(';').unwrap_or(text).trim_end();
+        match text.split_once('\n') {
+            Some((first, _)) => format!("{} …", first.trim_end()),
+            None => text.to_string(),
+        }
+    }
+
+    fn let_span(&self, stmt: &LetStatement) -> Span {
+        self.let_spans
+            .get(&stmt.token.span.start)
+            .copied()
+            .unwrap_or(stmt.token.span)
+    }
+
+    /// The document's `let` bindings, nested by the function values they
+    /// are made in.
+    pub fn symbols(&self) -> Vec<Symbol> {
+        let mut symbols = Symbols {
+            document: self,
+            levels: vec![Vec::new()],
+        };
+        if let Some(program) = &self.program {
+            symbols.visit_program(program);
+        }
+        symbols.levels.pop().unwrap_or_default()
+    }
+
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.text.len());
+        let before = &self.text[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Position {
+            line: before.matches('\n').count(),
+            character: before[line_start..].encode_utf16().count(),
+        }
+    }
+
+    /// The byte offset of `position`, clamped to the end of its line and
+    /// of the document.
+    pub fn offset(&self, position: Position) -> usize {
+        let mut line_start = 0;
+        for _ in 0..position.line {
+            match self.text[line_start..].find('\n') {
+                Some(i) => line_start += i + 1,
+                None => return self.text.len(),
+            }
+        }
+        let line = self.text[line_start..].split('\n').next().unwrap_or("");
+        let mut units = 0;
+        for (i, c) in line.char_indices() {
+            if units >= position.character {
+                return line_start + i;
+            }
+            units += c.len_utf16();
+        }
+        line_start + line.len()
+    }
+}
+
+/// Collects every identifier, binding or used.
+struct Identifiers<'a>(Vec<&'a Identifier>);
+
+impl<'a> Visitor<'a> for Identifiers<'a> {
+    fn visit_identifier(&mut self, ident: &'a Identifier) {
+        self.0.push(ident);
+    }
+}
+
+struct Symbols<'d> {
+    document: &'d Document,
+    /// The symbols found so far at each level of nesting.
+    levels: Vec<Vec<Symbol>>,
+}
+
+impl<'a> Visitor<'a> for Symbols<'_> {
+    fn visit_let_statement(&mut self, stmt: &'a LetStatement) {
+        self.levels.push(Vec::new());
+        walk_let_statement(self, stmt);
+        let children = self.levels.pop().unwrap_or_default();
+        let kind = match stmt.value {
+            Expression::Function(_) => SymbolKind::Function,
+            _ => SymbolKind::Variable,
+        };
+        let symbol = Symbol {
+            name: stmt.name.value.clone(),
+            kind,
+            span: self.document.let_span(stmt),
+            name_span: stmt.name.span(),
+            children,
+        };
+        self.levels
+            .last_mut()
+            .expect("no level to add a symbol to")
+            .push(symbol);
+    }
+}
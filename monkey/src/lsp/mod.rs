@@ -0,0 +1,5 @@
+pub mod document;
+pub mod server;
+
+pub use document::*;
+pub use server::*;
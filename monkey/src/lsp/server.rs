@@ -0,0 +1,307 @@
+//! A language server speaking LSP's JSON-RPC over a pair of streams,
+//! normally stdin and stdout. Documents are synced whole on every change.
+
+use crate::{Diagnostic, Document, Position, Severity, Span, Symbol, SymbolKind};
+use serde_json::{json, Value as Json};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Serve requests from `input` until the client sends `exit` or closes
+/// the stream.
+pub fn run_server(input: impl BufRead, output: impl Write) -> io::Result<()> {
+    Server {
+        input,
+        output,
+        documents: HashMap::new(),
+        shut_down: false,
+    }
+    .run()
+}
+
+struct Server<R, W> {
+    input: R,
+    output: W,
+    /// Open documents by URI.
+    documents: HashMap<String, Document>,
+    shut_down: bool,
+}
+
+/// What went wrong handling a request, sent back as its error.
+struct ResponseError {
+    code: i64,
+    message: String,
+}
+
+fn invalid_params(message: &str) -> ResponseError {
+    ResponseError {
+        code: INVALID_PARAMS,
+        message: message.to_string(),
+    }
+}
+
+impl<R: BufRead, W: Write> Server<R, W> {
+    fn run(&mut self) -> io::Result<()> {
+        while let Some(body) = self.read_message()? {
+            let message: Json = match serde_json::from_str(&body) {
+                Ok(message) => message,
+                Err(err) => {
+                    let error = json!({ "code": PARSE_ERROR, "message": err.to_string() });
+                    self.send(json!({ "jsonrpc": "2.0", "id": null, "error": error }))?;
+                    continue;
+                }
+            };
+            let method = message["method"].as_str().unwrap_or("");
+            if method == "exit" {
+                return Ok(());
+            }
+            match message.get("id") {
+                Some(id) if !method.is_empty() => {
+                    let id = id.clone();
+                    let response = match self.request(method, &message["params"]) {
+                        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                        Err(err) => {
+                            let error = json!({ "code": err.code, "message": err.message });
+                            json!({ "jsonrpc": "2.0", "id": id, "error": error })
+                        }
+                    };
+                    self.send(response)?;
+                }
+                // responses to requests we never make
+                Some(_) => {}
+                None => self.notification(method, &message["params"])?,
+            }
+        }
+        Ok(())
+    }
+
+    /// The body of the next message, or `None` at the end of the input.
+    fn read_message(&mut self) -> io::Result<Option<String>> {
+        let mut length = None;
+        loop {
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("Content-Length") {
+                    length = value.trim().parse::<usize>().ok();
+                }
+            }
+        }
+        let length = length.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "message without a Content-Length",
+            )
+        })?;
+        let mut body = vec![0; length];
+        self.input.read_exact(&mut body)?;
+        String::from_utf8(body)
+            .map(Some)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    fn send(&mut self, message: Json) -> io::Result<()> {
+        let body = message.to_string();
+        write!(
+            self.output,
+            "Content-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )?;
+        self.output.flush()
+    }
+
+    fn request(&mut self, method: &str, params: &Json) -> Result<Json, ResponseError> {
+        if self.shut_down {
+            return Err(ResponseError {
+                code: INVALID_REQUEST,
+                message: "the server is shutting down".to_string(),
+            });
+        }
+        match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "hoverProvider": true,
+                    "definitionProvider": true,
+                    "referencesProvider": true,
+                    "documentSymbolProvider": true,
+                    "documentFormattingProvider": true,
+                },
+                "serverInfo": { "name": "monkey", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "shutdown" => {
+                self.shut_down = true;
+                Ok(Json::Null)
+            }
+            "textDocument/hover" => {
+                let (_, document, offset) = self.position_params(params)?;
+                Ok(document.hover(offset).map_or(Json::Null, |hover| {
+                    json!({
+                        "contents": { "kind": "markdown", "value": hover.contents },
+                        "range": range(document, hover.span),
+                    })
+                }))
+            }
+            "textDocument/definition" => {
+                let (uri, document, offset) = self.position_params(params)?;
+                Ok(document.definition(offset).map_or(
+                    Json::Null,
+                    |span| json!({ "uri": uri, "range": range(document, span) }),
+                ))
+            }
+            "textDocument/references" => {
+                let (uri, document, offset) = self.position_params(params)?;
+                let include_declaration = params["context"]["includeDeclaration"]
+                    .as_bool()
+                    .unwrap_or(false);
+                let locations: Vec<Json> = document
+                    .references(offset, include_declaration)
+                    .into_iter()
+                    .map(|span| json!({ "uri": uri, "range": range(document, span) }))
+                    .collect();
+                Ok(Json::Array(locations))
+            }
+            "textDocument/documentSymbol" => {
+                let (_, document) = self.document_params(params)?;
+                let symbols = document.symbols();
+                Ok(Json::Array(
+                    symbols.iter().map(|s| symbol(document, s)).collect(),
+                ))
+            }
+            "textDocument/formatting" => {
+                let (_, document) = self.document_params(params)?;
+                Ok(match document.format() {
+                    // one edit replacing the whole document
+                    Some(formatted) if formatted != document.text() => json!([{
+                        "range": range(document, Span::new(0, document.text().len())),
+                        "newText": formatted,
+                    }]),
+                    Some(_) => json!([]),
+                    None => Json::Null,
+                })
+            }
+            _ => Err(ResponseError {
+                code: METHOD_NOT_FOUND,
+                message: format!("method not found: {}", method),
+            }),
+        }
+    }
+
+    fn notification(&mut self, method: &str, params: &Json) -> io::Result<()> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+        let text = match method {
+            "textDocument/didOpen" => params["textDocument"]["text"].as_str(),
+            "textDocument/didChange" => params["contentChanges"]
+                .as_array()
+                .and_then(|changes| changes.last())
+                .and_then(|change| change["text"].as_str()),
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                return self.publish_diagnostics(uri, Vec::new());
+            }
+            _ => return Ok(()),
+        };
+        if let Some(text) = text {
+            let document = Document::new(text);
+            let diagnostics = document
+                .diagnostics()
+                .iter()
+                .map(|d| diagnostic(&document, d))
+                .collect();
+            self.documents.insert(uri.to_string(), document);
+            self.publish_diagnostics(uri, diagnostics)?;
+        }
+        Ok(())
+    }
+
+    fn publish_diagnostics(&mut self, uri: &str, diagnostics: Vec<Json>) -> io::Result<()> {
+        self.send(json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": diagnostics },
+        }))
+    }
+
+    fn document_params(&self, params: &Json) -> Result<(String, &Document), ResponseError> {
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .ok_or_else(|| invalid_params("missing textDocument.uri"))?;
+        let document = self
+            .documents
+            .get(uri)
+            .ok_or_else(|| invalid_params(&format!("{} is not open", uri)))?;
+        Ok((uri.to_string(), document))
+    }
+
+    /// The document a request is about and the offset of its position.
+    fn position_params(&self, params: &Json) -> Result<(String, &Document, usize), ResponseError> {
+        let (uri, document) = self.document_params(params)?;
+        let position = &params["position"];
+        match (position["line"].as_u64(), position["character"].as_u64()) {
+            (Some(line), Some(character)) => {
+                let offset = document.offset(Position {
+                    line: line as usize,
+                    character: character as usize,
+                });
+                Ok((uri, document, offset))
+            }
+            _ => Err(invalid_params("missing position")),
+        }
+    }
+}
+
+fn range(document: &Document, span: Span) -> Json {
+    let start = document.position(span.start);
+    let end = document.position(span.end);
+    json!({
+        "start": { "line": start.line, "character": start.character },
+        "end": { "line": end.line, "character": end.character },
+    })
+}
+
+fn diagnostic(document: &Document, diagnostic: &Diagnostic) -> Json {
+    let severity = match diagnostic.severity {
+        Severity::Error => 1,
+        Severity::Warning => 2,
+    };
+    let mut message = diagnostic.message.clone();
+    for note in &diagnostic.notes {
+        message += &format!("\n{}", note);
+    }
+    let mut json = json!({
+        "range": range(document, diagnostic.span.unwrap_or_default()),
+        "severity": severity,
+        "source": "monkey",
+        "message": message,
+    });
+    if let Some(code) = diagnostic.code {
+        json["code"] = json!(code);
+    }
+    json
+}
+
+fn symbol(document: &Document, symbol: &Symbol) -> Json {
+    // LSP's SymbolKind numbers
+    let kind = match symbol.kind {
+        SymbolKind::Function => 12,
+        SymbolKind::Variable => 13,
+    };
+    json!({
+        "name": symbol.name,
+        "kind": kind,
+        "range": range(document, symbol.span),
+        "selectionRange": range(document, symbol.name_span),
+        "children": symbol.children.iter().map(|s| self::symbol(document, s)).collect::<Vec<_>>(),
+    })
+}
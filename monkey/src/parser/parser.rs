@@ -1,13 +1,24 @@
 use lazy_static::lazy_static;
 use std::collections::HashMap;
 // use derive_more::{Add, Sub, From};
+use crate::evaluator::limits::grow_stack;
 use crate::{
-    Expression, ExpressionStatement, Identifier, InfixExpression, IntegerLiteral, LetStatement,
-    Lexer, PrefixExpression, Program, ReturnStatement, Statement, Token, TokenType,
+    ArrayLiteral, BlockStatement, Boolean, CallExpression, Expression, ExpressionStatement,
+    FunctionLiteral, HashLiteral, Identifier, IfExpression, IndexExpression, InfixExpression,
+    IntegerLiteral, LetStatement, Lexer, NodeId, PrefixExpression, Program, ReturnStatement, Span,
+    Statement, StringLiteral, ThrowStatement, Token, TokenType, TryExpression, TypeAnnotation,
+    TypeAnnotationKind,
 };
+use std::rc::Rc;
 
-type InfixParseFn = fn(&mut Parser, Box<dyn Expression>) -> Box<dyn Expression>;
-type PrefixParseFn = fn(&mut Parser) -> Box<dyn Expression>;
+/// How deeply expressions and types may nest: parentheses, brackets,
+/// braces, prefix operators and function literals each open a level.
+/// Chains such as `1 + 2 + 3` or `f(1)(2)` don't count, however long:
+/// the passes over the tree grow the stack as they recurse along them.
+pub const MAX_NESTING: usize = 256;
+
+type InfixParseFn = fn(&mut Parser, Expression) -> Option<Expression>;
+type PrefixParseFn = fn(&mut Parser) -> Option<Expression>;
 
 #[derive(PartialOrd, PartialEq, Clone, Copy)]
 pub enum Precedence {
@@ -18,6 +29,15 @@ pub enum Precedence {
     Product,     // *
     Prefix,      // -X or !X
     Call,        // myFunction(X)
+    Index,       // array[index]
+}
+
+impl Precedence {
+    /// How tightly an infix operator of `token_type` binds; `Lowest` for
+    /// tokens that aren't infix operators.
+    pub fn of(token_type: TokenType) -> Precedence {
+        *PRECEDENCES.get(&token_type).unwrap_or(&Precedence::Lowest)
+    }
 }
 
 lazy_static! {
@@ -32,6 +52,7 @@ lazy_static! {
         m.insert(TokenType::SLASH, Precedence::Product);
         m.insert(TokenType::ASTERISK, Precedence::Product);
         m.insert(TokenType::LPAREN, Precedence::Call);
+        m.insert(TokenType::LBRACKET, Precedence::Index);
         m
     };
 }
@@ -41,6 +62,10 @@ pub struct Parser {
     pub cur_token: Option<Token>,
     pub peek_token: Option<Token>,
     errors: Vec<String>,
+    depth: usize,
+    /// Set once nesting went past `MAX_NESTING` and the rest of the input
+    /// was skipped.
+    too_deep: bool,
     pub prefix_parse_fns: HashMap<TokenType, PrefixParseFn>,
     pub infix_parse_fns: HashMap<TokenType, InfixParseFn>,
 }
@@ -52,23 +77,51 @@ impl Parser {
             cur_token: None,
             peek_token: None,
             errors: Vec::new(),
+            depth: 0,
+            too_deep: false,
             prefix_parse_fns: HashMap::new(),
             infix_parse_fns: HashMap::new(),
         };
         p.register_prefix(TokenType::IDENT, Parser::parse_identifier);
         p.register_prefix(TokenType::INT, Parser::parse_integer_literal);
+        p.register_prefix(TokenType::STRING, Parser::parse_string_literal);
+        p.register_prefix(TokenType::LBRACKET, Parser::parse_array_literal);
+        p.register_prefix(TokenType::LBRACE, Parser::parse_hash_literal);
         p.register_prefix(TokenType::BANG, Parser::parse_prefix_expression);
         p.register_prefix(TokenType::MINUS, Parser::parse_prefix_expression);
+        p.register_prefix(TokenType::TRUE, Parser::parse_boolean);
+        p.register_prefix(TokenType::FALSE, Parser::parse_boolean);
+        p.register_prefix(TokenType::LPAREN, Parser::parse_grouped_expression);
+        p.register_prefix(TokenType::IF, Parser::parse_if_expression);
+        p.register_prefix(TokenType::TRY, Parser::parse_try_expression);
+        p.register_prefix(TokenType::FUNCTION, Parser::parse_function_literal);
+        for token_type in [
+            TokenType::PLUS,
+            TokenType::MINUS,
+            TokenType::SLASH,
+            TokenType::ASTERISK,
+            TokenType::EQ,
+            TokenType::NotEq,
+            TokenType::LT,
+            TokenType::GT,
+        ] {
+            p.register_infix(token_type, Parser::parse_infix_expression);
+        }
+        p.register_infix(TokenType::LPAREN, Parser::parse_call_expression);
+        p.register_infix(TokenType::LBRACKET, Parser::parse_index_expression);
         p.next_token();
         p.next_token();
         p
     }
 
-    fn parse_identifier(&mut self) -> Box<dyn Expression> {
-        Box::new(Identifier {
+    fn parse_identifier(&mut self) -> Option<Expression> {
+        Some(Expression::Identifier(Identifier {
+            id: NodeId::DUMMY,
+            slot: None,
+            annotation: None,
             token: self.cur_token.clone().unwrap(),
             value: self.cur_token.clone().unwrap().literal,
-        })
+        }))
     }
 
     fn register_prefix(&mut self, token_type: TokenType, f: PrefixParseFn) {
@@ -83,8 +136,8 @@ impl Parser {
         &self.errors
     }
 
-    fn no_prefix_parse_fn_error(&mut self, t: TokenType) {
-        let msg = format!("no prefix parse function for {:?} found", t);
+    fn no_prefix_parse_fn_error(&mut self, token: Token) {
+        let msg = no_prefix_parse_fn_message(token.r#type, &token.literal);
         self.errors.push(msg);
     }
 
@@ -96,13 +149,11 @@ impl Parser {
     }
 
     pub fn parse_program(&mut self) -> Option<Program> {
-        let mut program = Program {
-            statements: Vec::new(),
-        };
+        let mut statements = Vec::new();
 
         if self.cur_token.is_none() {
             if let Some(stmt) = self.parse_statement() {
-                program.statements.push(stmt);
+                statements.push(stmt);
             }
             self.next_token();
         }
@@ -110,7 +161,7 @@ impl Parser {
         while let Some(cur_token) = self.cur_token.clone() {
             if cur_token.r#type != TokenType::EOF {
                 if let Some(stmt) = self.parse_statement() {
-                    program.statements.push(stmt);
+                    statements.push(stmt);
                 }
                 self.next_token();
             } else {
@@ -118,151 +169,565 @@ impl Parser {
             }
         }
 
-        Some(program)
+        let source = unsafe { Rc::from((*self.lexer).input()) };
+        Some(Program::new(statements, source))
     }
 
-    pub fn parse_statement(&mut self) -> Option<Box<dyn Statement>> {
+    pub fn parse_statement(&mut self) -> Option<Statement> {
         match self.cur_token.as_ref().map(|t| t.r#type) {
-            Some(TokenType::LET) => self
-                .parse_let_statement()
-                .map(|stmt| Box::new(stmt) as Box<dyn Statement>),
-
-            Some(TokenType::RETURN) => self
-                .parse_return_statement()
-                .map(|stmt| Box::new(stmt) as Box<dyn Statement>),
+            Some(TokenType::LET) => self.parse_let_statement().map(Statement::Let),
+            Some(TokenType::RETURN) => self.parse_return_statement().map(Statement::Return),
+            Some(TokenType::THROW) => self.parse_throw_statement().map(Statement::Throw),
             _ => self
                 .parser_expression_statement()
-                .map(|stmt| Box::new(stmt) as Box<dyn Statement>),
+                .map(Statement::Expression),
         }
     }
 
     fn parser_expression_statement(&mut self) -> Option<ExpressionStatement> {
-        let stmt = ExpressionStatement {
-            token: self.cur_token.clone().unwrap(),
-            expression: self.parse_expression(Precedence::Lowest),
-        };
+        let token = self.cur_token.clone().unwrap();
+        let expression = self.parse_expression(Precedence::Lowest);
         if self.peek_token_is(TokenType::SEMICOLON) {
             self.next_token()
         }
-        Some(stmt)
+        Some(ExpressionStatement {
+            id: NodeId::DUMMY,
+            token,
+            expression: expression?,
+        })
+    }
+
+    fn parse_expression(&mut self, precedence: Precedence) -> Option<Expression> {
+        let depth = self.depth;
+        let expression = grow_stack(|| self.parse_nested_expression(precedence));
+        self.depth = depth;
+        expression
     }
 
-    fn parse_expression(&mut self, _precedence: Precedence) -> Option<Box<dyn Expression>> {
+    fn parse_nested_expression(&mut self, precedence: Precedence) -> Option<Expression> {
+        self.nest()?;
         let prefix = self
             .prefix_parse_fns
             .get(&self.cur_token.clone().unwrap().r#type);
-        match prefix {
-            Some(prefix) => {
-                let m = prefix(self);
-                Some(m)
-            }
+        let mut left_exp = match prefix {
+            Some(prefix) => prefix(self)?,
             None => {
-                self.no_prefix_parse_fn_error(self.cur_token.clone().unwrap().r#type);
+                self.no_prefix_parse_fn_error(self.cur_token.clone().unwrap());
+                return None;
+            }
+        };
+
+        while !self.peek_token_is(TokenType::SEMICOLON) && precedence < self.peek_precedence() {
+            let infix = match self
+                .infix_parse_fns
+                .get(&self.peek_token.clone().unwrap().r#type)
+            {
+                Some(infix) => *infix,
+                None => return Some(left_exp),
+            };
+            self.next_token();
+            left_exp = infix(self, left_exp)?;
+        }
+
+        Some(left_exp)
+    }
+
+    /// Go one level deeper, or give up on the rest of the input once
+    /// past `MAX_NESTING`.
+    fn nest(&mut self) -> Option<()> {
+        self.depth += 1;
+        if self.depth <= MAX_NESTING {
+            return Some(());
+        }
+        self.errors.push(nesting_error());
+        self.too_deep = true;
+        while !self.cur_token_is(TokenType::EOF) {
+            self.next_token();
+        }
+        None
+    }
+
+    fn parse_prefix_expression(&mut self) -> Option<Expression> {
+        let token = self.cur_token.clone().unwrap();
+        self.next_token();
+
+        let right = self.parse_expression(Precedence::Prefix)?;
+
+        Some(Expression::Prefix(PrefixExpression {
+            id: NodeId::DUMMY,
+            operator: token.literal.clone(),
+            token,
+            right: Box::new(right),
+        }))
+    }
+
+    fn parse_infix_expression(&mut self, left: Expression) -> Option<Expression> {
+        let token = self.cur_token.clone().unwrap();
+        let precedence = self.cur_precedence();
+        self.next_token();
+
+        let right = self.parse_expression(precedence)?;
+
+        Some(Expression::Infix(InfixExpression {
+            id: NodeId::DUMMY,
+            operator: token.literal.clone(),
+            token,
+            left: Box::new(left),
+            right: Box::new(right),
+        }))
+    }
+
+    fn parse_integer_literal(&mut self) -> Option<Expression> {
+        let token = self.cur_token.clone().unwrap();
+        match token.literal.parse::<i64>() {
+            Ok(value) => Some(Expression::Integer(IntegerLiteral {
+                token,
+                id: NodeId::DUMMY,
+                value,
+            })),
+            #[cfg(feature = "bigint")]
+            Err(_) => {
+                let value = token.literal.parse().ok()?;
+                Some(Expression::BigInteger(crate::BigIntegerLiteral {
+                    id: NodeId::DUMMY,
+                    token,
+                    value,
+                }))
+            }
+            #[cfg(not(feature = "bigint"))]
+            Err(_) => {
+                self.errors.push(integer_too_large_message(&token.literal));
                 None
             }
         }
     }
 
-    fn parse_prefix_expression(&mut self) -> Box<dyn Expression> {
-        let mut expression = PrefixExpression {
-            token: self.cur_token.clone().unwrap().clone(),
-            operator: self.cur_token.clone().unwrap().literal.clone(),
-            right: None,
-        };
+    fn parse_boolean(&mut self) -> Option<Expression> {
+        Some(Expression::Boolean(Boolean {
+            id: NodeId::DUMMY,
+            token: self.cur_token.clone().unwrap(),
+            value: self.cur_token_is(TokenType::TRUE),
+        }))
+    }
+
+    fn parse_grouped_expression(&mut self) -> Option<Expression> {
+        self.next_token();
+
+        let exp = self.parse_expression(Precedence::Lowest)?;
+        if !self.expect_peek(TokenType::RPAREN) {
+            return None;
+        }
+        Some(exp)
+    }
 
+    fn parse_if_expression(&mut self) -> Option<Expression> {
+        let token = self.cur_token.clone().unwrap();
+
+        if !self.expect_peek(TokenType::LPAREN) {
+            return None;
+        }
         self.next_token();
+        let condition = self.parse_expression(Precedence::Lowest)?;
+
+        if !self.expect_peek(TokenType::RPAREN) {
+            return None;
+        }
+        if !self.expect_peek(TokenType::LBRACE) {
+            return None;
+        }
+        let consequence = self.parse_block_statement();
 
-        expression.right = self.parse_expression(Precedence::Prefix);
+        let mut alternative = None;
+        if self.peek_token_is(TokenType::ELSE) {
+            self.next_token();
+            if !self.expect_peek(TokenType::LBRACE) {
+                return None;
+            }
+            alternative = Some(self.parse_block_statement());
+        }
 
-        Box::new(expression)
+        Some(Expression::If(IfExpression {
+            id: NodeId::DUMMY,
+            token,
+            condition: Box::new(condition),
+            consequence,
+            alternative,
+        }))
     }
 
-    fn parse_infix_expression(&mut self, left: Box<dyn Expression>) -> Box<dyn Expression> {
-        let mut expression = InfixExpression {
+    fn parse_try_expression(&mut self) -> Option<Expression> {
+        let token = self.cur_token.clone().unwrap();
+
+        if !self.expect_peek(TokenType::LBRACE) {
+            return None;
+        }
+        let body = self.parse_block_statement();
+
+        if !self.expect_peek(TokenType::CATCH) {
+            return None;
+        }
+        if !self.expect_peek(TokenType::LPAREN) {
+            return None;
+        }
+        if !self.expect_peek(TokenType::IDENT) {
+            return None;
+        }
+        let param = self.cur_token.clone().unwrap();
+        let parameter = Identifier {
+            id: NodeId::DUMMY,
+            slot: None,
+            annotation: None,
+            value: param.literal.clone(),
+            token: param,
+        };
+        if !self.expect_peek(TokenType::RPAREN) {
+            return None;
+        }
+        if !self.expect_peek(TokenType::LBRACE) {
+            return None;
+        }
+        let handler = self.parse_block_statement();
+
+        Some(Expression::Try(TryExpression {
+            id: NodeId::DUMMY,
+            token,
+            body,
+            parameter,
+            handler,
+        }))
+    }
+
+    fn parse_block_statement(&mut self) -> BlockStatement {
+        let mut block = BlockStatement {
+            id: NodeId::DUMMY,
             token: self.cur_token.clone().unwrap(),
-            operator: self.cur_token.clone().unwrap().literal.clone(),
-            left: Some(left),
-            right: None,
+            statements: Vec::new(),
         };
+        self.next_token();
+
+        while !self.cur_token_is(TokenType::RBRACE) && !self.cur_token_is(TokenType::EOF) {
+            if let Some(stmt) = self.parse_statement() {
+                block.statements.push(stmt);
+            }
+            self.next_token();
+        }
+        if self.cur_token_is(TokenType::EOF) && !self.too_deep {
+            self.errors
+                .push("expected `}`, got EOF instead".to_string());
+        }
+        block
+    }
+
+    fn parse_function_literal(&mut self) -> Option<Expression> {
+        let token = self.cur_token.clone().unwrap();
+
+        if !self.expect_peek(TokenType::LPAREN) {
+            return None;
+        }
+        let parameters = self.parse_function_parameters()?;
+
+        let mut return_type = None;
+        if self.peek_token_is(TokenType::ARROW) {
+            self.next_token();
+            self.next_token();
+            return_type = Some(self.parse_type()?);
+        }
+
+        if !self.expect_peek(TokenType::LBRACE) {
+            return None;
+        }
+        let body = self.parse_block_statement();
+
+        Some(Expression::Function(FunctionLiteral {
+            id: NodeId::DUMMY,
+            token,
+            parameters,
+            return_type,
+            body: Rc::new(body),
+        }))
+    }
+
+    fn parse_function_parameters(&mut self) -> Option<Vec<Identifier>> {
+        let mut identifiers = Vec::new();
+
+        if self.peek_token_is(TokenType::RPAREN) {
+            self.next_token();
+            return Some(identifiers);
+        }
+
+        if !self.expect_peek(TokenType::IDENT) {
+            return None;
+        }
+        identifiers.push(self.parse_declared_name()?);
+
+        while self.peek_token_is(TokenType::COMMA) {
+            self.next_token();
+            if !self.expect_peek(TokenType::IDENT) {
+                return None;
+            }
+            identifiers.push(self.parse_declared_name()?);
+        }
+
+        if !self.expect_peek(TokenType::RPAREN) {
+            return None;
+        }
+        Some(identifiers)
+    }
+
+    fn parse_call_expression(&mut self, function: Expression) -> Option<Expression> {
+        let token = self.cur_token.clone().unwrap();
+        let arguments = self.parse_expression_list(TokenType::RPAREN)?;
+        Some(Expression::Call(CallExpression {
+            id: NodeId::DUMMY,
+            token,
+            function: Box::new(function),
+            arguments,
+        }))
+    }
+
+    fn parse_expression_list(&mut self, end: TokenType) -> Option<Vec<Expression>> {
+        let mut args = Vec::new();
+
+        if self.peek_token_is(end) {
+            self.next_token();
+            return Some(args);
+        }
 
-        let precedence = self.cur_precedence();
         self.next_token();
+        args.push(self.parse_expression(Precedence::Lowest)?);
 
-        if expression.operator == "+" {
-            let p = (precedence as i8) - 1;
-            expression.right = self.parse_expression(unsafe { std::mem::transmute(p) });
-        } else {
-            expression.right = self.parse_expression(precedence);
+        while self.peek_token_is(TokenType::COMMA) {
+            self.next_token();
+            self.next_token();
+            args.push(self.parse_expression(Precedence::Lowest)?);
         }
 
-        Box::new(expression)
+        if !self.expect_peek(end) {
+            return None;
+        }
+        Some(args)
     }
 
-    fn parse_integer_literal(&mut self) -> Box<dyn Expression> {
-        let mut lit = IntegerLiteral {
+    fn parse_string_literal(&mut self) -> Option<Expression> {
+        Some(Expression::String(StringLiteral {
+            id: NodeId::DUMMY,
             token: self.cur_token.clone().unwrap(),
-            value: 0,
-        };
+            value: self.cur_token.clone().unwrap().literal,
+        }))
+    }
+
+    fn parse_array_literal(&mut self) -> Option<Expression> {
+        let token = self.cur_token.clone().unwrap();
+        let elements = self.parse_expression_list(TokenType::RBRACKET)?;
+        Some(Expression::Array(ArrayLiteral {
+            token,
+            id: NodeId::DUMMY,
+            elements,
+        }))
+    }
+
+    fn parse_hash_literal(&mut self) -> Option<Expression> {
+        let token = self.cur_token.clone().unwrap();
+        let mut pairs = Vec::new();
 
-        match i64::from_str_radix(&self.cur_token.clone().unwrap().literal, 10) {
-            Ok(value) => {
-                lit.value = value;
-                Box::new(lit.clone())
+        while !self.peek_token_is(TokenType::RBRACE) {
+            self.next_token();
+            let key = self.parse_expression(Precedence::Lowest)?;
+            if !self.expect_peek(TokenType::COLON) {
+                return None;
             }
-            Err(_) => {
-                eprintln!(
-                    "could not parse {} as integer",
-                    self.cur_token.clone().unwrap().literal
-                );
-                Box::new(lit.clone())
+            self.next_token();
+            let value = self.parse_expression(Precedence::Lowest)?;
+            pairs.push((key, value));
+
+            if !self.peek_token_is(TokenType::RBRACE) && !self.expect_peek(TokenType::COMMA) {
+                return None;
             }
         }
+
+        if !self.expect_peek(TokenType::RBRACE) {
+            return None;
+        }
+        Some(Expression::Hash(HashLiteral {
+            token,
+            id: NodeId::DUMMY,
+            pairs,
+        }))
+    }
+
+    fn parse_index_expression(&mut self, left: Expression) -> Option<Expression> {
+        let token = self.cur_token.clone().unwrap();
+        self.next_token();
+        let index = self.parse_expression(Precedence::Lowest)?;
+
+        if !self.expect_peek(TokenType::RBRACKET) {
+            return None;
+        }
+        Some(Expression::Index(IndexExpression {
+            id: NodeId::DUMMY,
+            token,
+            left: Box::new(left),
+            index: Box::new(index),
+        }))
     }
 
     fn parse_return_statement(&mut self) -> Option<ReturnStatement> {
-        let stmt = ReturnStatement {
-            token: self.cur_token.clone().unwrap(),
-            return_value: None,
-        };
+        let token = self.cur_token.clone().unwrap();
         self.next_token();
 
-        while !self.cur_token_is(TokenType::SEMICOLON) {
+        let return_value = self.parse_expression(Precedence::Lowest);
+
+        if self.peek_token_is(TokenType::SEMICOLON) {
             self.next_token();
         }
 
-        Some(stmt)
+        Some(ReturnStatement {
+            id: NodeId::DUMMY,
+            token,
+            return_value: return_value?,
+        })
+    }
+
+    fn parse_throw_statement(&mut self) -> Option<ThrowStatement> {
+        let token = self.cur_token.clone().unwrap();
+        self.next_token();
+
+        let value = self.parse_expression(Precedence::Lowest);
+
+        if self.peek_token_is(TokenType::SEMICOLON) {
+            self.next_token();
+        }
+
+        Some(ThrowStatement {
+            id: NodeId::DUMMY,
+            token,
+            value: value?,
+        })
     }
 
     fn parse_let_statement(&mut self) -> Option<LetStatement> {
-        let mut stmt = LetStatement {
-            token: self.cur_token.clone().unwrap(),
-            name: None,
-            value: None,
-        };
+        let token = self.cur_token.clone().unwrap();
 
         if !self.expect_peek(TokenType::IDENT) {
             return None;
         }
-        let token = self.cur_token.clone().unwrap();
-        stmt.name = Some(Identifier {
-            token: token.clone(),
-            value: token.literal,
-        });
+        let name = self.parse_declared_name()?;
 
         if !self.expect_peek(TokenType::ASSIGN) {
             return None;
         }
 
-        // TODO:value赋值
-        // self.next_token();
-        // stmt.value =
+        self.next_token();
+        let value = self.parse_expression(Precedence::Lowest);
 
-        // TODO: 跳过对表达式的处理，直到遇见分号
-        while !self.cur_token_is(TokenType::SEMICOLON) {
+        if self.peek_token_is(TokenType::SEMICOLON) {
             self.next_token();
         }
 
-        Some(stmt)
+        Some(LetStatement {
+            id: NodeId::DUMMY,
+            token,
+            name,
+            value: value?,
+        })
+    }
+
+    /// The name at the current token, with the `: type` after it if
+    /// there is one.
+    fn parse_declared_name(&mut self) -> Option<Identifier> {
+        let token = self.cur_token.clone().unwrap();
+        let mut annotation = None;
+        if self.peek_token_is(TokenType::COLON) {
+            self.next_token();
+            self.next_token();
+            annotation = Some(self.parse_type()?);
+        }
+        Some(Identifier {
+            id: NodeId::DUMMY,
+            slot: None,
+            annotation,
+            value: token.literal.clone(),
+            token,
+        })
+    }
+
+    /// Parse the type starting at the current token, leaving the current
+    /// token at its last.
+    fn parse_type(&mut self) -> Option<TypeAnnotation> {
+        let depth = self.depth;
+        let annotation = self.parse_nested_type();
+        self.depth = depth;
+        annotation
+    }
+
+    fn parse_nested_type(&mut self) -> Option<TypeAnnotation> {
+        self.nest()?;
+        let token = self.cur_token.clone().unwrap();
+        let kind = match token.r#type {
+            TokenType::IDENT => TypeAnnotationKind::Named {
+                name: token.literal.clone(),
+            },
+            TokenType::LBRACKET => {
+                self.next_token();
+                let element = Box::new(self.parse_type()?);
+                if !self.expect_peek(TokenType::RBRACKET) {
+                    return None;
+                }
+                TypeAnnotationKind::Array { element }
+            }
+            TokenType::LBRACE => {
+                self.next_token();
+                let key = Box::new(self.parse_type()?);
+                if !self.expect_peek(TokenType::COLON) {
+                    return None;
+                }
+                self.next_token();
+                let value = Box::new(self.parse_type()?);
+                if !self.expect_peek(TokenType::RBRACE) {
+                    return None;
+                }
+                TypeAnnotationKind::Hash { key, value }
+            }
+            TokenType::FUNCTION => {
+                if !self.expect_peek(TokenType::LPAREN) {
+                    return None;
+                }
+                let mut parameters = Vec::new();
+                if self.peek_token_is(TokenType::RPAREN) {
+                    self.next_token();
+                } else {
+                    self.next_token();
+                    parameters.push(self.parse_type()?);
+                    while self.peek_token_is(TokenType::COMMA) {
+                        self.next_token();
+                        self.next_token();
+                        parameters.push(self.parse_type()?);
+                    }
+                    if !self.expect_peek(TokenType::RPAREN) {
+                        return None;
+                    }
+                }
+                if !self.expect_peek(TokenType::ARROW) {
+                    return None;
+                }
+                self.next_token();
+                let return_type = Box::new(self.parse_type()?);
+                TypeAnnotationKind::Function {
+                    parameters,
+                    return_type,
+                }
+            }
+            token_type => {
+                self.errors
+                    .push(format!("expected a type, got {:?} instead", token_type));
+                return None;
+            }
+        };
+        let end = self.cur_token.as_ref().unwrap().span.end;
+        Some(TypeAnnotation {
+            kind,
+            span: Span::new(token.span.start, end),
+        })
     }
 
     fn cur_token_is(&self, t: TokenType) -> bool {
@@ -289,15 +754,11 @@ impl Parser {
     }
 
     fn peek_precedence(&self) -> Precedence {
-        *PRECEDENCES
-            .get(&self.peek_token.clone().unwrap().r#type)
-            .unwrap_or(&Precedence::Lowest)
+        Precedence::of(self.peek_token.as_ref().unwrap().r#type)
     }
 
     fn cur_precedence(&self) -> Precedence {
-        *PRECEDENCES
-            .get(&self.cur_token.clone().unwrap().r#type)
-            .unwrap_or(&Precedence::Lowest)
+        Precedence::of(self.cur_token.as_ref().unwrap().r#type)
     }
 
     fn expect_peek(&mut self, t: TokenType) -> bool {
@@ -310,3 +771,30 @@ impl Parser {
         }
     }
 }
+
+/// The error for a token no expression can start with. The lexer makes a
+/// string missing its closing quote an `ILLEGAL` token starting with `"`.
+pub(crate) fn no_prefix_parse_fn_message(token_type: TokenType, literal: &str) -> String {
+    if token_type == TokenType::ILLEGAL && literal.starts_with('"') {
+        "unterminated string".to_string()
+    } else {
+        format!("no prefix parse function for {:?} found", token_type)
+    }
+}
+
+pub(crate) fn nesting_error() -> String {
+    format!(
+        "expressions are nested too deeply, the maximum is {}",
+        MAX_NESTING
+    )
+}
+
+/// Without the `bigint` feature, integers must fit in an `i64`.
+#[cfg(not(feature = "bigint"))]
+pub(crate) fn integer_too_large_message(literal: &str) -> String {
+    format!(
+        "integer literal {} is too large, the maximum is {}",
+        literal,
+        i64::MAX
+    )
+}
@@ -0,0 +1,125 @@
+use crate::{Session, Value};
+use std::time::Instant;
+
+pub type CommandFn = fn(&mut Session, &str) -> Result<String, String>;
+
+/// A repl meta-command, invoked as `:name args`.
+pub struct Command {
+    pub name: &'static str,
+    pub args: &'static str,
+    pub help: &'static str,
+    pub run: CommandFn,
+}
+
+pub fn default_commands() -> Vec<Command> {
+    vec![
+        Command {
+            name: "help",
+            args: "",
+            help: "show this list of commands",
+            run: help,
+        },
+        Command {
+            name: "load",
+            args: "<file>",
+            help: "evaluate a source file in the current session",
+            run: load,
+        },
+        Command {
+            name: "reset",
+            args: "",
+            help: "clear every binding",
+            run: reset,
+        },
+        Command {
+            name: "env",
+            args: "",
+            help: "list bindings and their values",
+            run: env,
+        },
+        Command {
+            name: "type",
+            args: "<expr>",
+            help: "show the runtime type of an expression",
+            run: type_of,
+        },
+        Command {
+            name: "doc",
+            args: "<builtin>",
+            help: "show the documentation of a builtin function",
+            run: doc,
+        },
+        Command {
+            name: "time",
+            args: "<expr>",
+            help: "evaluate an expression and report how long it took",
+            run: time,
+        },
+    ]
+}
+
+fn help(session: &mut Session, _: &str) -> Result<String, String> {
+    let lines: Vec<String> = session
+        .commands()
+        .iter()
+        .map(|c| format!("{:<16}{}", format!(":{} {}", c.name, c.args), c.help))
+        .collect();
+    Ok(lines.join("\n"))
+}
+
+fn load(session: &mut Session, path: &str) -> Result<String, String> {
+    if path.is_empty() {
+        return Err("usage: :load <file>".to_string());
+    }
+    session
+        .interpreter
+        .eval_file(path)
+        .map_err(|err| err.render())?;
+    Ok(format!("loaded {}", path))
+}
+
+fn reset(session: &mut Session, _: &str) -> Result<String, String> {
+    session.reset();
+    Ok("environment cleared".to_string())
+}
+
+fn env(session: &mut Session, _: &str) -> Result<String, String> {
+    let lines: Vec<String> = session
+        .interpreter
+        .env()
+        .borrow()
+        .bindings()
+        .iter()
+        .map(|(name, value)| format!("{} = {}", name, value))
+        .collect();
+    Ok(lines.join("\n"))
+}
+
+fn type_of(session: &mut Session, expr: &str) -> Result<String, String> {
+    if expr.is_empty() {
+        return Err("usage: :type <expr>".to_string());
+    }
+    Ok(session.eval(expr)?.type_name().to_string())
+}
+
+fn doc(session: &mut Session, name: &str) -> Result<String, String> {
+    let builtins = &session.interpreter.evaluator.builtins;
+    match builtins.doc(name) {
+        Some("") => Ok(format!("{} has no documentation", name)),
+        Some(doc) => Ok(doc.to_string()),
+        None => Err(format!("no builtin named {}", name)),
+    }
+}
+
+fn time(session: &mut Session, expr: &str) -> Result<String, String> {
+    if expr.is_empty() {
+        return Err("usage: :time <expr>".to_string());
+    }
+    let start = Instant::now();
+    let value = session.eval(expr)?;
+    let elapsed = start.elapsed();
+    match value {
+        Value::Null => Ok(format!("time: {:?}", elapsed)),
+        value => Ok(format!("{}\ntime: {:?}", value, elapsed)),
+    }
+}
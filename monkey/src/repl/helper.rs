@@ -0,0 +1,140 @@
+use crate::{Environment, KeyWord, Lexer, TokenType};
+use colored::{ColoredString, Colorize};
+use rustyline::completion::Completer;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Helper};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Line editor hooks for the repl, sharing the session environment so
+/// completion sees the bindings made so far.
+pub struct ReplHelper {
+    env: Rc<RefCell<Environment>>,
+    builtins: Vec<String>,
+}
+
+impl ReplHelper {
+    pub fn new(env: Rc<RefCell<Environment>>, builtins: Vec<String>) -> ReplHelper {
+        ReplHelper { env, builtins }
+    }
+
+    /// Start of the word under the cursor and every keyword, builtin or
+    /// binding it could be completed to.
+    pub fn completions(&self, line: &str, pos: usize) -> (usize, Vec<String>) {
+        let start = line[..pos]
+            .char_indices()
+            .rev()
+            .take_while(|(_, ch)| ch.is_ascii_alphabetic() || *ch == '_')
+            .last()
+            .map_or(pos, |(i, _)| i);
+        let word = &line[start..pos];
+        if word.is_empty() {
+            return (pos, Vec::new());
+        }
+
+        let mut candidates: Vec<String> = KeyWord::new()
+            .key_word
+            .into_keys()
+            .chain(self.builtins.iter().cloned())
+            .chain(self.env.borrow().names())
+            .filter(|name| name.starts_with(word))
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+        (start, candidates)
+    }
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(self.completions(line, pos))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {
+    fn highlight<'l>(&self, line: &'l str, _: usize) -> Cow<'l, str> {
+        Cow::Owned(highlight(line))
+    }
+
+    fn highlight_char(&self, _: &str, _: usize, _: bool) -> bool {
+        // every edit can change how the rest of the line lexes
+        true
+    }
+}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+/// Colorize `input` token by token, leaving the whitespace between
+/// tokens untouched.
+pub fn highlight(input: &str) -> String {
+    let mut lexer = Lexer::new(input.to_string());
+    let mut output = String::with_capacity(input.len());
+    let mut last = 0;
+    let mut comments = 0;
+    loop {
+        lexer.skip_white_space();
+        for comment in &lexer.comments()[comments..] {
+            output.push_str(&input[last..comment.span.start]);
+            output.push_str(&comment.text.bright_black().to_string());
+            last = comment.span.end;
+        }
+        comments = lexer.comments().len();
+        let tok = lexer.next_token();
+        if tok.r#type == TokenType::EOF {
+            break;
+        }
+        let (start, end) = (tok.span.start, tok.span.end);
+        output.push_str(&input[last..start]);
+        output.push_str(&paint(tok.r#type, &input[start..end]).to_string());
+        last = end;
+    }
+    output.push_str(&input[last..]);
+    output
+}
+
+fn paint(token_type: TokenType, text: &str) -> ColoredString {
+    match token_type {
+        TokenType::FUNCTION
+        | TokenType::LET
+        | TokenType::IF
+        | TokenType::ELSE
+        | TokenType::RETURN
+        | TokenType::TRY
+        | TokenType::CATCH
+        | TokenType::THROW => text.magenta().bold(),
+        TokenType::TRUE | TokenType::FALSE => text.yellow(),
+        TokenType::INT => text.cyan(),
+        TokenType::STRING => text.green(),
+        // a string still being typed, without its closing quote yet
+        TokenType::ILLEGAL if text.starts_with('"') => text.green(),
+        TokenType::ILLEGAL => text.red().bold(),
+        TokenType::IDENT
+        | TokenType::COMMA
+        | TokenType::SEMICOLON
+        | TokenType::COLON
+        | TokenType::LPAREN
+        | TokenType::RPAREN
+        | TokenType::LBRACE
+        | TokenType::RBRACE
+        | TokenType::LBRACKET
+        | TokenType::RBRACKET
+        | TokenType::EOF => text.normal(),
+        _ => text.bright_white(),
+    }
+}
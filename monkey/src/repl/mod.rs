@@ -1,3 +1,9 @@
+pub mod commands;
+pub mod helper;
 pub mod repl;
+pub mod session;
 
+pub use commands::*;
+pub use helper::*;
 pub use repl::*;
+pub use session::*;
@@ -1,29 +1,94 @@
-use crate::{Lexer, TokenType};
+use crate::{Lexer, ReplHelper, Session, TokenType};
 use colored::Colorize;
-use std::io;
-use std::io::Write;
+use rustyline::error::ReadlineError;
+use rustyline::history::FileHistory;
+use rustyline::{CompletionType, Config, Editor};
+use std::env;
+use std::path::PathBuf;
 
 const PROMPT: &str = ">>";
+const CONTINUATION_PROMPT: &str = "..";
+const HISTORY_FILE: &str = ".monkey_history";
 
 pub fn start() {
     // This is primarily used for Windows 10 environments
     // which will not correctly colorize the outputs based on ANSI escape codes.
     #[cfg(windows)]
     colored::control::set_virtual_terminal(true).unwrap();
+    let mut session = Session::new();
+    let config = Config::builder()
+        .completion_type(CompletionType::List)
+        .build();
+    let mut editor: Editor<ReplHelper, FileHistory> =
+        Editor::with_config(config).expect("failed to initialize line editor");
+    editor.set_helper(Some(ReplHelper::new(
+        session.interpreter.env().clone(),
+        session.interpreter.evaluator.builtins.names(),
+    )));
+    let history = history_path();
+    if let Some(path) = &history {
+        // a missing history file just means this is the first session
+        let _ = editor.load_history(path);
+    }
     loop {
-        print!("{} ", PROMPT.italic().green());
-        io::stdout().flush().unwrap();
-        let mut codes = String::new();
-        io::stdin()
-            .read_line(&mut codes)
-            .expect("invalid code line");
-        let mut lexer = Lexer::new(codes);
-        loop {
-            let tok = lexer.next_token();
-            if tok.r#type == TokenType::EOF {
+        let codes = match read_input(&mut editor) {
+            Ok(codes) => codes,
+            // Ctrl-C drops the pending input and starts over
+            Err(ReadlineError::Interrupted) => continue,
+            // Ctrl-D leaves the repl
+            Err(ReadlineError::Eof) => break,
+            Err(err) => {
+                eprintln!("{}", err);
                 break;
             }
-            println!("{:?}", tok);
+        };
+        if codes.trim().is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(codes.as_str());
+        match session.execute(&codes) {
+            Ok(output) if output.is_empty() => {}
+            Ok(output) => println!("{}", output),
+            Err(err) => println!("{}", err.red()),
+        }
+    }
+    if let Some(path) = &history {
+        if let Err(err) = editor.save_history(path) {
+            eprintln!("could not save history: {}", err);
+        }
+    }
+}
+
+/// Read one complete input, prompting for continuation lines
+/// while parentheses or braces are left open.
+fn read_input(editor: &mut Editor<ReplHelper, FileHistory>) -> rustyline::Result<String> {
+    let mut codes = editor.readline(&format!("{} ", PROMPT.italic().green()))?;
+    while is_incomplete(&codes) {
+        let line = editor.readline(&format!("{} ", CONTINUATION_PROMPT.italic().green()))?;
+        codes.push('\n');
+        codes.push_str(&line);
+    }
+    Ok(codes)
+}
+
+/// Whether `input` has more `(`/`{`/`[` than `)`/`}`/`]`, i.e. the user
+/// is still in the middle of typing a function body, call or array.
+pub fn is_incomplete(input: &str) -> bool {
+    let mut lexer = Lexer::new(input.to_string());
+    let mut depth = 0;
+    loop {
+        match lexer.next_token().r#type {
+            TokenType::LPAREN | TokenType::LBRACE | TokenType::LBRACKET => depth += 1,
+            TokenType::RPAREN | TokenType::RBRACE | TokenType::RBRACKET => depth -= 1,
+            TokenType::EOF => break,
+            _ => {}
         }
     }
+    depth > 0
+}
+
+fn history_path() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(HISTORY_FILE))
 }
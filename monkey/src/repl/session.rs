@@ -0,0 +1,64 @@
+use crate::{default_commands, Command, Interpreter, Value};
+
+/// State kept across inputs of one repl session: the interpreter with
+/// its global environment, and the table of `:` commands.
+pub struct Session {
+    pub interpreter: Interpreter,
+    commands: Vec<Command>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session::new()
+    }
+}
+
+impl Session {
+    pub fn new() -> Session {
+        Session {
+            interpreter: Interpreter::new(),
+            commands: default_commands(),
+        }
+    }
+
+    /// Add a `:` command, replacing any existing one with the same name.
+    pub fn register(&mut self, command: Command) {
+        self.commands.retain(|c| c.name != command.name);
+        self.commands.push(command);
+    }
+
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+
+    /// Drop every binding made so far. The environment is cleared in
+    /// place so anything holding on to it sees the reset too.
+    pub fn reset(&mut self) {
+        self.interpreter.reset();
+    }
+
+    /// Parse and evaluate `input` in the session environment.
+    pub fn eval(&mut self, input: &str) -> Result<Value, String> {
+        self.interpreter.eval_str(input).map_err(|err| err.render())
+    }
+
+    /// Handle one line of repl input, either a `:` command or code,
+    /// returning the text to show the user.
+    pub fn execute(&mut self, input: &str) -> Result<String, String> {
+        let input = input.trim();
+        if let Some(command) = input.strip_prefix(':') {
+            let (name, args) = command
+                .split_once(char::is_whitespace)
+                .unwrap_or((command, ""));
+            let run = match self.commands.iter().find(|c| c.name == name) {
+                Some(command) => command.run,
+                None => return Err(format!("unknown command :{}, try :help", name)),
+            };
+            return run(self, args.trim());
+        }
+        match self.eval(input)? {
+            Value::Null => Ok(String::new()),
+            value => Ok(value.to_string()),
+        }
+    }
+}
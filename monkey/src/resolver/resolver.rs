@@ -0,0 +1,408 @@
+use crate::{
+    line_column, walk_block_statement, Builtins, Diagnostic, FunctionLiteral, Identifier,
+    LetStatement, Node, NodeId, Program, Span, TryExpression, Visitor, VisitorMut,
+};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Identifies a binding within its `Resolution`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BindingId(pub u32);
+
+impl BindingId {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingKind {
+    Let,
+    Parameter,
+    /// The error bound by `catch`.
+    Catch,
+}
+
+impl fmt::Display for BindingKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BindingKind::Let => write!(f, "let"),
+            BindingKind::Parameter => write!(f, "parameter"),
+            BindingKind::Catch => write!(f, "catch"),
+        }
+    }
+}
+
+/// Where an identifier's binding lives: `depth` scopes out from the
+/// scope the identifier is in. At runtime each scope is an `Environment`,
+/// so `depth` says how far up the chain of environments to look for the
+/// name, skipping the scopes in between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Slot {
+    pub depth: u32,
+}
+
+/// A name introduced by `let`, a parameter list or `catch`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binding {
+    pub name: String,
+    pub kind: BindingKind,
+    /// The `Identifier` naming it.
+    pub ident: NodeId,
+    pub span: Span,
+    /// The `let` statement, function literal or `try` expression that
+    /// introduces it.
+    pub owner: NodeId,
+}
+
+/// An identifier used where no binding of its name is visible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unresolved {
+    pub ident: NodeId,
+    pub name: String,
+    pub span: Span,
+    scope: ScopeId,
+}
+
+/// Scopes are numbered in the order they are opened.
+type ScopeId = usize;
+
+/// Which binding each identifier in a program refers to.
+///
+/// Scopes follow the evaluator's environments: one for the program, one
+/// per function call and one per `catch` handler. Blocks don't open a
+/// scope. Within a scope, a name is visible from the statement after
+/// the one binding it. A function body is only run when the function is
+/// called, so names it uses from outer scopes are looked up in the
+/// nearest one binding them anywhere, which lets functions call
+/// themselves and each other.
+#[derive(Debug, Clone, Default)]
+pub struct Resolution {
+    bindings: Vec<Binding>,
+    /// The scope each binding is in.
+    binding_scopes: Vec<ScopeId>,
+    /// The scope each scope is in.
+    scope_parents: Vec<Option<ScopeId>>,
+    /// The binding of every identifier that names one, whether it binds
+    /// it or uses it.
+    targets: HashMap<NodeId, BindingId>,
+    slots: HashMap<NodeId, Slot>,
+    /// Every use of each binding, in source order.
+    references: Vec<Vec<NodeId>>,
+    unresolved: Vec<Unresolved>,
+}
+
+impl Resolution {
+    pub fn bindings(&self) -> &[Binding] {
+        &self.bindings
+    }
+
+    pub fn binding(&self, id: BindingId) -> &Binding {
+        &self.bindings[id.index()]
+    }
+
+    /// The binding an identifier binds or refers to.
+    pub fn binding_of(&self, ident: NodeId) -> Option<BindingId> {
+        self.targets.get(&ident).copied()
+    }
+
+    /// Where the binding an identifier binds or refers to lives.
+    pub fn slot(&self, ident: NodeId) -> Option<Slot> {
+        self.slots.get(&ident).copied()
+    }
+
+    /// The identifiers using a binding, not counting the one binding it.
+    pub fn references(&self, id: BindingId) -> &[NodeId] {
+        &self.references[id.index()]
+    }
+
+    /// The identifiers no binding was found for, in source order. They
+    /// may still name builtins or globals set by the host.
+    pub fn unresolved(&self) -> &[Unresolved] {
+        &self.unresolved
+    }
+
+    /// Names used before they are defined or not defined at all, as
+    /// errors, and bindings that shadow others or are never used, as
+    /// warnings. Names starting with `_` are exempt from the unused
+    /// check.
+    pub fn diagnostics(&self, program: &Program, builtins: &Builtins) -> Vec<Diagnostic> {
+        let location = |span: Span| {
+            let (line, column) = line_column(&program.source, span.start);
+            format!("{}:{}", line, column)
+        };
+        let mut diagnostics = Vec::new();
+        for unresolved in &self.unresolved {
+            let name = &unresolved.name;
+            let later = self.bindings.iter().enumerate().find(|(i, binding)| {
+                &binding.name == name
+                    && self.encloses(self.binding_scopes[*i], unresolved.scope)
+                    && (binding.span.start > unresolved.span.start
+                        || is_within(program, unresolved.ident, binding.owner))
+            });
+            if let Some((_, binding)) = later {
+                diagnostics.push(
+                    Diagnostic::error(format!("`{}` is used before it is defined", name))
+                        .with_code("use-before-definition")
+                        .with_span(unresolved.span)
+                        .with_note(format!(
+                            "`{}` is defined at {}",
+                            name,
+                            location(binding.span)
+                        )),
+                );
+            } else if builtins.get(name).is_none() {
+                diagnostics.push(
+                    Diagnostic::error(format!("`{}` is not defined", name))
+                        .with_code("undefined-name")
+                        .with_span(unresolved.span),
+                );
+            }
+        }
+        for (i, binding) in self.bindings.iter().enumerate() {
+            if let Some(outer) = self.shadowed(BindingId(i as u32)) {
+                let outer = self.binding(outer);
+                diagnostics.push(
+                    Diagnostic::warning(format!("`{}` shadows an outer binding", binding.name))
+                        .with_code("shadowing")
+                        .with_span(binding.span)
+                        .with_note(format!(
+                            "the outer `{}` is defined at {}",
+                            outer.name,
+                            location(outer.span)
+                        )),
+                );
+            }
+            if self.references[i].is_empty() && !binding.name.starts_with('_') {
+                diagnostics.push(
+                    Diagnostic::warning(format!("`{}` is never used", binding.name))
+                        .with_code("unused-binding")
+                        .with_span(binding.span)
+                        .with_note(format!("name it `_{}` if that is intended", binding.name)),
+                );
+            }
+        }
+        diagnostics.sort_by_key(|diagnostic| diagnostic.span.map(|span| span.start));
+        diagnostics
+    }
+
+    /// Whether `outer` is `inner` or one of the scopes around it.
+    fn encloses(&self, outer: ScopeId, inner: ScopeId) -> bool {
+        let mut scope = Some(inner);
+        while let Some(id) = scope {
+            if id == outer {
+                return true;
+            }
+            scope = self.scope_parents[id];
+        }
+        false
+    }
+
+    /// The binding of the same name in the nearest scope around the
+    /// binding's own: the last one made before it, or else the first.
+    fn shadowed(&self, id: BindingId) -> Option<BindingId> {
+        let binding = self.binding(id);
+        let mut scope = self.scope_parents[self.binding_scopes[id.index()]];
+        while let Some(outer) = scope {
+            let candidates: Vec<usize> = (0..self.bindings.len())
+                .filter(|&i| {
+                    self.binding_scopes[i] == outer && self.bindings[i].name == binding.name
+                })
+                .collect();
+            let before = candidates
+                .iter()
+                .rev()
+                .find(|&&i| self.bindings[i].span.start < binding.span.start);
+            if let Some(&i) = before.or_else(|| candidates.first()) {
+                return Some(BindingId(i as u32));
+            }
+            scope = self.scope_parents[outer];
+        }
+        None
+    }
+}
+
+/// Whether node `id` is `ancestor` or inside it.
+fn is_within(program: &Program, id: NodeId, ancestor: NodeId) -> bool {
+    let mut node = Some(id);
+    while let Some(id) = node {
+        if id == ancestor {
+            return true;
+        }
+        node = program.parent(id);
+    }
+    false
+}
+
+/// Work out what every identifier in `program` refers to.
+pub fn resolve(program: &Program) -> Resolution {
+    let mut resolver = Resolver::default();
+    resolver.enter_scope(false);
+    resolver.visit_program(program);
+    resolver.leave_scope();
+    let mut resolution = resolver.resolution;
+    resolution
+        .unresolved
+        .extend(resolver.deferred.into_iter().map(|use_| use_.unresolved));
+    resolution
+        .unresolved
+        .sort_by_key(|unresolved| unresolved.ident);
+    for references in &mut resolution.references {
+        references.sort();
+    }
+    resolution
+}
+
+/// Fill in the `slot` of every identifier `resolution` has one for.
+pub fn annotate(program: &mut Program, resolution: &Resolution) {
+    struct Annotate<'r>(&'r Resolution);
+
+    impl VisitorMut for Annotate<'_> {
+        fn visit_identifier(&mut self, ident: &mut Identifier) {
+            ident.slot = self.0.slot(ident.id);
+        }
+    }
+
+    Annotate(resolution).visit_program(program);
+}
+
+struct Scope {
+    id: ScopeId,
+    names: HashMap<String, BindingId>,
+    /// Whether this is a function's scope. Uses in it are resolved
+    /// against outer scopes only once those are complete.
+    function: bool,
+}
+
+/// A use inside a function body waiting for the scopes around the
+/// function to be complete.
+struct Deferred {
+    unresolved: Unresolved,
+    /// Where the scope the use is in sits on the stack of scopes.
+    level: usize,
+    /// Where the innermost function scope around the use sits. Only
+    /// scopes outside it can still bind the name.
+    function_level: usize,
+}
+
+#[derive(Default)]
+struct Resolver {
+    resolution: Resolution,
+    scopes: Vec<Scope>,
+    deferred: Vec<Deferred>,
+}
+
+impl Resolver {
+    fn bind(&mut self, ident: &Identifier, kind: BindingKind, owner: NodeId) {
+        let id = BindingId(self.resolution.bindings.len() as u32);
+        let scope = self.scopes.last_mut().expect("no scope to bind in");
+        scope.names.insert(ident.value.clone(), id);
+        self.resolution.bindings.push(Binding {
+            name: ident.value.clone(),
+            kind,
+            ident: ident.id,
+            span: ident.span(),
+            owner,
+        });
+        self.resolution.binding_scopes.push(scope.id);
+        self.resolution.references.push(Vec::new());
+        self.resolution.targets.insert(ident.id, id);
+        self.resolution.slots.insert(ident.id, Slot { depth: 0 });
+    }
+
+    fn refer(&mut self, ident: NodeId, binding: BindingId, depth: usize) {
+        self.resolution.targets.insert(ident, binding);
+        self.resolution.slots.insert(
+            ident,
+            Slot {
+                depth: depth as u32,
+            },
+        );
+        self.resolution.references[binding.index()].push(ident);
+    }
+
+    fn enter_scope(&mut self, function: bool) {
+        let id = self.resolution.scope_parents.len();
+        let parent = self.scopes.last().map(|scope| scope.id);
+        self.resolution.scope_parents.push(parent);
+        self.scopes.push(Scope {
+            id,
+            names: HashMap::new(),
+            function,
+        });
+    }
+
+    /// Close the innermost scope, resolving the deferred uses it binds.
+    fn leave_scope(&mut self) {
+        let scope = self.scopes.pop().expect("no scope to leave");
+        let level = self.scopes.len();
+        let deferred = std::mem::take(&mut self.deferred);
+        for mut use_ in deferred {
+            match scope.names.get(&use_.unresolved.name) {
+                Some(&binding) if level < use_.function_level => {
+                    self.refer(use_.unresolved.ident, binding, use_.level - level)
+                }
+                // a scope opened later at this level is not around the use
+                _ => {
+                    use_.function_level = use_.function_level.min(level);
+                    self.deferred.push(use_);
+                }
+            }
+        }
+    }
+}
+
+impl<'ast> Visitor<'ast> for Resolver {
+    fn visit_let_statement(&mut self, stmt: &'ast LetStatement) {
+        // the value is evaluated before the name is bound
+        self.visit_expression(&stmt.value);
+        self.bind(&stmt.name, BindingKind::Let, stmt.id);
+    }
+
+    fn visit_function_literal(&mut self, func: &'ast FunctionLiteral) {
+        self.enter_scope(true);
+        for param in &func.parameters {
+            self.bind(param, BindingKind::Parameter, func.id);
+        }
+        walk_block_statement(self, &func.body);
+        self.leave_scope();
+    }
+
+    fn visit_try_expression(&mut self, exp: &'ast TryExpression) {
+        self.visit_block_statement(&exp.body);
+        self.enter_scope(false);
+        self.bind(&exp.parameter, BindingKind::Catch, exp.id);
+        walk_block_statement(self, &exp.handler);
+        self.leave_scope();
+    }
+
+    /// Only reached for identifiers being used, since the nodes that bind
+    /// names are handled above.
+    fn visit_identifier(&mut self, ident: &'ast Identifier) {
+        let level = self.scopes.len() - 1;
+        let function_level = self.scopes.iter().rposition(|scope| scope.function);
+        // the scopes out to the innermost function's run in order, so
+        // only what they have bound so far is visible
+        let first = function_level.unwrap_or(0);
+        let found = (first..=level)
+            .rev()
+            .find_map(|i| Some((i, *self.scopes[i].names.get(&ident.value)?)));
+        if let Some((i, binding)) = found {
+            return self.refer(ident.id, binding, level - i);
+        }
+        let unresolved = Unresolved {
+            ident: ident.id,
+            name: ident.value.clone(),
+            span: ident.span(),
+            scope: self.scopes[level].id,
+        };
+        match function_level {
+            Some(function_level) => self.deferred.push(Deferred {
+                unresolved,
+                level,
+                function_level,
+            }),
+            None => self.resolution.unresolved.push(unresolved),
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use crate::TokenType;
+use std::fmt;
+
+/// What a node or token of the syntax tree is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+    /// A token the lexer produces.
+    Token(TokenType),
+    Whitespace,
+    /// A `//` comment.
+    Comment,
+
+    Program,
+    LetStatement,
+    ReturnStatement,
+    ThrowStatement,
+    ExpressionStatement,
+    Block,
+    /// A name being bound: a `let` name, parameter or `catch` parameter,
+    /// with the `: type` after it if it has one.
+    Name,
+    /// A type annotation, or one of the types written inside it.
+    Type,
+    Identifier,
+    IntegerLiteral,
+    StringLiteral,
+    BooleanLiteral,
+    PrefixExpression,
+    InfixExpression,
+    /// An expression in parentheses, which the AST has no node for.
+    ParenExpression,
+    IfExpression,
+    TryExpression,
+    FunctionLiteral,
+    ParameterList,
+    CallExpression,
+    ArgumentList,
+    ArrayLiteral,
+    IndexExpression,
+    HashLiteral,
+    HashPair,
+    /// Tokens the parser couldn't make sense of.
+    Error,
+}
+
+impl SyntaxKind {
+    /// Whitespace and comments, which the AST leaves out.
+    pub fn is_trivia(self) -> bool {
+        matches!(self, SyntaxKind::Whitespace | SyntaxKind::Comment)
+    }
+
+    pub fn is_statement(self) -> bool {
+        matches!(
+            self,
+            SyntaxKind::LetStatement
+                | SyntaxKind::ReturnStatement
+                | SyntaxKind::ThrowStatement
+                | SyntaxKind::ExpressionStatement
+                | SyntaxKind::Block
+        )
+    }
+
+    pub fn is_expression(self) -> bool {
+        matches!(
+            self,
+            SyntaxKind::Identifier
+                | SyntaxKind::IntegerLiteral
+                | SyntaxKind::StringLiteral
+                | SyntaxKind::BooleanLiteral
+                | SyntaxKind::PrefixExpression
+                | SyntaxKind::InfixExpression
+                | SyntaxKind::ParenExpression
+                | SyntaxKind::IfExpression
+                | SyntaxKind::TryExpression
+                | SyntaxKind::FunctionLiteral
+                | SyntaxKind::CallExpression
+                | SyntaxKind::ArrayLiteral
+                | SyntaxKind::IndexExpression
+                | SyntaxKind::HashLiteral
+        )
+    }
+}
+
+impl fmt::Display for SyntaxKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SyntaxKind::Token(token_type) => write!(f, "{:?}", token_type),
+            kind => write!(f, "{:?}", kind),
+        }
+    }
+}
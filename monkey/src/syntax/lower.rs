@@ -0,0 +1,373 @@
+//! Deriving the AST from a syntax tree. The tree keeps the tokens the
+//! AST nodes are built from, so both parsing modes give the same AST.
+
+use crate::evaluator::limits::grow_stack;
+use crate::{
+    ArrayLiteral, BlockStatement, Boolean, CallExpression, Expression, ExpressionStatement,
+    FunctionLiteral, HashLiteral, Identifier, IfExpression, IndexExpression, InfixExpression,
+    IntegerLiteral, LetStatement, NodeId, PrefixExpression, Program, ReturnStatement, Statement,
+    StringLiteral, SyntaxKind, SyntaxNode, SyntaxToken, ThrowStatement, Token, TokenType,
+    TryExpression, TypeAnnotation, TypeAnnotationKind,
+};
+use std::rc::Rc;
+
+/// Build the AST for a `Program` node, or say which parts of the tree
+/// don't make a complete program, for example after an edit.
+pub fn lower(root: &SyntaxNode) -> Result<Program, Vec<String>> {
+    if root.kind() != SyntaxKind::Program {
+        return Err(vec![format!("expected a Program node, got {:?}", root)]);
+    }
+    let mut statements = Vec::new();
+    let mut errors = Vec::new();
+    for node in root.children() {
+        match statement(&node) {
+            Ok(stmt) => statements.push(stmt),
+            Err(err) => errors.push(err),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(Program::new(statements, Rc::from(root.text())))
+}
+
+type Lowered<T> = Result<T, String>;
+
+fn incomplete(node: &SyntaxNode) -> String {
+    format!("incomplete {} at {}", node.kind(), node.span().start)
+}
+
+fn token(node: &SyntaxNode, token_type: TokenType) -> Lowered<Token> {
+    node.child_token(token_type)
+        .map(|token| ast_token(&token))
+        .ok_or_else(|| incomplete(node))
+}
+
+/// The token as the lexer would have given it to `Parser`.
+fn ast_token(token: &SyntaxToken) -> Token {
+    let token_type = match token.kind() {
+        SyntaxKind::Token(token_type) => token_type,
+        kind => unreachable!("{} is not a lexer token", kind),
+    };
+    let text = token.text();
+    let literal = match token_type {
+        TokenType::STRING => text[1..].strip_suffix('"').unwrap_or(&text[1..]),
+        _ => text,
+    };
+    Token {
+        r#type: token_type,
+        literal: literal.to_string(),
+        span: token.span(),
+    }
+}
+
+fn child(node: &SyntaxNode, kind: SyntaxKind) -> Lowered<SyntaxNode> {
+    node.children()
+        .into_iter()
+        .find(|child| child.kind() == kind)
+        .ok_or_else(|| incomplete(node))
+}
+
+/// The `n`th expression directly inside `node`.
+fn operand(node: &SyntaxNode, n: usize) -> Lowered<Expression> {
+    let child = node
+        .children()
+        .into_iter()
+        .filter(|child| child.kind().is_expression())
+        .nth(n)
+        .ok_or_else(|| incomplete(node))?;
+    expression(&child)
+}
+
+fn operands(node: &SyntaxNode) -> Lowered<Vec<Expression>> {
+    node.children()
+        .iter()
+        .filter(|child| child.kind().is_expression())
+        .map(expression)
+        .collect()
+}
+
+fn statement(node: &SyntaxNode) -> Lowered<Statement> {
+    Ok(match node.kind() {
+        SyntaxKind::LetStatement => Statement::Let(LetStatement {
+            token: token(node, TokenType::LET)?,
+            id: NodeId::DUMMY,
+            name: name(&child(node, SyntaxKind::Name)?)?,
+            value: operand(node, 0)?,
+        }),
+        SyntaxKind::ReturnStatement => Statement::Return(ReturnStatement {
+            token: token(node, TokenType::RETURN)?,
+            id: NodeId::DUMMY,
+            return_value: operand(node, 0)?,
+        }),
+        SyntaxKind::ThrowStatement => Statement::Throw(ThrowStatement {
+            token: token(node, TokenType::THROW)?,
+            id: NodeId::DUMMY,
+            value: operand(node, 0)?,
+        }),
+        SyntaxKind::ExpressionStatement => Statement::Expression(ExpressionStatement {
+            token: node
+                .first_token()
+                .map(|token| ast_token(&token))
+                .ok_or_else(|| incomplete(node))?,
+            id: NodeId::DUMMY,
+            expression: operand(node, 0)?,
+        }),
+        SyntaxKind::Block => Statement::Block(block(node)?),
+        _ => return Err(unexpected(node)),
+    })
+}
+
+fn unexpected(node: &SyntaxNode) -> String {
+    format!(
+        "unexpected {:?} at {}",
+        node.text().trim(),
+        node.span().start
+    )
+}
+
+fn block(node: &SyntaxNode) -> Lowered<BlockStatement> {
+    let statements = node
+        .children()
+        .iter()
+        .map(statement)
+        .collect::<Lowered<_>>()?;
+    Ok(BlockStatement {
+        token: token(node, TokenType::LBRACE)?,
+        id: NodeId::DUMMY,
+        statements,
+    })
+}
+
+/// The block directly inside `node` after skipping `skip` others.
+fn nth_block(node: &SyntaxNode, skip: usize) -> Lowered<Option<BlockStatement>> {
+    node.children()
+        .iter()
+        .filter(|child| child.kind() == SyntaxKind::Block)
+        .nth(skip)
+        .map(block)
+        .transpose()
+}
+
+fn name(node: &SyntaxNode) -> Lowered<Identifier> {
+    let token = token(node, TokenType::IDENT)?;
+    Ok(Identifier {
+        id: NodeId::DUMMY,
+        slot: None,
+        annotation: optional_type(node)?,
+        value: token.literal.clone(),
+        token,
+    })
+}
+
+/// The `Type` node directly inside `node`, if there is one.
+fn optional_type(node: &SyntaxNode) -> Lowered<Option<TypeAnnotation>> {
+    node.children()
+        .iter()
+        .find(|child| child.kind() == SyntaxKind::Type)
+        .map(type_annotation)
+        .transpose()
+}
+
+fn type_annotation(node: &SyntaxNode) -> Lowered<TypeAnnotation> {
+    let mut types = node
+        .children()
+        .iter()
+        .filter(|child| child.kind() == SyntaxKind::Type)
+        .map(type_annotation)
+        .collect::<Lowered<Vec<_>>>()?;
+    let first = node.first_token().ok_or_else(|| incomplete(node))?;
+    let kind = match first.kind() {
+        SyntaxKind::Token(TokenType::IDENT) => TypeAnnotationKind::Named {
+            name: first.text().to_string(),
+        },
+        SyntaxKind::Token(TokenType::LBRACKET) if types.len() == 1 => TypeAnnotationKind::Array {
+            element: Box::new(types.remove(0)),
+        },
+        SyntaxKind::Token(TokenType::LBRACE) if types.len() == 2 => {
+            let value = Box::new(types.remove(1));
+            TypeAnnotationKind::Hash {
+                key: Box::new(types.remove(0)),
+                value,
+            }
+        }
+        SyntaxKind::Token(TokenType::FUNCTION) if !types.is_empty() => {
+            let return_type = Box::new(types.pop().expect("checked not empty"));
+            TypeAnnotationKind::Function {
+                parameters: types,
+                return_type,
+            }
+        }
+        _ => return Err(incomplete(node)),
+    };
+    Ok(TypeAnnotation {
+        kind,
+        span: node.span(),
+    })
+}
+
+/// Each kind of node with expressions below it is lowered by a function
+/// of its own, so the frames of this recursion stay small.
+fn expression(node: &SyntaxNode) -> Lowered<Expression> {
+    grow_stack(|| lower_expression(node))
+}
+
+fn lower_expression(node: &SyntaxNode) -> Lowered<Expression> {
+    Ok(match node.kind() {
+        SyntaxKind::Identifier => Expression::Identifier(name(node)?),
+        SyntaxKind::IntegerLiteral => integer_literal(node)?,
+        SyntaxKind::StringLiteral => {
+            let token = token(node, TokenType::STRING)?;
+            Expression::String(StringLiteral {
+                id: NodeId::DUMMY,
+                value: token.literal.clone(),
+                token,
+            })
+        }
+        SyntaxKind::BooleanLiteral => {
+            let token = node
+                .first_token()
+                .map(|token| ast_token(&token))
+                .ok_or_else(|| incomplete(node))?;
+            Expression::Boolean(Boolean {
+                id: NodeId::DUMMY,
+                value: token.r#type == TokenType::TRUE,
+                token,
+            })
+        }
+        SyntaxKind::PrefixExpression => prefix_expression(node)?,
+        SyntaxKind::InfixExpression => infix_expression(node)?,
+        SyntaxKind::ParenExpression => operand(node, 0)?,
+        SyntaxKind::IfExpression => if_expression(node)?,
+        SyntaxKind::TryExpression => try_expression(node)?,
+        SyntaxKind::FunctionLiteral => function_literal(node)?,
+        SyntaxKind::CallExpression => call_expression(node)?,
+        SyntaxKind::ArrayLiteral => Expression::Array(ArrayLiteral {
+            token: token(node, TokenType::LBRACKET)?,
+            id: NodeId::DUMMY,
+            elements: operands(node)?,
+        }),
+        SyntaxKind::IndexExpression => index_expression(node)?,
+        SyntaxKind::HashLiteral => hash_literal(node)?,
+        _ => return Err(unexpected(node)),
+    })
+}
+
+fn prefix_expression(node: &SyntaxNode) -> Lowered<Expression> {
+    let token = node
+        .first_token()
+        .map(|token| ast_token(&token))
+        .ok_or_else(|| incomplete(node))?;
+    Ok(Expression::Prefix(PrefixExpression {
+        id: NodeId::DUMMY,
+        operator: token.literal.clone(),
+        token,
+        right: Box::new(operand(node, 0)?),
+    }))
+}
+
+fn infix_expression(node: &SyntaxNode) -> Lowered<Expression> {
+    let token = node
+        .children_with_tokens()
+        .into_iter()
+        .find_map(|child| match child {
+            crate::SyntaxElement::Token(token) if !token.kind().is_trivia() => {
+                Some(ast_token(&token))
+            }
+            _ => None,
+        })
+        .ok_or_else(|| incomplete(node))?;
+    Ok(Expression::Infix(InfixExpression {
+        id: NodeId::DUMMY,
+        operator: token.literal.clone(),
+        token,
+        left: Box::new(operand(node, 0)?),
+        right: Box::new(operand(node, 1)?),
+    }))
+}
+
+fn if_expression(node: &SyntaxNode) -> Lowered<Expression> {
+    Ok(Expression::If(IfExpression {
+        token: token(node, TokenType::IF)?,
+        id: NodeId::DUMMY,
+        condition: Box::new(operand(node, 0)?),
+        consequence: nth_block(node, 0)?.ok_or_else(|| incomplete(node))?,
+        alternative: nth_block(node, 1)?,
+    }))
+}
+
+fn try_expression(node: &SyntaxNode) -> Lowered<Expression> {
+    Ok(Expression::Try(TryExpression {
+        token: token(node, TokenType::TRY)?,
+        id: NodeId::DUMMY,
+        body: nth_block(node, 0)?.ok_or_else(|| incomplete(node))?,
+        parameter: name(&child(node, SyntaxKind::Name)?)?,
+        handler: nth_block(node, 1)?.ok_or_else(|| incomplete(node))?,
+    }))
+}
+
+fn function_literal(node: &SyntaxNode) -> Lowered<Expression> {
+    Ok(Expression::Function(FunctionLiteral {
+        token: token(node, TokenType::FUNCTION)?,
+        id: NodeId::DUMMY,
+        parameters: child(node, SyntaxKind::ParameterList)?
+            .children()
+            .iter()
+            .map(name)
+            .collect::<Lowered<_>>()?,
+        return_type: optional_type(node)?,
+        body: Rc::new(nth_block(node, 0)?.ok_or_else(|| incomplete(node))?),
+    }))
+}
+
+fn call_expression(node: &SyntaxNode) -> Lowered<Expression> {
+    let arguments = child(node, SyntaxKind::ArgumentList)?;
+    Ok(Expression::Call(CallExpression {
+        token: token(&arguments, TokenType::LPAREN)?,
+        id: NodeId::DUMMY,
+        function: Box::new(operand(node, 0)?),
+        arguments: operands(&arguments)?,
+    }))
+}
+
+fn index_expression(node: &SyntaxNode) -> Lowered<Expression> {
+    Ok(Expression::Index(IndexExpression {
+        token: token(node, TokenType::LBRACKET)?,
+        id: NodeId::DUMMY,
+        left: Box::new(operand(node, 0)?),
+        index: Box::new(operand(node, 1)?),
+    }))
+}
+
+fn hash_literal(node: &SyntaxNode) -> Lowered<Expression> {
+    Ok(Expression::Hash(HashLiteral {
+        token: token(node, TokenType::LBRACE)?,
+        id: NodeId::DUMMY,
+        pairs: node
+            .children()
+            .iter()
+            .map(|pair| Ok((operand(pair, 0)?, operand(pair, 1)?)))
+            .collect::<Lowered<_>>()?,
+    }))
+}
+
+fn integer_literal(node: &SyntaxNode) -> Lowered<Expression> {
+    let token = token(node, TokenType::INT)?;
+    match token.literal.parse::<i64>() {
+        Ok(value) => Ok(Expression::Integer(IntegerLiteral {
+            token,
+            id: NodeId::DUMMY,
+            value,
+        })),
+        #[cfg(feature = "bigint")]
+        Err(_) => Ok(Expression::BigInteger(crate::BigIntegerLiteral {
+            id: NodeId::DUMMY,
+            value: token.literal.parse().map_err(|_| incomplete(node))?,
+            token,
+        })),
+        #[cfg(not(feature = "bigint"))]
+        Err(_) => Err(crate::parser::parser::integer_too_large_message(
+            &token.literal,
+        )),
+    }
+}
@@ -0,0 +1,11 @@
+pub mod green;
+pub mod kind;
+pub mod lower;
+pub mod parse;
+pub mod red;
+
+pub use green::*;
+pub use kind::*;
+pub use lower::*;
+pub use parse::*;
+pub use red::*;
@@ -0,0 +1,564 @@
+//! The lossless parsing mode. It follows the same grammar as `Parser`
+//! but keeps every byte of the source, whitespace and comments included,
+//! and never drops tokens: what it can't parse ends up in `Error` nodes.
+
+use crate::evaluator::limits::grow_stack;
+#[cfg(not(feature = "bigint"))]
+use crate::parser::parser::integer_too_large_message;
+use crate::parser::parser::{nesting_error, no_prefix_parse_fn_message, MAX_NESTING};
+use crate::{
+    lower, Diagnostic, GreenNodeBuilder, Lexer, Precedence, Program, Span, SyntaxKind, SyntaxNode,
+    TokenType,
+};
+
+/// A parsed syntax tree, along with what went wrong parsing it.
+pub struct SyntaxTree {
+    root: SyntaxNode,
+    errors: Vec<Diagnostic>,
+}
+
+impl SyntaxTree {
+    pub fn root(&self) -> &SyntaxNode {
+        &self.root
+    }
+
+    /// One error per problem found, worded like `Parser`'s and pointing
+    /// at the token where parsing went wrong.
+    pub fn errors(&self) -> &[Diagnostic] {
+        &self.errors
+    }
+
+    /// The AST for the tree, or the parse errors if there were any.
+    pub fn to_program(&self) -> Result<Program, Vec<String>> {
+        if !self.errors.is_empty() {
+            return Err(self.errors.iter().map(|err| err.message.clone()).collect());
+        }
+        lower(&self.root)
+    }
+}
+
+/// Parse `source` into a lossless syntax tree, whose text is always
+/// exactly `source`.
+pub fn parse_syntax(source: &str) -> SyntaxTree {
+    let mut parser = SyntaxParser {
+        tokens: lex(source),
+        pos: 0,
+        offset: 0,
+        builder: GreenNodeBuilder::new(),
+        errors: Vec::new(),
+        depth: 0,
+        too_deep: false,
+    };
+    parser.builder.start_node(SyntaxKind::Program);
+    while !parser.at(TokenType::EOF) {
+        parser.statement_or_error();
+    }
+    parser.eat_trivia();
+    parser.builder.finish_node();
+    SyntaxTree {
+        root: SyntaxNode::new_root(parser.builder.finish()),
+        errors: parser.errors,
+    }
+}
+
+/// Split `source` into tokens with the whitespace and comments between
+/// them, so that together they cover every byte.
+fn lex(source: &str) -> Vec<(SyntaxKind, &str)> {
+    let mut lexer = Lexer::new(source.to_string());
+    let mut tokens = Vec::new();
+    let mut last = 0;
+    loop {
+        let tok = lexer.next_token();
+        let mut gap = &source[last..tok.span.start];
+        while !gap.is_empty() {
+            let len = if gap.starts_with("//") {
+                let line = gap.find('\n').map_or(gap, |end| &gap[..end]);
+                line.trim_end_matches('\r').len()
+            } else {
+                gap.find("//").unwrap_or(gap.len())
+            };
+            let kind = if gap.starts_with("//") {
+                SyntaxKind::Comment
+            } else {
+                SyntaxKind::Whitespace
+            };
+            tokens.push((kind, &gap[..len]));
+            gap = &gap[len..];
+        }
+        if tok.r#type == TokenType::EOF {
+            return tokens;
+        }
+        tokens.push((
+            SyntaxKind::Token(tok.r#type),
+            &source[tok.span.start..tok.span.end],
+        ));
+        last = tok.span.end;
+    }
+}
+
+/// The node an expression starting with `token_type` becomes, if any.
+fn prefix_kind(token_type: TokenType) -> Option<SyntaxKind> {
+    Some(match token_type {
+        TokenType::IDENT => SyntaxKind::Identifier,
+        TokenType::INT => SyntaxKind::IntegerLiteral,
+        TokenType::STRING => SyntaxKind::StringLiteral,
+        TokenType::TRUE | TokenType::FALSE => SyntaxKind::BooleanLiteral,
+        TokenType::BANG | TokenType::MINUS => SyntaxKind::PrefixExpression,
+        TokenType::LPAREN => SyntaxKind::ParenExpression,
+        TokenType::IF => SyntaxKind::IfExpression,
+        TokenType::TRY => SyntaxKind::TryExpression,
+        TokenType::FUNCTION => SyntaxKind::FunctionLiteral,
+        TokenType::LBRACKET => SyntaxKind::ArrayLiteral,
+        TokenType::LBRACE => SyntaxKind::HashLiteral,
+        _ => return None,
+    })
+}
+
+struct SyntaxParser<'a> {
+    tokens: Vec<(SyntaxKind, &'a str)>,
+    /// The next token to add to the tree.
+    pos: usize,
+    /// Where the next token to add starts in the source.
+    offset: usize,
+    builder: GreenNodeBuilder,
+    errors: Vec<Diagnostic>,
+    /// How deeply the expression being parsed is nested.
+    depth: usize,
+    /// Set once nesting went past `MAX_NESTING` and the rest of the input
+    /// was put in an `Error` node.
+    too_deep: bool,
+}
+
+impl SyntaxParser<'_> {
+    /// The type of the next token that isn't trivia.
+    fn current(&self) -> TokenType {
+        self.tokens[self.pos..]
+            .iter()
+            .find_map(|(kind, _)| match kind {
+                SyntaxKind::Token(token_type) => Some(*token_type),
+                _ => None,
+            })
+            .unwrap_or(TokenType::EOF)
+    }
+
+    /// The text of the next token that isn't trivia.
+    fn current_text(&self) -> &str {
+        self.tokens[self.pos..]
+            .iter()
+            .find(|(kind, _)| !kind.is_trivia())
+            .map_or("", |(_, text)| text)
+    }
+
+    fn at(&self, token_type: TokenType) -> bool {
+        self.current() == token_type
+    }
+
+    /// Report a problem at the next token that isn't trivia.
+    fn error(&mut self, message: String) {
+        let mut start = self.offset;
+        let mut len = 0;
+        for (kind, text) in &self.tokens[self.pos..] {
+            if !kind.is_trivia() {
+                len = text.len();
+                break;
+            }
+            start += text.len();
+        }
+        self.errors
+            .push(Diagnostic::error(message).with_span(Span::new(start, start + len)));
+    }
+
+    /// Add the trivia before the next token to the node being built.
+    fn eat_trivia(&mut self) {
+        while let Some((kind, text)) = self.tokens.get(self.pos) {
+            if !kind.is_trivia() {
+                break;
+            }
+            self.builder.token(*kind, text);
+            self.pos += 1;
+            self.offset += text.len();
+        }
+    }
+
+    fn bump(&mut self) {
+        self.eat_trivia();
+        if let Some((kind, text)) = self.tokens.get(self.pos) {
+            self.builder.token(*kind, text);
+            self.pos += 1;
+            self.offset += text.len();
+        }
+    }
+
+    /// Start a node at the next token, leaving the trivia before it to
+    /// the enclosing node.
+    fn start_node(&mut self, kind: SyntaxKind) {
+        self.eat_trivia();
+        self.builder.start_node(kind);
+    }
+
+    fn finish_node(&mut self) {
+        self.builder.finish_node();
+    }
+
+    fn expect(&mut self, token_type: TokenType) -> bool {
+        if self.at(token_type) {
+            self.bump();
+            true
+        } else {
+            let msg = format!(
+                "expected next token to be {:?}, got {:?} instead",
+                token_type,
+                self.current()
+            );
+            self.error(msg);
+            false
+        }
+    }
+
+    fn eat_semicolon(&mut self) {
+        if self.at(TokenType::SEMICOLON) {
+            self.bump();
+        }
+    }
+
+    /// Parse a statement. If that fails without using up a single token,
+    /// put the next one in an `Error` node so parsing moves on.
+    fn statement_or_error(&mut self) {
+        let pos = self.pos;
+        if !self.statement()
+            && self.tokens[pos..self.pos]
+                .iter()
+                .all(|(k, _)| k.is_trivia())
+        {
+            self.start_node(SyntaxKind::Error);
+            self.bump();
+            self.finish_node();
+        }
+    }
+
+    fn statement(&mut self) -> bool {
+        match self.current() {
+            TokenType::LET => self.let_statement(),
+            TokenType::RETURN => self.keyword_statement(SyntaxKind::ReturnStatement),
+            TokenType::THROW => self.keyword_statement(SyntaxKind::ThrowStatement),
+            // fail before starting a statement node that would stay empty
+            token_type if prefix_kind(token_type).is_none() => self.prefix(),
+            _ => {
+                self.start_node(SyntaxKind::ExpressionStatement);
+                let ok = self.expression(Precedence::Lowest);
+                self.eat_semicolon();
+                self.finish_node();
+                ok
+            }
+        }
+    }
+
+    fn let_statement(&mut self) -> bool {
+        self.start_node(SyntaxKind::LetStatement);
+        self.bump();
+        let ok = self.declared_name() && self.expect(TokenType::ASSIGN) && {
+            let ok = self.expression(Precedence::Lowest);
+            self.eat_semicolon();
+            ok
+        };
+        self.finish_node();
+        ok
+    }
+
+    /// `return` or `throw` and the expression after it.
+    fn keyword_statement(&mut self, kind: SyntaxKind) -> bool {
+        self.start_node(kind);
+        self.bump();
+        let ok = self.expression(Precedence::Lowest);
+        self.eat_semicolon();
+        self.finish_node();
+        ok
+    }
+
+    fn name(&mut self) -> bool {
+        if !self.at(TokenType::IDENT) {
+            return self.expect(TokenType::IDENT);
+        }
+        self.start_node(SyntaxKind::Name);
+        self.bump();
+        self.finish_node();
+        true
+    }
+
+    /// A name that may be followed by `: type`.
+    fn declared_name(&mut self) -> bool {
+        if !self.at(TokenType::IDENT) {
+            return self.expect(TokenType::IDENT);
+        }
+        self.start_node(SyntaxKind::Name);
+        self.bump();
+        let ok = !self.at(TokenType::COLON) || {
+            self.bump();
+            self.type_annotation()
+        };
+        self.finish_node();
+        ok
+    }
+
+    fn type_annotation(&mut self) -> bool {
+        let depth = self.depth;
+        let ok = self.nest() && self.nested_type_annotation();
+        self.depth = depth;
+        ok
+    }
+
+    fn nested_type_annotation(&mut self) -> bool {
+        let token_type = self.current();
+        if !matches!(
+            token_type,
+            TokenType::IDENT | TokenType::LBRACKET | TokenType::LBRACE | TokenType::FUNCTION
+        ) {
+            self.error(format!("expected a type, got {:?} instead", token_type));
+            return false;
+        }
+        self.start_node(SyntaxKind::Type);
+        self.bump();
+        let ok = match token_type {
+            TokenType::LBRACKET => self.type_annotation() && self.expect(TokenType::RBRACKET),
+            TokenType::LBRACE => {
+                self.type_annotation()
+                    && self.expect(TokenType::COLON)
+                    && self.type_annotation()
+                    && self.expect(TokenType::RBRACE)
+            }
+            TokenType::FUNCTION => {
+                self.expect(TokenType::LPAREN)
+                    && (self.at(TokenType::RPAREN) || {
+                        let mut ok = self.type_annotation();
+                        while ok && self.at(TokenType::COMMA) {
+                            self.bump();
+                            ok = self.type_annotation();
+                        }
+                        ok
+                    })
+                    && self.expect(TokenType::RPAREN)
+                    && self.expect(TokenType::ARROW)
+                    && self.type_annotation()
+            }
+            _ => true,
+        };
+        self.finish_node();
+        ok
+    }
+
+    fn block(&mut self) -> bool {
+        if !self.at(TokenType::LBRACE) {
+            return self.expect(TokenType::LBRACE);
+        }
+        self.start_node(SyntaxKind::Block);
+        self.bump();
+        while !self.at(TokenType::RBRACE) && !self.at(TokenType::EOF) {
+            self.statement_or_error();
+        }
+        if self.at(TokenType::RBRACE) {
+            self.bump();
+        } else if !self.too_deep {
+            self.error("expected `}`, got EOF instead".to_string());
+        }
+        self.finish_node();
+        true
+    }
+
+    fn expression(&mut self, precedence: Precedence) -> bool {
+        let depth = self.depth;
+        let ok = grow_stack(|| self.nested_expression(precedence));
+        self.depth = depth;
+        ok
+    }
+
+    fn nested_expression(&mut self, precedence: Precedence) -> bool {
+        self.eat_trivia();
+        let checkpoint = self.builder.checkpoint();
+        if !self.nest() || !self.prefix() {
+            return false;
+        }
+        loop {
+            let token_type = self.current();
+            let binding = Precedence::of(token_type);
+            if token_type == TokenType::SEMICOLON || precedence >= binding {
+                return true;
+            }
+            let ok = match token_type {
+                TokenType::LPAREN => {
+                    self.builder
+                        .start_node_at(checkpoint, SyntaxKind::CallExpression);
+                    self.start_node(SyntaxKind::ArgumentList);
+                    let ok = self.expression_list(TokenType::RPAREN);
+                    self.finish_node();
+                    ok
+                }
+                TokenType::LBRACKET => {
+                    self.builder
+                        .start_node_at(checkpoint, SyntaxKind::IndexExpression);
+                    self.bump();
+                    self.expression(Precedence::Lowest) && self.expect(TokenType::RBRACKET)
+                }
+                _ => {
+                    self.builder
+                        .start_node_at(checkpoint, SyntaxKind::InfixExpression);
+                    self.bump();
+                    self.expression(binding)
+                }
+            };
+            self.finish_node();
+            if !ok {
+                return false;
+            }
+        }
+    }
+
+    /// Go one level deeper, or put the rest of the input in an `Error`
+    /// node once past `MAX_NESTING`.
+    fn nest(&mut self) -> bool {
+        self.depth += 1;
+        if self.depth <= MAX_NESTING {
+            return true;
+        }
+        self.error(nesting_error());
+        self.too_deep = true;
+        self.start_node(SyntaxKind::Error);
+        while !self.at(TokenType::EOF) {
+            self.bump();
+        }
+        self.finish_node();
+        false
+    }
+
+    fn prefix(&mut self) -> bool {
+        let token_type = self.current();
+        let kind = match prefix_kind(token_type) {
+            Some(kind) => kind,
+            None => {
+                let msg = no_prefix_parse_fn_message(token_type, self.current_text());
+                self.error(msg);
+                return false;
+            }
+        };
+        self.start_node(kind);
+        let ok = match kind {
+            SyntaxKind::PrefixExpression => {
+                self.bump();
+                self.expression(Precedence::Prefix)
+            }
+            SyntaxKind::ParenExpression => {
+                self.bump();
+                self.expression(Precedence::Lowest) && self.expect(TokenType::RPAREN)
+            }
+            SyntaxKind::IfExpression => self.if_expression(),
+            SyntaxKind::TryExpression => self.try_expression(),
+            SyntaxKind::FunctionLiteral => self.function_literal(),
+            SyntaxKind::ArrayLiteral => self.expression_list(TokenType::RBRACKET),
+            SyntaxKind::HashLiteral => self.hash_literal(),
+            #[cfg(not(feature = "bigint"))]
+            SyntaxKind::IntegerLiteral if self.current_text().parse::<i64>().is_err() => {
+                let msg = integer_too_large_message(self.current_text());
+                self.error(msg);
+                self.bump();
+                false
+            }
+            _ => {
+                self.bump();
+                true
+            }
+        };
+        self.finish_node();
+        ok
+    }
+
+    fn if_expression(&mut self) -> bool {
+        self.bump();
+        self.expect(TokenType::LPAREN)
+            && self.expression(Precedence::Lowest)
+            && self.expect(TokenType::RPAREN)
+            && self.block()
+            && (!self.at(TokenType::ELSE) || {
+                self.bump();
+                self.block()
+            })
+    }
+
+    fn try_expression(&mut self) -> bool {
+        self.bump();
+        self.block()
+            && self.expect(TokenType::CATCH)
+            && self.expect(TokenType::LPAREN)
+            && self.name()
+            && self.expect(TokenType::RPAREN)
+            && self.block()
+    }
+
+    fn function_literal(&mut self) -> bool {
+        self.bump();
+        if !self.at(TokenType::LPAREN) {
+            return self.expect(TokenType::LPAREN);
+        }
+        self.start_node(SyntaxKind::ParameterList);
+        self.bump();
+        let ok = if self.at(TokenType::RPAREN) {
+            self.bump();
+            true
+        } else {
+            self.declared_name() && self.names() && self.expect(TokenType::RPAREN)
+        };
+        self.finish_node();
+        let ok = ok
+            && (!self.at(TokenType::ARROW) || {
+                self.bump();
+                self.type_annotation()
+            });
+        ok && self.block()
+    }
+
+    /// The `, name` pairs after a parameter list's first name.
+    fn names(&mut self) -> bool {
+        while self.at(TokenType::COMMA) {
+            self.bump();
+            if !self.declared_name() {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// The opening token, comma-separated expressions and `end`.
+    fn expression_list(&mut self, end: TokenType) -> bool {
+        self.bump();
+        if self.at(end) {
+            self.bump();
+            return true;
+        }
+        if !self.expression(Precedence::Lowest) {
+            return false;
+        }
+        while self.at(TokenType::COMMA) {
+            self.bump();
+            if !self.expression(Precedence::Lowest) {
+                return false;
+            }
+        }
+        self.expect(end)
+    }
+
+    fn hash_literal(&mut self) -> bool {
+        self.bump();
+        while !self.at(TokenType::RBRACE) {
+            self.start_node(SyntaxKind::HashPair);
+            let ok = self.expression(Precedence::Lowest)
+                && self.expect(TokenType::COLON)
+                && self.expression(Precedence::Lowest);
+            self.finish_node();
+            if !ok {
+                return false;
+            }
+            if !self.at(TokenType::RBRACE) && !self.expect(TokenType::COMMA) {
+                return false;
+            }
+        }
+        self.expect(TokenType::RBRACE)
+    }
+}
@@ -0,0 +1,302 @@
+//! The red tree: a view of a green tree with parent links and absolute
+//! offsets, built lazily as it is walked.
+
+use crate::{GreenElement, GreenNode, GreenToken, Span, SyntaxKind, TokenType};
+use std::fmt;
+use std::rc::Rc;
+
+#[derive(Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+struct NodeData {
+    green: Rc<GreenNode>,
+    parent: Option<SyntaxNode>,
+    /// Where the node sits among its parent's children.
+    index: usize,
+    offset: usize,
+}
+
+/// A node holds its parent, so dropping the last node of a deep path
+/// would drop the whole path one level per call. Walk it in a loop.
+impl Drop for NodeData {
+    fn drop(&mut self) {
+        let mut parent = self.parent.take();
+        while let Some(SyntaxNode(node)) = parent {
+            parent = match Rc::try_unwrap(node) {
+                Ok(mut node) => node.parent.take(),
+                Err(_) => None,
+            };
+        }
+    }
+}
+
+impl SyntaxNode {
+    pub fn new_root(green: Rc<GreenNode>) -> SyntaxNode {
+        SyntaxNode(Rc::new(NodeData {
+            green,
+            parent: None,
+            index: 0,
+            offset: 0,
+        }))
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.green.kind()
+    }
+
+    pub fn green(&self) -> &Rc<GreenNode> {
+        &self.0.green
+    }
+
+    pub fn span(&self) -> Span {
+        Span::new(self.0.offset, self.0.offset + self.0.green.len())
+    }
+
+    /// The node's source text, trivia included.
+    pub fn text(&self) -> String {
+        self.0.green.to_string()
+    }
+
+    pub fn parent(&self) -> Option<SyntaxNode> {
+        self.0.parent.clone()
+    }
+
+    pub fn children_with_tokens(&self) -> Vec<SyntaxElement> {
+        let mut offset = self.0.offset;
+        let mut children = Vec::new();
+        for (index, child) in self.0.green.children().iter().enumerate() {
+            children.push(match child {
+                GreenElement::Node(green) => SyntaxElement::Node(SyntaxNode(Rc::new(NodeData {
+                    green: green.clone(),
+                    parent: Some(self.clone()),
+                    index,
+                    offset,
+                }))),
+                GreenElement::Token(_) => SyntaxElement::Token(SyntaxToken {
+                    parent: self.clone(),
+                    index,
+                    offset,
+                }),
+            });
+            offset += child.len();
+        }
+        children
+    }
+
+    pub fn children(&self) -> Vec<SyntaxNode> {
+        self.children_with_tokens()
+            .into_iter()
+            .filter_map(|child| match child {
+                SyntaxElement::Node(node) => Some(node),
+                SyntaxElement::Token(_) => None,
+            })
+            .collect()
+    }
+
+    /// This node and every node below it, in preorder.
+    pub fn descendants(&self) -> Vec<SyntaxNode> {
+        let mut nodes = vec![self.clone()];
+        self.preorder(|element| {
+            if let SyntaxElement::Node(node) = element {
+                nodes.push(node.clone());
+            }
+            true
+        });
+        nodes
+    }
+
+    /// Every token below this node, trivia included, in source order.
+    pub fn tokens(&self) -> Vec<SyntaxToken> {
+        let mut tokens = Vec::new();
+        self.preorder(|element| {
+            if let SyntaxElement::Token(token) = element {
+                tokens.push(token.clone());
+            }
+            true
+        });
+        tokens
+    }
+
+    /// The first token below this node that isn't trivia.
+    pub fn first_token(&self) -> Option<SyntaxToken> {
+        let mut first = None;
+        self.preorder(|element| match element {
+            SyntaxElement::Token(token) if !token.kind().is_trivia() => {
+                first = Some(token.clone());
+                false
+            }
+            _ => true,
+        });
+        first
+    }
+
+    /// Pass every element below this node to `visit` in source order,
+    /// until it returns false. The walk keeps its own stack, so a deep
+    /// tree such as a long chain of `+` doesn't overflow the thread's.
+    fn preorder(&self, mut visit: impl FnMut(&SyntaxElement) -> bool) {
+        let mut pending = self.children_with_tokens();
+        pending.reverse();
+        while let Some(element) = pending.pop() {
+            if !visit(&element) {
+                return;
+            }
+            if let SyntaxElement::Node(node) = &element {
+                pending.extend(node.children_with_tokens().into_iter().rev());
+            }
+        }
+    }
+
+    /// The token of type `token_type` directly inside this node.
+    pub fn child_token(&self, token_type: TokenType) -> Option<SyntaxToken> {
+        self.children_with_tokens()
+            .into_iter()
+            .find_map(|child| match child {
+                SyntaxElement::Token(token) if token.kind() == SyntaxKind::Token(token_type) => {
+                    Some(token)
+                }
+                _ => None,
+            })
+    }
+
+    /// The root of a new tree with this node replaced. Everything
+    /// outside the path from the root down to this node is shared with
+    /// the old tree.
+    pub fn replace_with(&self, replacement: Rc<GreenNode>) -> Rc<GreenNode> {
+        match &self.0.parent {
+            None => replacement,
+            Some(parent) => {
+                let green = parent
+                    .green()
+                    .replace_child(self.0.index, GreenElement::Node(replacement));
+                parent.replace_with(Rc::new(green))
+            }
+        }
+    }
+}
+
+impl PartialEq for SyntaxNode {
+    fn eq(&self, other: &SyntaxNode) -> bool {
+        self.0.offset == other.0.offset && Rc::ptr_eq(&self.0.green, &other.0.green)
+    }
+}
+
+impl Eq for SyntaxNode {}
+
+impl fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0.green)
+    }
+}
+
+/// `{:?}` shows the node's kind and span; `{:#?}` the whole subtree, one
+/// element per line.
+impl fmt::Debug for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let span = self.span();
+        write!(f, "{}@{}..{}", self.kind(), span.start, span.end)?;
+        if f.alternate() {
+            let mut depth = 1;
+            dump_children(self, &mut depth, f)?;
+        }
+        Ok(())
+    }
+}
+
+fn dump_children(node: &SyntaxNode, depth: &mut usize, f: &mut fmt::Formatter) -> fmt::Result {
+    for child in node.children_with_tokens() {
+        write!(f, "\n{}", "  ".repeat(*depth))?;
+        match child {
+            SyntaxElement::Node(node) => {
+                write!(f, "{:?}", node)?;
+                *depth += 1;
+                dump_children(&node, depth, f)?;
+                *depth -= 1;
+            }
+            SyntaxElement::Token(token) => write!(f, "{:?}", token)?,
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct SyntaxToken {
+    parent: SyntaxNode,
+    index: usize,
+    offset: usize,
+}
+
+impl SyntaxToken {
+    pub fn green(&self) -> &Rc<GreenToken> {
+        match &self.parent.green().children()[self.index] {
+            GreenElement::Token(token) => token,
+            GreenElement::Node(_) => unreachable!("a token's index points at a node"),
+        }
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.green().kind()
+    }
+
+    pub fn text(&self) -> &str {
+        self.green().text()
+    }
+
+    pub fn span(&self) -> Span {
+        Span::new(self.offset, self.offset + self.text().len())
+    }
+
+    pub fn parent(&self) -> SyntaxNode {
+        self.parent.clone()
+    }
+
+    /// The root of a new tree with this token replaced.
+    pub fn replace_with(&self, replacement: GreenToken) -> Rc<GreenNode> {
+        let green = self
+            .parent
+            .green()
+            .replace_child(self.index, GreenElement::Token(Rc::new(replacement)));
+        self.parent.replace_with(Rc::new(green))
+    }
+}
+
+impl fmt::Display for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+impl fmt::Debug for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let span = self.span();
+        write!(
+            f,
+            "{}@{}..{} {:?}",
+            self.kind(),
+            span.start,
+            span.end,
+            self.text()
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            SyntaxElement::Node(node) => node.kind(),
+            SyntaxElement::Token(token) => token.kind(),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            SyntaxElement::Node(node) => node.span(),
+            SyntaxElement::Token(token) => token.span(),
+        }
+    }
+}
@@ -23,7 +23,16 @@ impl KeyWord {
                 ("if".to_string(), TokenType::IF),
                 ("else".to_string(), TokenType::ELSE),
                 ("return".to_string(), TokenType::RETURN),
+                ("try".to_string(), TokenType::TRY),
+                ("catch".to_string(), TokenType::CATCH),
+                ("throw".to_string(), TokenType::THROW),
             ]),
         }
     }
 }
+
+impl Default for KeyWord {
+    fn default() -> Self {
+        KeyWord::new()
+    }
+}
@@ -1,4 +1,9 @@
-mod keywords;
+pub mod keywords;
+pub mod span;
 pub mod token;
+pub mod trivia;
 
+pub use keywords::*;
+pub use span::*;
 pub use token::*;
+pub use trivia::*;
@@ -1,12 +1,15 @@
 use crate::token::keywords::KeyWord;
+use crate::Span;
 use crate::TokenType::IDENT;
 
 #[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum TokenType {
     ILLEGAL,
     EOF,
     IDENT,
     INT,
+    STRING,
     ASSIGN,
     PLUS,
     MINUS,
@@ -19,10 +22,15 @@ pub enum TokenType {
     NotEq,
     COMMA,
     SEMICOLON,
+    COLON,
+    /// `->`, before a function's return type.
+    ARROW,
     LPAREN,
     RPAREN,
     LBRACE,
     RBRACE,
+    LBRACKET,
+    RBRACKET,
     FUNCTION,
     LET,
     TRUE,
@@ -30,12 +38,17 @@ pub enum TokenType {
     IF,
     ELSE,
     RETURN,
+    TRY,
+    CATCH,
+    THROW,
 }
 
 #[derive(Eq, PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Token {
     pub r#type: TokenType,
     pub literal: String,
+    pub span: Span,
 }
 
 impl Token {
@@ -43,6 +56,7 @@ impl Token {
         Token {
             r#type: token_type,
             literal,
+            span: Span::default(),
         }
     }
 
@@ -0,0 +1,11 @@
+use crate::Span;
+
+/// A `//` comment, running to the end of its line. The lexer skips
+/// comments like whitespace but keeps them, so tools such as the
+/// formatter can put them back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    /// The comment's text, including the leading `//`.
+    pub text: String,
+    pub span: Span,
+}
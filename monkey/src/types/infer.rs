@@ -0,0 +1,696 @@
+//! Hindley–Milner inference over a resolved program, made gradual by
+//! `any`: whatever the checker can't type is `any`, and `any` agrees
+//! with every type, so unannotated code is only reported where its own
+//! uses contradict each other.
+
+use super::types::Type;
+use crate::evaluator::limits::grow_stack;
+use crate::{
+    BindingId, BlockStatement, Diagnostic, Expression, FunctionLiteral, Identifier, LetStatement,
+    NodeId, NodeRef, Program, Resolution, Span, Statement, TypeAnnotation, TypeAnnotationKind,
+};
+use std::collections::HashMap;
+
+/// The types `check_types` found, and what it found wrong.
+#[derive(Debug, Clone, Default)]
+pub struct TypeCheck {
+    types: HashMap<NodeId, Type>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl TypeCheck {
+    /// The type of an expression, or of the name a `let`, parameter or
+    /// `catch` binds. Type variables left in it can be anything.
+    pub fn type_of(&self, node: NodeId) -> Option<&Type> {
+        self.types.get(&node)
+    }
+
+    /// Type errors, with the codes `type-mismatch` and `unknown-type`,
+    /// in the order they were found.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+}
+
+/// Infer the type of every expression in `program`, checking it against
+/// the annotations it has.
+///
+/// A `let` binding without an annotation is generalized, so `let id =
+/// fn(x) { x };` can be called with an int and then with a string. Names
+/// used before their `let` has been checked, and names the resolver
+/// couldn't find that aren't builtins, are `any`.
+pub fn check_types(program: &Program, resolution: &Resolution) -> TypeCheck {
+    let mut checker = Checker {
+        resolution,
+        substitution: Vec::new(),
+        trail: Vec::new(),
+        bindings: HashMap::new(),
+        environment: Vec::new(),
+        functions: Vec::new(),
+        additions: Vec::new(),
+        types: HashMap::new(),
+        diagnostics: Vec::new(),
+    };
+    checker.statements(&program.statements);
+
+    for (ty, operator, span) in std::mem::take(&mut checker.additions) {
+        let ty = checker.resolve(&ty);
+        if !matches!(ty, Type::Int | Type::String | Type::Any | Type::Var(_)) {
+            checker.error(span, format!("`{}` can't be applied to `{}`", operator, ty));
+        }
+    }
+    let types = std::mem::take(&mut checker.types)
+        .into_iter()
+        .map(|(id, ty)| (id, checker.resolve(&ty)))
+        .collect();
+    TypeCheck {
+        types,
+        diagnostics: checker.diagnostics,
+    }
+}
+
+/// A binding's type, with the variables each use may pick afresh.
+#[derive(Debug, Clone)]
+struct Scheme {
+    vars: Vec<u32>,
+    ty: Type,
+}
+
+impl Scheme {
+    fn monomorphic(ty: Type) -> Scheme {
+        Scheme {
+            vars: Vec::new(),
+            ty,
+        }
+    }
+}
+
+/// What a function being checked returns.
+struct Function {
+    declared: Option<Type>,
+    returned: Vec<Type>,
+}
+
+struct Checker<'a> {
+    resolution: &'a Resolution,
+    /// What each type variable has been bound to.
+    substitution: Vec<Option<Type>>,
+    /// The variables bound so far, in order, so a failed unification can
+    /// be undone.
+    trail: Vec<u32>,
+    bindings: HashMap<BindingId, Scheme>,
+    /// The types of the bindings in scope that aren't generalized: the
+    /// parameters of the functions being checked and the `let`s whose
+    /// values are.
+    environment: Vec<Type>,
+    functions: Vec<Function>,
+    /// Operands of `+` whose type was unknown: each must turn out to be
+    /// an int or a string.
+    additions: Vec<(Type, &'static str, Span)>,
+    types: HashMap<NodeId, Type>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Checker<'_> {
+    fn error(&mut self, span: Span, message: String) {
+        self.diagnostics.push(
+            Diagnostic::error(message)
+                .with_code("type-mismatch")
+                .with_span(span),
+        );
+    }
+
+    fn mismatch(&mut self, span: Span, expected: &Type, found: &Type) {
+        let (expected, found) = (self.resolve(expected), self.resolve(found));
+        self.error(span, format!("expected `{}`, found `{}`", expected, found));
+    }
+
+    fn fresh(&mut self) -> Type {
+        self.substitution.push(None);
+        Type::Var(self.substitution.len() as u32 - 1)
+    }
+
+    /// `ty` with its outermost variables replaced by what they're bound to.
+    fn shallow(&self, ty: &Type) -> Type {
+        let mut ty = ty.clone();
+        while let Type::Var(var) = ty {
+            match &self.substitution[var as usize] {
+                Some(bound) => ty = bound.clone(),
+                None => break,
+            }
+        }
+        ty
+    }
+
+    /// `ty` with every bound variable replaced by what it's bound to.
+    fn resolve(&self, ty: &Type) -> Type {
+        grow_stack(|| match self.shallow(ty) {
+            Type::Array(element) => Type::Array(Box::new(self.resolve(&element))),
+            Type::Hash(key, value) => {
+                Type::Hash(Box::new(self.resolve(&key)), Box::new(self.resolve(&value)))
+            }
+            Type::Function(parameters, result) => Type::Function(
+                parameters.iter().map(|p| self.resolve(p)).collect(),
+                Box::new(self.resolve(&result)),
+            ),
+            ty => ty,
+        })
+    }
+
+    /// Make `a` and `b` the same type, binding variables as needed. On
+    /// failure nothing is bound.
+    fn unify(&mut self, a: &Type, b: &Type) -> bool {
+        let mark = self.trail.len();
+        let unified = self.unify_inner(a, b);
+        if !unified {
+            for var in self.trail.drain(mark..) {
+                self.substitution[var as usize] = None;
+            }
+        }
+        unified
+    }
+
+    fn unify_inner(&mut self, a: &Type, b: &Type) -> bool {
+        grow_stack(|| match (self.shallow(a), self.shallow(b)) {
+            (Type::Any, _) | (_, Type::Any) => true,
+            (Type::Var(a), Type::Var(b)) if a == b => true,
+            (Type::Var(var), ty) | (ty, Type::Var(var)) => {
+                // an infinite type, like that of a function returning
+                // itself, can't be written down, so it is `any`
+                let ty = if self.resolve(&ty).vars().contains(&var) {
+                    Type::Any
+                } else {
+                    ty
+                };
+                self.substitution[var as usize] = Some(ty);
+                self.trail.push(var);
+                true
+            }
+            (Type::Array(a), Type::Array(b)) => self.unify_inner(&a, &b),
+            (Type::Hash(a_key, a_value), Type::Hash(b_key, b_value)) => {
+                self.unify_inner(&a_key, &b_key) && self.unify_inner(&a_value, &b_value)
+            }
+            (Type::Function(a_parameters, a_result), Type::Function(b_parameters, b_result)) => {
+                a_parameters.len() == b_parameters.len()
+                    && a_parameters
+                        .iter()
+                        .zip(&b_parameters)
+                        .all(|(a, b)| self.unify_inner(a, b))
+                    && self.unify_inner(&a_result, &b_result)
+            }
+            (a, b) => a == b,
+        })
+    }
+
+    /// A type both `a` and `b` have, or `any` where they differ, as for
+    /// the two branches of an `if`.
+    fn join(&mut self, a: &Type, b: &Type) -> Type {
+        if self.unify(a, b) {
+            a.clone()
+        } else {
+            Type::Any
+        }
+    }
+
+    fn generalize(&self, ty: &Type) -> Scheme {
+        let ty = self.resolve(ty);
+        let mut free = Vec::new();
+        for bound in &self.environment {
+            free.extend(self.resolve(bound).vars());
+        }
+        let vars = ty
+            .vars()
+            .into_iter()
+            .filter(|v| !free.contains(v))
+            .collect();
+        Scheme { vars, ty }
+    }
+
+    fn instantiate(&mut self, scheme: &Scheme) -> Type {
+        let fresh: HashMap<u32, Type> =
+            scheme.vars.iter().map(|&var| (var, self.fresh())).collect();
+        substitute(&scheme.ty, &fresh)
+    }
+
+    fn bind(&mut self, ident: &Identifier, scheme: Scheme) {
+        self.types.insert(ident.id, scheme.ty.clone());
+        if let Some(binding) = self.resolution.binding_of(ident.id) {
+            self.bindings.insert(binding, scheme);
+        }
+    }
+
+    fn annotation(&mut self, annotation: &TypeAnnotation) -> Type {
+        match &annotation.kind {
+            TypeAnnotationKind::Named { name } => match name.as_str() {
+                "int" => Type::Int,
+                "string" => Type::String,
+                "bool" => Type::Bool,
+                "null" => Type::Null,
+                "any" => Type::Any,
+                _ => {
+                    self.diagnostics.push(
+                        Diagnostic::error(format!("unknown type `{}`", name))
+                            .with_code("unknown-type")
+                            .with_span(annotation.span)
+                            .with_note("the types are int, string, bool, null and any"),
+                    );
+                    Type::Any
+                }
+            },
+            TypeAnnotationKind::Array { element } => {
+                Type::Array(Box::new(self.annotation(element)))
+            }
+            TypeAnnotationKind::Hash { key, value } => Type::Hash(
+                Box::new(self.annotation(key)),
+                Box::new(self.annotation(value)),
+            ),
+            TypeAnnotationKind::Function {
+                parameters,
+                return_type,
+            } => Type::Function(
+                parameters.iter().map(|p| self.annotation(p)).collect(),
+                Box::new(self.annotation(return_type)),
+            ),
+        }
+    }
+
+    /// The type of a list of statements' value: that of the last one,
+    /// `null` if that isn't an expression, or a fresh variable if the
+    /// list returns or throws, as its value is then never used.
+    fn statements(&mut self, statements: &[Statement]) -> Type {
+        let mut ty = Type::Null;
+        let mut exits = false;
+        for stmt in statements {
+            ty = match stmt {
+                Statement::Let(stmt) => {
+                    self.let_statement(stmt);
+                    Type::Null
+                }
+                Statement::Return(stmt) => {
+                    exits = true;
+                    let span = NodeRef::Expression(&stmt.return_value).extent();
+                    let ty = self.expression(&stmt.return_value);
+                    self.returned(ty, span);
+                    Type::Null
+                }
+                Statement::Throw(stmt) => {
+                    exits = true;
+                    self.expression(&stmt.value);
+                    Type::Null
+                }
+                Statement::Expression(stmt) => self.expression(&stmt.expression),
+                Statement::Block(block) => self.block(block),
+            };
+        }
+        if exits {
+            self.fresh()
+        } else {
+            ty
+        }
+    }
+
+    fn block(&mut self, block: &BlockStatement) -> Type {
+        self.statements(&block.statements)
+    }
+
+    fn returned(&mut self, ty: Type, span: Span) {
+        let declared = match self.functions.last_mut() {
+            Some(function) => match &function.declared {
+                Some(declared) => declared.clone(),
+                None => return function.returned.push(ty),
+            },
+            // a `return` outside a function ends the program
+            None => return,
+        };
+        if !self.unify(&declared, &ty) {
+            self.mismatch(span, &declared, &ty);
+        }
+    }
+
+    fn let_statement(&mut self, stmt: &LetStatement) {
+        let declared = stmt
+            .name
+            .annotation
+            .as_ref()
+            .map(|annotation| self.annotation(annotation));
+        // bound before the value is checked, so a function can call itself
+        let recursive = match &declared {
+            Some(declared) => declared.clone(),
+            None => self.fresh(),
+        };
+        self.bind(&stmt.name, Scheme::monomorphic(recursive.clone()));
+        self.environment.push(recursive.clone());
+        let value = match &declared {
+            Some(declared) => {
+                self.check(&stmt.value, declared);
+                declared.clone()
+            }
+            None => self.expression(&stmt.value),
+        };
+        self.environment.pop();
+
+        let ty = match declared {
+            Some(declared) => declared,
+            None => {
+                // the value's own recursive calls disagree with what it is
+                if !self.unify(&recursive, &value) {
+                    let span = NodeRef::Expression(&stmt.value).extent();
+                    self.mismatch(span, &recursive, &value);
+                }
+                value
+            }
+        };
+        let scheme = self.generalize(&ty);
+        self.bind(&stmt.name, scheme);
+    }
+
+    fn function(&mut self, function: &FunctionLiteral, span: Span) -> Type {
+        let mut parameters = Vec::new();
+        for parameter in &function.parameters {
+            let ty = match &parameter.annotation {
+                Some(annotation) => self.annotation(annotation),
+                None => self.fresh(),
+            };
+            self.bind(parameter, Scheme::monomorphic(ty.clone()));
+            self.environment.push(ty.clone());
+            parameters.push(ty);
+        }
+        let declared = function
+            .return_type
+            .as_ref()
+            .map(|annotation| self.annotation(annotation));
+
+        self.functions.push(Function {
+            declared: declared.clone(),
+            returned: Vec::new(),
+        });
+        let body = self.block(&function.body);
+        let returned = self.functions.pop().expect("pushed above").returned;
+        self.environment
+            .truncate(self.environment.len() - parameters.len());
+
+        let result = match declared {
+            Some(declared) => {
+                if !self.unify(&declared, &body) {
+                    let span = match function.body.statements.last() {
+                        Some(last) => NodeRef::Statement(last).extent(),
+                        None => span,
+                    };
+                    self.mismatch(span, &declared, &body);
+                }
+                declared
+            }
+            None => returned
+                .iter()
+                .fold(body, |result, ty| self.join(&result, ty)),
+        };
+        Type::Function(parameters, Box::new(result))
+    }
+
+    /// Check `exp` against the type it has to have. Against an array or
+    /// hash type with no variables left, each element, key and value of
+    /// a literal is checked on its own, where inferring the literal would
+    /// join them and let `[1, "a"]` pass as `[any]`.
+    fn check(&mut self, exp: &Expression, expected: &Type) {
+        let expected = self.resolve(expected);
+        let literal = expected.vars().is_empty();
+        match (exp, &expected) {
+            (Expression::Array(array), Type::Array(element)) if literal => {
+                for exp in &array.elements {
+                    self.check(exp, element);
+                }
+                self.types.insert(exp.id(), expected);
+            }
+            (Expression::Hash(hash), Type::Hash(key, value)) if literal => {
+                for (key_exp, value_exp) in &hash.pairs {
+                    self.check(key_exp, key);
+                    self.check(value_exp, value);
+                }
+                self.types.insert(exp.id(), expected);
+            }
+            _ => {
+                let ty = self.expression(exp);
+                if !self.unify(&expected, &ty) {
+                    let span = NodeRef::Expression(exp).extent();
+                    self.mismatch(span, &expected, &ty);
+                }
+            }
+        }
+    }
+
+    fn expression(&mut self, exp: &Expression) -> Type {
+        let ty = grow_stack(|| self.expression_inner(exp));
+        self.types.insert(exp.id(), ty.clone());
+        ty
+    }
+
+    fn expression_inner(&mut self, exp: &Expression) -> Type {
+        let extent = |exp: &Expression| NodeRef::Expression(exp).extent();
+        match exp {
+            Expression::Identifier(ident) => {
+                let binding = self.resolution.binding_of(ident.id);
+                match binding
+                    .and_then(|binding| self.bindings.get(&binding))
+                    .cloned()
+                {
+                    Some(scheme) => self.instantiate(&scheme),
+                    None if binding.is_none() => self.builtin(&ident.value),
+                    // its `let` hasn't been checked yet
+                    None => Type::Any,
+                }
+            }
+            Expression::Integer(_) => Type::Int,
+            #[cfg(feature = "bigint")]
+            Expression::BigInteger(_) => Type::Int,
+            Expression::String(_) => Type::String,
+            Expression::Boolean(_) => Type::Bool,
+            Expression::Prefix(prefix) => {
+                let right = self.expression(&prefix.right);
+                if prefix.operator == "!" {
+                    return Type::Bool;
+                }
+                if !self.unify(&Type::Int, &right) {
+                    let right = self.resolve(&right);
+                    self.error(
+                        extent(exp),
+                        format!("`{}` can't be applied to `{}`", prefix.operator, right),
+                    );
+                }
+                Type::Int
+            }
+            Expression::Infix(infix) => {
+                let left = self.expression(&infix.left);
+                let right = self.expression(&infix.right);
+                match self.infix(&infix.operator, &left, &right, exp) {
+                    Some(ty) => ty,
+                    None => {
+                        let (left, right) = (self.resolve(&left), self.resolve(&right));
+                        self.error(
+                            extent(exp),
+                            format!(
+                                "`{}` can't be applied to `{}` and `{}`",
+                                infix.operator, left, right
+                            ),
+                        );
+                        Type::Any
+                    }
+                }
+            }
+            Expression::If(exp) => {
+                self.expression(&exp.condition);
+                let consequence = self.block(&exp.consequence);
+                let alternative = match &exp.alternative {
+                    Some(alternative) => self.block(alternative),
+                    None => Type::Null,
+                };
+                self.join(&consequence, &alternative)
+            }
+            Expression::Try(exp) => {
+                let body = self.block(&exp.body);
+                let caught = Type::Hash(Box::new(Type::String), Box::new(Type::Any));
+                self.bind(&exp.parameter, Scheme::monomorphic(caught));
+                let handler = self.block(&exp.handler);
+                self.join(&body, &handler)
+            }
+            Expression::Function(function) => self.function(function, extent(exp)),
+            Expression::Call(call) => {
+                let callee = self.expression(&call.function);
+                let parameters = match self.shallow(&callee) {
+                    Type::Function(parameters, _) => parameters,
+                    _ => Vec::new(),
+                };
+                let mut arguments = Vec::new();
+                for (i, argument) in call.arguments.iter().enumerate() {
+                    arguments.push(match parameters.get(i) {
+                        Some(parameter) => {
+                            self.check(argument, parameter);
+                            parameter.clone()
+                        }
+                        None => self.expression(argument),
+                    });
+                }
+                match self.shallow(&callee) {
+                    Type::Function(parameters, result) => {
+                        if parameters.len() != arguments.len() {
+                            self.error(
+                                extent(exp),
+                                format!(
+                                    "expected {} argument{}, found {}",
+                                    parameters.len(),
+                                    if parameters.len() == 1 { "" } else { "s" },
+                                    arguments.len()
+                                ),
+                            );
+                        }
+                        for ((parameter, argument), exp) in
+                            parameters.iter().zip(&arguments).zip(&call.arguments)
+                        {
+                            if !self.unify(parameter, argument) {
+                                self.mismatch(extent(exp), parameter, argument);
+                            }
+                        }
+                        *result
+                    }
+                    Type::Any => Type::Any,
+                    callee @ Type::Var(_) => {
+                        let result = self.fresh();
+                        let function = Type::Function(arguments, Box::new(result.clone()));
+                        self.unify(&callee, &function);
+                        result
+                    }
+                    callee => {
+                        let callee = self.resolve(&callee);
+                        self.error(
+                            extent(&call.function),
+                            format!("`{}` is not a function", callee),
+                        );
+                        Type::Any
+                    }
+                }
+            }
+            Expression::Array(array) => {
+                let mut element = self.fresh();
+                for exp in &array.elements {
+                    let ty = self.expression(exp);
+                    element = self.join(&element, &ty);
+                }
+                Type::Array(Box::new(element))
+            }
+            Expression::Index(index) => {
+                let left = self.expression(&index.left);
+                let key = self.expression(&index.index);
+                let (expected, result) = match self.shallow(&left) {
+                    Type::Array(element) => (Type::Int, *element),
+                    Type::Hash(key, value) => (*key, *value),
+                    Type::Any | Type::Var(_) => return Type::Any,
+                    left => {
+                        let left = self.resolve(&left);
+                        self.error(extent(&index.left), format!("`{}` can't be indexed", left));
+                        return Type::Any;
+                    }
+                };
+                if !self.unify(&expected, &key) {
+                    self.mismatch(extent(&index.index), &expected, &key);
+                }
+                result
+            }
+            Expression::Hash(hash) => {
+                let (mut key, mut value) = (self.fresh(), self.fresh());
+                for (key_exp, value_exp) in &hash.pairs {
+                    let key_ty = self.expression(key_exp);
+                    let resolved = self.resolve(&key_ty);
+                    if !resolved.is_hashable() {
+                        self.error(
+                            extent(key_exp),
+                            format!("`{}` can't be used as a hash key", resolved),
+                        );
+                    }
+                    key = self.join(&key, &key_ty);
+                    let value_ty = self.expression(value_exp);
+                    value = self.join(&value, &value_ty);
+                }
+                Type::Hash(Box::new(key), Box::new(value))
+            }
+        }
+    }
+
+    /// The type of `left operator right`, or `None` if the operator
+    /// can't be applied to them.
+    /// `exp` is only for the span of a deferred addition: working out the
+    /// extent of every operand up front would cost quadratic time on a
+    /// long chain like `1 + 2 + ... + n`.
+    fn infix(
+        &mut self,
+        operator: &str,
+        left: &Type,
+        right: &Type,
+        exp: &Expression,
+    ) -> Option<Type> {
+        match operator {
+            "+" => {
+                let (left, right) = (self.shallow(left), self.shallow(right));
+                match (&left, &right) {
+                    (Type::Any, other) | (other, Type::Any) => match other {
+                        Type::Int | Type::String => Some(other.clone()),
+                        Type::Var(_) | Type::Any => Some(Type::Any),
+                        _ => None,
+                    },
+                    (Type::Int | Type::String, other) | (other, Type::Int | Type::String) => {
+                        let known = if matches!(left, Type::Int | Type::String) {
+                            &left
+                        } else {
+                            &right
+                        };
+                        let known = known.clone();
+                        self.unify(&known, other).then_some(known)
+                    }
+                    (Type::Var(_), Type::Var(_)) => {
+                        self.unify(&left, &right);
+                        let span = NodeRef::Expression(exp).extent();
+                        self.additions.push((left.clone(), "+", span));
+                        Some(left)
+                    }
+                    _ => None,
+                }
+            }
+            "-" | "*" | "/" => {
+                (self.unify(&Type::Int, left) && self.unify(&Type::Int, right)).then_some(Type::Int)
+            }
+            "<" | ">" => (self.unify(&Type::Int, left) && self.unify(&Type::Int, right))
+                .then_some(Type::Bool),
+            _ => self.unify(left, right).then_some(Type::Bool),
+        }
+    }
+
+    /// The type of a builtin, each use getting its own variables.
+    fn builtin(&mut self, name: &str) -> Type {
+        let element = || Type::Var(0);
+        let array = || Type::Array(Box::new(element()));
+        let ty = match name {
+            "len" => Type::Function(vec![Type::Any], Box::new(Type::Int)),
+            "first" | "last" => Type::Function(vec![array()], Box::new(element())),
+            "rest" => Type::Function(vec![array()], Box::new(array())),
+            "push" => Type::Function(vec![array(), element()], Box::new(array())),
+            _ => return Type::Any,
+        };
+        let vars = ty.vars();
+        self.instantiate(&Scheme { vars, ty })
+    }
+}
+
+/// `ty` with the variables in `vars` replaced.
+fn substitute(ty: &Type, vars: &HashMap<u32, Type>) -> Type {
+    match ty {
+        Type::Var(var) => vars.get(var).cloned().unwrap_or_else(|| ty.clone()),
+        Type::Array(element) => Type::Array(Box::new(substitute(element, vars))),
+        Type::Hash(key, value) => Type::Hash(
+            Box::new(substitute(key, vars)),
+            Box::new(substitute(value, vars)),
+        ),
+        Type::Function(parameters, result) => Type::Function(
+            parameters.iter().map(|p| substitute(p, vars)).collect(),
+            Box::new(substitute(result, vars)),
+        ),
+        ty => ty.clone(),
+    }
+}
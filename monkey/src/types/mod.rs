@@ -0,0 +1,5 @@
+pub mod infer;
+pub mod types;
+
+pub use infer::*;
+pub use types::*;
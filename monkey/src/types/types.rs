@@ -0,0 +1,110 @@
+use crate::evaluator::limits::grow_stack;
+use std::collections::HashMap;
+use std::fmt;
+
+/// The static type of a Monkey value, written the way annotations are.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    Int,
+    String,
+    Bool,
+    Null,
+    /// Anything at all. Code the checker can't type, such as calls to
+    /// functions it knows nothing about, gets `any`, and `any` agrees
+    /// with every other type, so it is never reported.
+    Any,
+    /// A type the checker hasn't worked out yet, or, in a generalized
+    /// function type, one that can be anything as long as each use is
+    /// consistent.
+    Var(u32),
+    Array(Box<Type>),
+    Hash(Box<Type>, Box<Type>),
+    Function(Vec<Type>, Box<Type>),
+}
+
+impl Type {
+    /// Every type variable in the type, in the order they appear.
+    pub fn vars(&self) -> Vec<u32> {
+        let mut vars = Vec::new();
+        self.collect_vars(&mut vars);
+        vars
+    }
+
+    fn collect_vars(&self, vars: &mut Vec<u32>) {
+        grow_stack(|| match self {
+            Type::Var(var) if !vars.contains(var) => vars.push(*var),
+            Type::Array(element) => element.collect_vars(vars),
+            Type::Hash(key, value) => {
+                key.collect_vars(vars);
+                value.collect_vars(vars);
+            }
+            Type::Function(parameters, result) => {
+                for parameter in parameters {
+                    parameter.collect_vars(vars);
+                }
+                result.collect_vars(vars);
+            }
+            _ => {}
+        })
+    }
+
+    /// Whether a value of this type can be a hash key.
+    pub fn is_hashable(&self) -> bool {
+        !matches!(self, Type::Array(_) | Type::Hash(..) | Type::Function(..))
+    }
+
+    fn write(&self, f: &mut fmt::Formatter, names: &HashMap<u32, String>) -> fmt::Result {
+        grow_stack(|| match self {
+            Type::Int => write!(f, "int"),
+            Type::String => write!(f, "string"),
+            Type::Bool => write!(f, "bool"),
+            Type::Null => write!(f, "null"),
+            Type::Any => write!(f, "any"),
+            Type::Var(var) => write!(f, "{}", names[var]),
+            Type::Array(element) => {
+                write!(f, "[")?;
+                element.write(f, names)?;
+                write!(f, "]")
+            }
+            Type::Hash(key, value) => {
+                write!(f, "{{")?;
+                key.write(f, names)?;
+                write!(f, ": ")?;
+                value.write(f, names)?;
+                write!(f, "}}")
+            }
+            Type::Function(parameters, result) => {
+                write!(f, "fn(")?;
+                for (i, parameter) in parameters.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    parameter.write(f, names)?;
+                }
+                write!(f, ") -> ")?;
+                result.write(f, names)
+            }
+        })
+    }
+}
+
+/// Type variables are shown as `'a`, `'b` and so on, in the order they
+/// appear, whatever their numbers.
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = self
+            .vars()
+            .into_iter()
+            .enumerate()
+            .map(|(i, var)| {
+                let letter = (b'a' + (i % 26) as u8) as char;
+                let name = match i / 26 {
+                    0 => format!("'{}", letter),
+                    n => format!("'{}{}", letter, n),
+                };
+                (var, name)
+            })
+            .collect();
+        self.write(f, &names)
+    }
+}
@@ -0,0 +1,203 @@
+use crate::{ErrorKind, HashKey, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Conversion from a Monkey value into a Rust type, used to unpack the
+/// arguments of host functions.
+pub trait FromValue: Sized {
+    /// The Monkey type accepted, as shown in argument errors.
+    fn type_name() -> String;
+
+    fn from_value(value: Value) -> Option<Self>;
+}
+
+/// Conversion from a Rust type into a Monkey value, used for the return
+/// values of host functions and for globals set by the host.
+pub trait IntoValue {
+    fn into_value(self) -> Value;
+}
+
+impl FromValue for Value {
+    fn type_name() -> String {
+        "any value".to_string()
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        Some(value)
+    }
+}
+
+impl FromValue for i64 {
+    fn type_name() -> String {
+        "INTEGER".to_string()
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Integer(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "bigint")]
+impl FromValue for num_bigint::BigInt {
+    fn type_name() -> String {
+        "INTEGER".to_string()
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Integer(value) => Some(value.into()),
+            Value::BigInt(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for bool {
+    fn type_name() -> String {
+        "BOOLEAN".to_string()
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Boolean(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl FromValue for String {
+    fn type_name() -> String {
+        "STRING".to_string()
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    fn type_name() -> String {
+        format!("ARRAY of {}", T::type_name())
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Array(elements) => elements.into_iter().map(T::from_value).collect(),
+            _ => None,
+        }
+    }
+}
+
+/// `null` converts to `None`.
+impl<T: FromValue> FromValue for Option<T> {
+    fn type_name() -> String {
+        T::type_name()
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Null => Some(None),
+            value => T::from_value(value).map(Some),
+        }
+    }
+}
+
+impl IntoValue for Value {
+    fn into_value(self) -> Value {
+        self
+    }
+}
+
+impl IntoValue for i64 {
+    fn into_value(self) -> Value {
+        Value::Integer(self)
+    }
+}
+
+#[cfg(feature = "bigint")]
+impl IntoValue for num_bigint::BigInt {
+    fn into_value(self) -> Value {
+        crate::evaluator::arithmetic::integer(self)
+    }
+}
+
+impl IntoValue for bool {
+    fn into_value(self) -> Value {
+        Value::Boolean(self)
+    }
+}
+
+impl IntoValue for String {
+    fn into_value(self) -> Value {
+        Value::String(self)
+    }
+}
+
+impl IntoValue for &str {
+    fn into_value(self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+impl IntoValue for () {
+    fn into_value(self) -> Value {
+        Value::Null
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn into_value(self) -> Value {
+        Value::Array(self.into_iter().map(IntoValue::into_value).collect())
+    }
+}
+
+impl<T: IntoValue> IntoValue for Option<T> {
+    fn into_value(self) -> Value {
+        self.map_or(Value::Null, IntoValue::into_value)
+    }
+}
+
+/// An `Err` becomes a Monkey error value carrying its message.
+impl<T: IntoValue, E: fmt::Display> IntoValue for Result<T, E> {
+    fn into_value(self) -> Value {
+        match self {
+            Ok(value) => value.into_value(),
+            Err(err) => Value::error(ErrorKind::Custom, err.to_string()),
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for BTreeMap<String, T> {
+    fn type_name() -> String {
+        format!("HASH of {}", T::type_name())
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Hash(pairs) => pairs
+                .into_iter()
+                .map(|(key, value)| match key {
+                    HashKey::String(key) => Some((key, T::from_value(value)?)),
+                    _ => None,
+                })
+                .collect(),
+            _ => None,
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for BTreeMap<String, T> {
+    fn into_value(self) -> Value {
+        Value::Hash(
+            self.into_iter()
+                .map(|(key, value)| (HashKey::String(key), value.into_value()))
+                .collect(),
+        )
+    }
+}
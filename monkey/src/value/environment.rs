@@ -0,0 +1,67 @@
+use crate::Value;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// The bindings visible at some point of evaluation, chained to the
+/// scope it was created in.
+#[derive(Default)]
+pub struct Environment {
+    store: HashMap<String, Value>,
+    outer: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub fn new() -> Environment {
+        Environment::default()
+    }
+
+    pub fn new_enclosed(outer: Rc<RefCell<Environment>>) -> Environment {
+        Environment {
+            store: HashMap::new(),
+            outer: Some(outer),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<Value> {
+        match self.store.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self.outer.as_ref().and_then(|o| o.borrow().get(name)),
+        }
+    }
+
+    /// Look `name` up only in the scope `depth` levels out, as a `Slot`
+    /// from the resolver says to.
+    pub fn get_at(&self, depth: u32, name: &str) -> Option<Value> {
+        match (depth, &self.outer) {
+            (0, _) => self.store.get(name).cloned(),
+            (_, Some(outer)) => outer.borrow().get_at(depth - 1, name),
+            (_, None) => None,
+        }
+    }
+
+    pub fn set(&mut self, name: String, value: Value) -> Value {
+        self.store.insert(name, value.clone());
+        value
+    }
+
+    /// Names of every binding visible from this scope.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.store.keys().cloned().collect();
+        if let Some(outer) = &self.outer {
+            names.extend(outer.borrow().names());
+        }
+        names
+    }
+
+    /// Bindings defined directly in this scope, sorted by name.
+    pub fn bindings(&self) -> Vec<(String, Value)> {
+        let mut bindings: Vec<(String, Value)> = self
+            .store
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        bindings.sort_by(|a, b| a.0.cmp(&b.0));
+        bindings
+    }
+}
@@ -0,0 +1,198 @@
+use crate::{line_column, Diagnostic, HashKey, Span, Value};
+use std::fmt;
+use std::rc::Rc;
+
+/// What went wrong, for hosts and scripts that handle some errors
+/// differently from others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    TypeMismatch,
+    UnknownOperator,
+    UndefinedIdentifier,
+    DivisionByZero,
+    /// An integer result that doesn't fit in 64 bits. Never raised with
+    /// the `bigint` feature, which switches to arbitrary precision instead.
+    IntegerOverflow,
+    IndexOutOfRange,
+    NotCallable,
+    WrongArgumentCount,
+    InvalidArgument,
+    PermissionDenied,
+    Io,
+    LimitExceeded,
+    Cancelled,
+    Timeout,
+    /// Raised by host code rather than the interpreter.
+    Custom,
+}
+
+impl ErrorKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorKind::TypeMismatch => "TYPE_MISMATCH",
+            ErrorKind::UnknownOperator => "UNKNOWN_OPERATOR",
+            ErrorKind::UndefinedIdentifier => "UNDEFINED_IDENTIFIER",
+            ErrorKind::DivisionByZero => "DIVISION_BY_ZERO",
+            ErrorKind::IntegerOverflow => "INTEGER_OVERFLOW",
+            ErrorKind::IndexOutOfRange => "INDEX_OUT_OF_RANGE",
+            ErrorKind::NotCallable => "NOT_CALLABLE",
+            ErrorKind::WrongArgumentCount => "WRONG_ARGUMENT_COUNT",
+            ErrorKind::InvalidArgument => "INVALID_ARGUMENT",
+            ErrorKind::PermissionDenied => "PERMISSION_DENIED",
+            ErrorKind::Io => "IO",
+            ErrorKind::LimitExceeded => "LIMIT_EXCEEDED",
+            ErrorKind::Cancelled => "CANCELLED",
+            ErrorKind::Timeout => "TIMEOUT",
+            ErrorKind::Custom => "CUSTOM",
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A span together with the source it points into. Functions can be
+/// called from code parsed separately from their own, so each location
+/// keeps its source.
+#[derive(Debug, Clone)]
+pub struct Location {
+    pub span: Span,
+    pub source: Rc<str>,
+}
+
+impl Location {
+    pub fn line_column(&self) -> (usize, usize) {
+        line_column(&self.source, self.span.start)
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (line, column) = self.line_column();
+        write!(f, "{}:{}", line, column)
+    }
+}
+
+/// One call the error unwound through: the function, and where it was
+/// called from.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub function: String,
+    pub call_site: Option<Location>,
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.call_site {
+            Some(call_site) => write!(f, "in `{}` called at {}", self.function, call_site),
+            None => write!(f, "in `{}`", self.function),
+        }
+    }
+}
+
+/// Deep recursion can leave hundreds of frames; past this many only
+/// their count is shown.
+const MAX_RENDERED_FRAMES: usize = 16;
+
+/// The error a Monkey program evaluates to. It travels as a value,
+/// stopping blocks and calls on its way out, and picks up where it
+/// happened and the calls it passed through.
+#[derive(Debug, Clone)]
+pub struct RuntimeError {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub location: Option<Location>,
+    /// Innermost call first.
+    pub trace: Vec<Frame>,
+    /// What a `throw` statement threw; `None` for errors raised by the
+    /// interpreter itself.
+    pub thrown: Option<Value>,
+}
+
+impl RuntimeError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> RuntimeError {
+        RuntimeError {
+            kind,
+            message: message.into(),
+            location: None,
+            trace: Vec::new(),
+            thrown: None,
+        }
+    }
+
+    /// The error for `throw value`. Strings become the message as they
+    /// are; anything else is shown the way the repl would show it.
+    pub fn thrown(value: Value) -> RuntimeError {
+        let message = match &value {
+            Value::String(message) => message.clone(),
+            other => other.to_string(),
+        };
+        RuntimeError {
+            thrown: Some(value),
+            ..RuntimeError::new(ErrorKind::Custom, message)
+        }
+    }
+
+    /// The hash a `catch` clause binds: the `message`, the `kind` name,
+    /// the `trace` as one string per call, and the `value` thrown, if
+    /// any.
+    pub fn to_caught(&self) -> Value {
+        let trace = self
+            .trace
+            .iter()
+            .map(|frame| Value::String(frame.to_string()))
+            .collect();
+        let pairs = [
+            ("message", Value::String(self.message.clone())),
+            ("kind", Value::String(self.kind.name().to_string())),
+            ("trace", Value::Array(trace)),
+            ("value", self.thrown.clone().unwrap_or(Value::Null)),
+        ];
+        Value::Hash(
+            pairs
+                .into_iter()
+                .map(|(key, value)| (HashKey::String(key.to_string()), value))
+                .collect(),
+        )
+    }
+
+    pub fn diagnostic(&self) -> Diagnostic {
+        let mut diagnostic = Diagnostic::error(&self.message);
+        if let Some(location) = &self.location {
+            diagnostic = diagnostic.with_span(location.span);
+        }
+        for frame in self.trace.iter().take(MAX_RENDERED_FRAMES) {
+            diagnostic = diagnostic.with_note(frame.to_string());
+        }
+        if self.trace.len() > MAX_RENDERED_FRAMES {
+            let hidden = self.trace.len() - MAX_RENDERED_FRAMES;
+            diagnostic = diagnostic.with_note(format!("... and {} more calls", hidden));
+        }
+        diagnostic
+    }
+
+    /// The diagnostic for this error, with the line it happened on.
+    pub fn render(&self) -> String {
+        let source = self.location.as_ref().map_or("", |l| &l.source);
+        self.diagnostic().render(source)
+    }
+}
+
+/// Errors are equal when they are the same kind of error with the same
+/// message, wherever they happened.
+impl PartialEq for RuntimeError {
+    fn eq(&self, other: &RuntimeError) -> bool {
+        self.kind == other.kind && self.message == other.message
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for RuntimeError {}
@@ -0,0 +1,9 @@
+pub mod convert;
+pub mod environment;
+pub mod error;
+pub mod value;
+
+pub use convert::*;
+pub use environment::*;
+pub use error::*;
+pub use value::*;
@@ -0,0 +1,165 @@
+use crate::{BlockStatement, Builtin, Environment, ErrorKind, Identifier, RuntimeError};
+#[cfg(feature = "bigint")]
+use num_bigint::BigInt;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::rc::Rc;
+
+#[derive(Clone)]
+pub enum Value {
+    Integer(i64),
+    /// An integer outside the `i64` range. Arithmetic only produces one
+    /// when the result doesn't fit an `Integer`.
+    #[cfg(feature = "bigint")]
+    BigInt(BigInt),
+    Boolean(bool),
+    String(String),
+    Array(Vec<Value>),
+    Hash(BTreeMap<HashKey, Value>),
+    Null,
+    ReturnValue(Box<Value>),
+    Error(Box<RuntimeError>),
+    Function(Rc<Function>),
+    Builtin(Rc<Builtin>),
+}
+
+/// The values usable as hash keys.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HashKey {
+    Integer(i64),
+    #[cfg(feature = "bigint")]
+    BigInt(BigInt),
+    Boolean(bool),
+    String(String),
+}
+
+impl From<HashKey> for Value {
+    fn from(key: HashKey) -> Value {
+        match key {
+            HashKey::Integer(value) => Value::Integer(value),
+            #[cfg(feature = "bigint")]
+            HashKey::BigInt(value) => Value::BigInt(value),
+            HashKey::Boolean(value) => Value::Boolean(value),
+            HashKey::String(value) => Value::String(value),
+        }
+    }
+}
+
+impl fmt::Display for HashKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HashKey::Integer(value) => write!(f, "{}", value),
+            #[cfg(feature = "bigint")]
+            HashKey::BigInt(value) => write!(f, "{}", value),
+            HashKey::Boolean(value) => write!(f, "{}", value),
+            HashKey::String(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+pub struct Function {
+    /// The name it was first bound to with `let`, for stack traces.
+    pub name: Option<String>,
+    pub parameters: Vec<Identifier>,
+    pub body: Rc<BlockStatement>,
+    pub env: Rc<RefCell<Environment>>,
+    /// The source the body was parsed from.
+    pub source: Rc<str>,
+}
+
+impl Value {
+    pub fn error(kind: ErrorKind, message: impl Into<String>) -> Value {
+        Value::Error(Box::new(RuntimeError::new(kind, message)))
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Integer(_) => "INTEGER",
+            #[cfg(feature = "bigint")]
+            Value::BigInt(_) => "INTEGER",
+            Value::Boolean(_) => "BOOLEAN",
+            Value::String(_) => "STRING",
+            Value::Array(_) => "ARRAY",
+            Value::Hash(_) => "HASH",
+            Value::Null => "NULL",
+            Value::ReturnValue(_) => "RETURN_VALUE",
+            Value::Error(_) => "ERROR",
+            Value::Function(_) => "FUNCTION",
+            Value::Builtin(_) => "BUILTIN",
+        }
+    }
+
+    pub fn hash_key(&self) -> Option<HashKey> {
+        match self {
+            Value::Integer(value) => Some(HashKey::Integer(*value)),
+            #[cfg(feature = "bigint")]
+            Value::BigInt(value) => Some(HashKey::BigInt(value.clone())),
+            Value::Boolean(value) => Some(HashKey::Boolean(*value)),
+            Value::String(value) => Some(HashKey::String(value.clone())),
+            _ => None,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        matches!(self, Value::Error(_))
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Integer(a), Value::Integer(b)) => a == b,
+            #[cfg(feature = "bigint")]
+            (Value::BigInt(a), Value::BigInt(b)) => a == b,
+            (Value::Boolean(a), Value::Boolean(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Array(a), Value::Array(b)) => a == b,
+            (Value::Hash(a), Value::Hash(b)) => a == b,
+            (Value::Null, Value::Null) => true,
+            (Value::ReturnValue(a), Value::ReturnValue(b)) => a == b,
+            (Value::Error(a), Value::Error(b)) => a == b,
+            (Value::Function(a), Value::Function(b)) => Rc::ptr_eq(a, b),
+            (Value::Builtin(a), Value::Builtin(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Integer(value) => write!(f, "{}", value),
+            #[cfg(feature = "bigint")]
+            Value::BigInt(value) => write!(f, "{}", value),
+            Value::Boolean(value) => write!(f, "{}", value),
+            Value::String(value) => write!(f, "{}", value),
+            Value::Array(elements) => {
+                let elements: Vec<String> = elements.iter().map(|e| e.to_string()).collect();
+                write!(f, "[{}]", elements.join(", "))
+            }
+            Value::Hash(pairs) => {
+                let pairs: Vec<String> = pairs
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key, value))
+                    .collect();
+                write!(f, "{{{}}}", pairs.join(", "))
+            }
+            Value::Null => write!(f, "null"),
+            Value::ReturnValue(value) => write!(f, "{}", value),
+            Value::Error(err) => write!(f, "ERROR: {}", err),
+            Value::Function(function) => {
+                let params: Vec<String> =
+                    function.parameters.iter().map(|p| p.to_string()).collect();
+                write!(f, "fn({}) {{\n{}\n}}", params.join(", "), function.body)
+            }
+            Value::Builtin(builtin) => write!(f, "builtin function {}", builtin.name),
+        }
+    }
+}
+
+impl fmt::Debug for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}({})", self.type_name(), self)
+    }
+}
@@ -1,44 +1,64 @@
 #[cfg(test)]
 mod tests {
-    use monkey::{Expression, Identifier, LetStatement, Node, Program, Token, TokenType};
+    use monkey::{
+        parser::parser::Parser, Expression, Identifier, LetStatement, Lexer, Node, NodeId, Program,
+        Statement, Token, TokenType,
+    };
 
     #[test]
     fn test_macros() {
         let i = &Identifier {
             token: Token::new(TokenType::LET, "let".to_string()),
+            id: NodeId::DUMMY,
+            slot: None,
+            annotation: None,
             value: "test_val".to_string(),
         };
-        i.expression_node();
         assert_eq!("let".to_string(), i.token_literal());
+        assert_eq!("test_val".to_string(), i.to_string());
     }
 
     #[test]
     fn test_let_string() {
-        let program = Program {
-            statements: vec![Box::new(LetStatement {
-                token: Token {
-                    r#type: TokenType::LET,
-                    literal: "let".to_string(),
-                },
-                name: Some(Identifier {
-                    token: Token {
-                        r#type: TokenType::IDENT,
-                        literal: "myVar".to_string(),
-                    },
+        let program = Program::new(
+            vec![Statement::Let(LetStatement {
+                token: Token::new(TokenType::LET, "let".to_string()),
+                id: NodeId::DUMMY,
+                name: Identifier {
+                    token: Token::new(TokenType::IDENT, "myVar".to_string()),
+                    id: NodeId::DUMMY,
+                    slot: None,
+                    annotation: None,
                     value: "myVar".to_string(),
-                }),
-                value: Some(Box::new(Identifier {
-                    token: Token {
-                        r#type: TokenType::IDENT,
-                        literal: "anotherVar".to_string(),
-                    },
+                },
+                value: Expression::Identifier(Identifier {
+                    token: Token::new(TokenType::IDENT, "anotherVar".to_string()),
+                    id: NodeId::DUMMY,
+                    slot: None,
+                    annotation: None,
                     value: "anotherVar".to_string(),
-                })),
+                }),
             })],
+            "let myVar = anotherVar;".into(),
+        );
+        assert_eq!(program.to_string(), "let myVar = anotherVar;")
+    }
+
+    #[test]
+    fn test_clone_and_equality() {
+        let parse = |input: &str| {
+            let mut l = Lexer::new(input.to_string());
+            Parser::new(&mut l).parse_program().unwrap()
         };
+        let program = parse("let add = fn(a, b) { a + b }; add(1, [2, 3][0]);");
+        assert_eq!(program.clone(), program);
         assert_eq!(
-            format!("{}", program.to_string()),
-            "let myVar = anotherVar;"
-        )
+            parse("let add = fn(a, b) { a + b }; add(1, [2, 3][0]);"),
+            program
+        );
+        assert_ne!(
+            parse("let add = fn(a, b) { a - b }; add(1, [2, 3][0]);"),
+            program
+        );
     }
 }
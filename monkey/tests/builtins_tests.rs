@@ -0,0 +1,62 @@
+#[cfg(test)]
+mod tests {
+    use monkey::{Arity, Builtins, ErrorKind, Value};
+
+    #[test]
+    fn test_standard_builtins() {
+        assert_eq!(
+            Builtins::new().names(),
+            [
+                "clock",
+                "env",
+                "first",
+                "last",
+                "len",
+                "push",
+                "puts",
+                "random",
+                "read_file",
+                "rest",
+                "write_file"
+            ]
+        );
+        assert!(Builtins::empty().names().is_empty());
+    }
+
+    #[test]
+    fn test_register_builtin() {
+        let mut builtins = Builtins::empty();
+        builtins.register("double", Arity::Exact(1), |args| match &args[0] {
+            Value::Integer(value) => Value::Integer(value * 2),
+            other => monkey::argument_error("double", "INTEGER", other),
+        });
+
+        let Some(Value::Builtin(double)) = builtins.get("double") else {
+            panic!("double not registered");
+        };
+        assert_eq!(double.call(vec![Value::Integer(21)]), Value::Integer(42));
+        assert_eq!(
+            double.call(vec![Value::Boolean(true)]),
+            Value::error(
+                ErrorKind::InvalidArgument,
+                "argument to `double` must be INTEGER, got BOOLEAN"
+            )
+        );
+        assert_eq!(
+            double.call(vec![]),
+            Value::error(
+                ErrorKind::WrongArgumentCount,
+                "wrong number of arguments to `double`: want=1, got=0"
+            )
+        );
+    }
+
+    #[test]
+    fn test_arity() {
+        assert!(Arity::Exact(2).accepts(2));
+        assert!(!Arity::Exact(2).accepts(3));
+        assert!(Arity::AtLeast(1).accepts(3));
+        assert!(!Arity::AtLeast(1).accepts(0));
+        assert_eq!(Arity::AtLeast(1).to_string(), "at least 1");
+    }
+}
@@ -0,0 +1,148 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::runtime_error;
+    use monkey::{Access, Capabilities, Capability, Interpreter, Value};
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// A fresh directory with one file in it.
+    fn sandbox_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("data.txt"), "hello").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_nothing_allowed() {
+        let mut interpreter = Interpreter::with_capabilities(Capabilities::none());
+
+        assert_eq!(
+            runtime_error(&mut interpreter, "puts(1)").message,
+            "permission denied: write to stdout"
+        );
+        assert_eq!(
+            runtime_error(&mut interpreter, "env(\"HOME\")").message,
+            "permission denied: read environment variable HOME"
+        );
+        assert_eq!(
+            runtime_error(&mut interpreter, "clock()").message,
+            "permission denied: read the clock"
+        );
+        assert_eq!(
+            runtime_error(&mut interpreter, "random(6)").message,
+            "permission denied: generate random numbers"
+        );
+        assert_eq!(
+            runtime_error(&mut interpreter, "read_file(\"/etc/hostname\")").message,
+            "permission denied: read /etc/hostname"
+        );
+        // argument errors still come from the builtin itself
+        assert_eq!(
+            runtime_error(&mut interpreter, "read_file(1)").message,
+            "argument to `read_file` must be STRING, got INTEGER"
+        );
+        // pure builtins need no capability
+        assert_eq!(
+            interpreter.eval_str("len(\"abc\")").unwrap(),
+            Value::Integer(3)
+        );
+    }
+
+    #[test]
+    fn test_allowed_paths() {
+        let dir = sandbox_dir("monkey_capabilities_paths");
+        let mut interpreter = Interpreter::with_capabilities(Capabilities {
+            read: Access::Only(vec![dir.clone()]),
+            write: Access::Only(vec![dir.clone()]),
+            ..Capabilities::none()
+        });
+        interpreter.set_global("dir", dir.display().to_string());
+
+        assert_eq!(
+            interpreter
+                .eval_str("read_file(dir + \"/data.txt\")")
+                .unwrap(),
+            Value::String("hello".to_string())
+        );
+        interpreter
+            .eval_str("write_file(dir + \"/out.txt\", \"written\")")
+            .unwrap();
+        assert_eq!(fs::read_to_string(dir.join("out.txt")).unwrap(), "written");
+
+        let escape = runtime_error(&mut interpreter, "read_file(dir + \"/../data.txt\")").message;
+        assert!(escape.starts_with("permission denied: read "), "{}", escape);
+        let outside = runtime_error(&mut interpreter, "write_file(\"out.txt\", \"x\")").message;
+        assert_eq!(outside, "permission denied: write out.txt");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dangling_symlink() {
+        let dir = sandbox_dir("monkey_capabilities_symlink");
+        let outside = std::env::temp_dir().join("monkey_capabilities_outside.txt");
+        let _ = fs::remove_file(&outside);
+        let link = dir.join("link.txt");
+        let _ = fs::remove_file(&link);
+        std::os::unix::fs::symlink(&outside, &link).unwrap();
+        let mut interpreter = Interpreter::with_capabilities(Capabilities {
+            write: Access::Only(vec![dir.clone()]),
+            ..Capabilities::none()
+        });
+        interpreter.set_global("link", link.display().to_string());
+
+        let error = runtime_error(&mut interpreter, "write_file(link, \"x\")").message;
+        assert!(error.starts_with("permission denied: write "), "{}", error);
+        assert!(!outside.exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_allowed_env_vars() {
+        let capabilities = Capabilities {
+            env: Access::Only(vec!["MONKEY_ALLOWED".to_string()]),
+            ..Capabilities::none()
+        };
+
+        assert!(capabilities.allows(&Capability::Env("MONKEY_ALLOWED".to_string())));
+        assert!(!capabilities.allows(&Capability::Env("PATH".to_string())));
+        assert!(!capabilities.allows(&Capability::Stdout));
+    }
+
+    #[test]
+    fn test_everything_allowed_by_default() {
+        let dir = sandbox_dir("monkey_capabilities_default");
+        let mut interpreter = Interpreter::new();
+        interpreter.set_global("path", dir.join("data.txt").display().to_string());
+
+        assert_eq!(
+            interpreter.eval_str("read_file(path)").unwrap(),
+            Value::String("hello".to_string())
+        );
+        assert_eq!(
+            interpreter.eval_str("clock() > 0").unwrap(),
+            Value::Boolean(true)
+        );
+        let Value::Integer(roll) = interpreter.eval_str("random(6)").unwrap() else {
+            panic!("random should return an integer");
+        };
+        assert!((0..6).contains(&roll));
+        assert_eq!(
+            interpreter
+                .eval_str("env(\"MONKEY_SURELY_UNSET\")")
+                .unwrap(),
+            Value::Null
+        );
+        assert_eq!(
+            runtime_error(&mut interpreter, "random(0)").message,
+            "argument to `random` must be positive"
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
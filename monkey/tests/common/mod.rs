@@ -0,0 +1,22 @@
+//! Helpers shared by the integration tests. Each test file is its own
+//! crate and uses only some of them.
+#![allow(dead_code)]
+
+use monkey::{Error, Interpreter, Lexer, Parser, Program, RuntimeError};
+
+/// The runtime error evaluating `input` raises, panicking if it doesn't.
+pub fn runtime_error(interpreter: &mut Interpreter, input: &str) -> RuntimeError {
+    match interpreter.eval_str(input) {
+        Err(Error::Runtime(err)) => err,
+        other => panic!("expected runtime error, got {:?}", other),
+    }
+}
+
+/// Parse `input` with `Parser`, panicking on any parse error.
+pub fn parse(input: &str) -> Program {
+    let mut l = Lexer::new(input.to_string());
+    let mut p = Parser::new(&mut l);
+    let program = p.parse_program().unwrap();
+    assert!(p.errors().is_empty(), "parser errors: {:?}", p.errors());
+    program
+}
@@ -0,0 +1,153 @@
+#[cfg(test)]
+mod tests {
+    use monkey::{FromValue, Interpreter, IntoValue, MonkeyValue, Value};
+
+    #[derive(MonkeyValue, Debug, PartialEq)]
+    struct Config {
+        name: String,
+        #[monkey(rename = "max-retries")]
+        max_retries: i64,
+        verbose: Option<bool>,
+        #[monkey(default)]
+        tags: Vec<String>,
+        #[monkey(default = "default_level")]
+        level: Level,
+    }
+
+    #[derive(MonkeyValue, Debug, PartialEq)]
+    enum Level {
+        Debug,
+        #[monkey(rename = "warn")]
+        Warning,
+        Custom {
+            threshold: i64,
+        },
+    }
+
+    fn default_level() -> Level {
+        Level::Debug
+    }
+
+    #[derive(MonkeyValue, Debug, PartialEq)]
+    struct Page<T, E>
+    where
+        E: Clone,
+    {
+        items: Vec<T>,
+        error: Option<E>,
+    }
+
+    #[test]
+    fn test_struct_from_script() {
+        let mut interpreter = Interpreter::new();
+        let value = interpreter
+            .eval_str(r#"{"name": "svc", "max-retries": 3, "level": "warn"}"#)
+            .unwrap();
+
+        assert_eq!(
+            Config::from_value(value),
+            Some(Config {
+                name: "svc".to_string(),
+                max_retries: 3,
+                verbose: None,
+                tags: vec![],
+                level: Level::Warning,
+            })
+        );
+    }
+
+    #[test]
+    fn test_struct_round_trip() {
+        let config = Config {
+            name: "svc".to_string(),
+            max_retries: 1,
+            verbose: Some(true),
+            tags: vec!["a".to_string()],
+            level: Level::Custom { threshold: 5 },
+        };
+
+        let mut interpreter = Interpreter::new();
+        interpreter.set_global("config", config);
+        let value = interpreter
+            .eval_str(
+                r#"[config["max-retries"], config["level"]["type"], config["level"]["threshold"]]"#,
+            )
+            .unwrap();
+        assert_eq!(
+            value,
+            Value::Array(vec![
+                Value::Integer(1),
+                Value::String("Custom".to_string()),
+                Value::Integer(5)
+            ])
+        );
+
+        let config = interpreter.get_global("config").unwrap();
+        let config = Config::from_value(config).unwrap();
+        assert_eq!(config.tags, ["a"]);
+        assert_eq!(config.verbose, Some(true));
+        assert_eq!(config.into_value().type_name(), "HASH");
+    }
+
+    #[test]
+    fn test_generic_struct() {
+        let mut interpreter = Interpreter::new();
+        let value = interpreter.eval_str(r#"{"items": [1, 2]}"#).unwrap();
+        let page = Page::<i64, String>::from_value(value).unwrap();
+        assert_eq!(
+            page,
+            Page {
+                items: vec![1, 2],
+                error: None,
+            }
+        );
+
+        interpreter.set_global(
+            "page",
+            Page {
+                items: vec!["a".to_string()],
+                error: Some("late".to_string()),
+            },
+        );
+        assert_eq!(
+            interpreter
+                .eval_str(r#"page["items"][0] + page["error"]"#)
+                .unwrap(),
+            Value::String("alate".to_string())
+        );
+    }
+
+    #[test]
+    fn test_struct_mismatch() {
+        let mut interpreter = Interpreter::new();
+        // missing required key
+        let value = interpreter.eval_str(r#"{"name": "svc"}"#).unwrap();
+        assert_eq!(Config::from_value(value), None);
+        // wrong field type
+        let value = interpreter
+            .eval_str(r#"{"name": 1, "max-retries": 3}"#)
+            .unwrap();
+        assert_eq!(Config::from_value(value), None);
+        // unknown variant
+        let value = interpreter.eval_str(r#""Trace""#).unwrap();
+        assert_eq!(Level::from_value(value), None);
+        assert_eq!(Level::type_name(), "STRING or HASH");
+    }
+
+    #[test]
+    fn test_derived_function_arguments() {
+        let mut interpreter = Interpreter::new();
+        interpreter.register_fn("retries", |config: Config| config.max_retries);
+
+        assert_eq!(
+            interpreter
+                .eval_str(r#"retries({"name": "x", "max-retries": 9})"#)
+                .unwrap(),
+            Value::Integer(9)
+        );
+        assert_eq!(
+            interpreter.eval_str("retries(1)").unwrap_err().to_string(),
+            "argument to `retries` must be HASH, got INTEGER"
+        );
+    }
+}
@@ -0,0 +1,114 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::runtime_error;
+    use monkey::{line_column, Diagnostic, Error, ErrorKind, Interpreter, Span, Value};
+
+    #[test]
+    fn test_line_column() {
+        let source = "let a = 1;\nlet é = a;\n";
+        assert_eq!(line_column(source, 0), (1, 1));
+        assert_eq!(line_column(source, 4), (1, 5));
+        assert_eq!(line_column(source, 11), (2, 1));
+        // columns count characters, not bytes
+        assert_eq!(line_column(source, 18), (2, 7));
+        assert_eq!(line_column(source, 100), (3, 1));
+    }
+
+    #[test]
+    fn test_render() {
+        let source = "let x = 1;\nx + true;";
+        let diagnostic = Diagnostic::error("type mismatch: INTEGER + BOOLEAN")
+            .with_span(Span::new(13, 14))
+            .with_note("in `f` called at 3:1");
+
+        assert_eq!(
+            diagnostic.render(source),
+            "error: type mismatch: INTEGER + BOOLEAN\n \
+             --> 2:3\n  \
+             |\n\
+             2 | x + true;\n  \
+             |   ^\n  \
+             = in `f` called at 3:1"
+        );
+        assert_eq!(
+            Diagnostic::warning("unused").render(source),
+            "warning: unused"
+        );
+        assert_eq!(
+            Diagnostic::warning("`x` is never used")
+                .with_code("unused-binding")
+                .with_span(Span::new(4, 5))
+                .render_in("main.mk", source),
+            "warning[unused-binding]: `x` is never used\n \
+             --> main.mk:1:5\n  \
+             |\n\
+             1 | let x = 1;\n  \
+             |     ^"
+        );
+    }
+
+    #[test]
+    fn test_error_location() {
+        let mut interpreter = Interpreter::new();
+
+        let err = runtime_error(&mut interpreter, "let a = [1, 2];\nlen(a) + a[5]");
+        assert_eq!(err.kind, ErrorKind::IndexOutOfRange);
+        assert_eq!(err.location.unwrap().line_column(), (2, 11));
+
+        let err = runtime_error(&mut interpreter, "len(1)");
+        assert_eq!(err.kind, ErrorKind::InvalidArgument);
+        assert_eq!(err.location.unwrap().span, Span::new(0, 4));
+        assert!(err.trace.is_empty());
+    }
+
+    #[test]
+    fn test_stack_trace() {
+        let mut interpreter = Interpreter::new();
+        interpreter
+            .eval_str("let inner = fn(x) {\n  x / 0\n};\nlet outer = fn(y) { inner(y) };")
+            .unwrap();
+
+        let err = runtime_error(&mut interpreter, "1 + outer(2)");
+        assert_eq!(err.kind, ErrorKind::DivisionByZero);
+        let frames: Vec<String> = err.trace.iter().map(|f| f.to_string()).collect();
+        assert_eq!(
+            frames,
+            ["in `inner` called at 4:21", "in `outer` called at 1:5"]
+        );
+        // the error points into the source the function came from
+        assert_eq!(
+            err.render(),
+            "error: division by zero\n \
+             --> 2:5\n  \
+             |\n\
+             2 |   x / 0\n  \
+             |     ^\n  \
+             = in `inner` called at 4:21\n  \
+             = in `outer` called at 1:5"
+        );
+
+        // called from the host, the outermost frame has no call site
+        let err = match interpreter.call_function("outer", vec![Value::Integer(2)]) {
+            Err(Error::Runtime(err)) => err,
+            other => panic!("expected runtime error, got {:?}", other),
+        };
+        assert_eq!(err.trace.last().unwrap().to_string(), "in `outer`");
+    }
+
+    #[test]
+    fn test_anonymous_functions() {
+        let mut interpreter = Interpreter::new();
+
+        interpreter.set_global("yes", true);
+        let err = runtime_error(&mut interpreter, "fn() { -yes }()");
+        assert_eq!(err.kind, ErrorKind::UnknownOperator);
+        assert_eq!(err.trace[0].to_string(), "in `<anonymous>` called at 1:1");
+
+        // a function keeps the first name it was bound to
+        let err = runtime_error(&mut interpreter, "let f = fn() { missing }; let g = f; g()");
+        assert_eq!(err.kind, ErrorKind::UndefinedIdentifier);
+        assert_eq!(err.trace[0].function, "f");
+    }
+}
@@ -0,0 +1,122 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::parse;
+
+    use std::process::Command;
+
+    #[test]
+    fn test_sexp() {
+        let tests = [
+            ("let x = 1 + 2 * 3;", "(program (let x (+ 1 (* 2 3))))"),
+            ("-a; !true", "(program (- a) (! true))"),
+            ("return \"hi\";", "(program (return \"hi\"))"),
+            (
+                "if (x < y) { x } else { throw y }",
+                "(program (if (< x y) (block x) (block (throw y))))",
+            ),
+            (
+                "fn(a, b) { a }(1, [2][0])",
+                "(program (call (fn (a b) (block a)) 1 (index (array 2) 0)))",
+            ),
+            (
+                "try { f() } catch (e) { {\"k\": e} }",
+                "(program (try (block (call f)) (catch e (block (hash (\"k\" e))))))",
+            ),
+            (
+                "let f: any = fn(a: [int], b) -> int { a[0] };",
+                "(program (let f: any (fn (a: [int] b) -> int (block (index a 0)))))",
+            ),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(parse(input).to_sexp(), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_sexp_breaks_long_lists() {
+        let program = parse(
+            "let long = fn(first, second) { first + second + first * second - first / second };",
+        );
+        assert_eq!(
+            program.to_sexp(),
+            "\
+(program
+  (let long
+    (fn
+      (first second)
+      (block (- (+ (+ first second) (* first second)) (/ first second))))))"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json() {
+        let program = parse("let x = -1;");
+        let json: serde_json::Value = serde_json::from_str(&program.to_json()).unwrap();
+        let stmt = &json["statements"][0];
+        assert_eq!(stmt["kind"], "let");
+        assert_eq!(stmt["id"], 0);
+        assert_eq!(stmt["name"]["value"], "x");
+        assert_eq!(stmt["value"]["kind"], "prefix");
+        assert_eq!(stmt["value"]["operator"], "-");
+        assert_eq!(stmt["value"]["right"]["kind"], "integer");
+        assert_eq!(stmt["value"]["right"]["value"], 1);
+        let span = &stmt["value"]["right"]["token"]["span"];
+        assert_eq!(
+            (span["start"].as_u64(), span["end"].as_u64()),
+            (Some(9), Some(10))
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json_spans() {
+        let program = parse("let x = f(1)[0];\n(1 + 2) * 3;");
+        let json: serde_json::Value = serde_json::from_str(&program.to_json()).unwrap();
+        let span = |node: &serde_json::Value| {
+            (
+                node["span"]["start"].as_u64().unwrap(),
+                node["span"]["end"].as_u64().unwrap(),
+            )
+        };
+
+        let stmt = &json["statements"][0];
+        assert_eq!(span(stmt), (0, 16));
+        assert_eq!(span(&stmt["name"]), (4, 5));
+        assert_eq!(span(&stmt["value"]), (8, 15));
+        assert_eq!(span(&stmt["value"]["left"]), (8, 12));
+        assert_eq!(span(&stmt["value"]["left"]["arguments"][0]), (10, 11));
+
+        let stmt = &json["statements"][1];
+        assert_eq!(span(stmt), (17, 29));
+        assert_eq!(span(&stmt["expression"]), (17, 28));
+        assert_eq!(span(&stmt["expression"]["left"]), (18, 23));
+    }
+
+    #[test]
+    fn test_parse_command() {
+        let path = std::env::temp_dir().join("monkey_parse_command.mk");
+        std::fs::write(&path, "let x = f(1);").unwrap();
+        let output = Command::new(env!("CARGO_BIN_EXE_monkey"))
+            .args(["parse", "--format", "sexp"])
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "(program (let x (call f 1)))\n"
+        );
+
+        std::fs::write(&path, "let = 1;").unwrap();
+        let output = Command::new(env!("CARGO_BIN_EXE_monkey"))
+            .arg("parse")
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).starts_with("error: "));
+    }
+}
@@ -0,0 +1,456 @@
+#[cfg(test)]
+mod tests {
+    use monkey::{
+        parser::parser::Parser, Environment, ErrorKind, Evaluator, HashKey, Lexer, Value,
+    };
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn test_eval(input: &str) -> Value {
+        let mut l = Lexer::new(input.to_string());
+        let mut p = Parser::new(&mut l);
+        let program = p.parse_program().unwrap();
+        assert!(p.errors().is_empty(), "parser errors: {:?}", p.errors());
+        Evaluator::new().eval_program(&program, &Rc::new(RefCell::new(Environment::new())))
+    }
+
+    #[test]
+    fn test_eval_integer_expression() {
+        let tests = [
+            ("5", 5),
+            ("-10", -10),
+            ("5 + 5 + 5 + 5 - 10", 10),
+            ("2 * 2 * 2 * 2 * 2", 32),
+            ("-50 + 100 + -50", 0),
+            ("20 + 2 * -10", 0),
+            ("2 * (5 + 10)", 30),
+            ("3 * 3 * 3 + 10", 37),
+            ("(5 + 10 * 2 + 15 / 3) * 2 + -10", 50),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input), Value::Integer(expected), "{}", input);
+        }
+    }
+
+    #[test]
+    #[cfg(not(feature = "bigint"))]
+    fn test_integer_overflow() {
+        let tests = [
+            (
+                "9223372036854775807 + 1",
+                "integer overflow: 9223372036854775807 + 1",
+            ),
+            (
+                "-9223372036854775807 - 2",
+                "integer overflow: -9223372036854775807 - 2",
+            ),
+            (
+                "4611686018427387904 * 2",
+                "integer overflow: 4611686018427387904 * 2",
+            ),
+            (
+                "let min = -9223372036854775807 - 1; min / -1",
+                "integer overflow: -9223372036854775808 / -1",
+            ),
+            (
+                "let min = -9223372036854775807 - 1; -min",
+                "integer overflow: -(-9223372036854775808)",
+            ),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(
+                test_eval(input),
+                Value::error(ErrorKind::IntegerOverflow, expected),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "bigint")]
+    fn test_big_integers() {
+        let tests = [
+            ("9223372036854775807 + 1", "9223372036854775808"),
+            (
+                "9223372036854775807 * 9223372036854775807",
+                "85070591730234615847396907784232501249",
+            ),
+            ("-18446744073709551616 + 1", "-18446744073709551615"),
+            ("(9223372036854775807 + 1) > 9223372036854775807", "true"),
+            ("{18446744073709551616: 1}[18446744073709551616]", "1"),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input).to_string(), expected, "{}", input);
+        }
+        // results shrink back to plain integers once they fit
+        assert_eq!(
+            test_eval("(9223372036854775807 + 1) - 1"),
+            Value::Integer(i64::MAX)
+        );
+        assert_eq!(
+            test_eval("18446744073709551616 / 0"),
+            Value::error(ErrorKind::DivisionByZero, "division by zero")
+        );
+    }
+
+    #[test]
+    fn test_eval_boolean_expression() {
+        let tests = [
+            ("true", true),
+            ("false", false),
+            ("1 < 2", true),
+            ("1 > 2", false),
+            ("1 == 1", true),
+            ("1 != 1", false),
+            ("true == true", true),
+            ("true != false", true),
+            ("(1 < 2) == true", true),
+            ("!true", false),
+            ("!!5", true),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input), Value::Boolean(expected), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_if_else_expressions() {
+        let tests = [
+            ("if (true) { 10 }", Value::Integer(10)),
+            ("if (false) { 10 }", Value::Null),
+            ("if (1 < 2) { 10 } else { 20 }", Value::Integer(10)),
+            ("if (1 > 2) { 10 } else { 20 }", Value::Integer(20)),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_return_statements() {
+        let tests = [
+            ("return 10; 9;", 10),
+            ("9; return 2 * 5; 9;", 10),
+            ("if (10 > 1) { if (10 > 1) { return 10; } return 1; }", 10),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input), Value::Integer(expected), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_error_handling() {
+        let tests = [
+            (
+                "5 + true;",
+                ErrorKind::TypeMismatch,
+                "type mismatch: INTEGER + BOOLEAN",
+            ),
+            (
+                "5 + true; 5;",
+                ErrorKind::TypeMismatch,
+                "type mismatch: INTEGER + BOOLEAN",
+            ),
+            (
+                "-true",
+                ErrorKind::UnknownOperator,
+                "unknown operator: -BOOLEAN",
+            ),
+            (
+                "true + false;",
+                ErrorKind::UnknownOperator,
+                "unknown operator: BOOLEAN + BOOLEAN",
+            ),
+            (
+                "if (10 > 1) { return true + false; }",
+                ErrorKind::UnknownOperator,
+                "unknown operator: BOOLEAN + BOOLEAN",
+            ),
+            (
+                "foobar",
+                ErrorKind::UndefinedIdentifier,
+                "identifier not found: foobar",
+            ),
+            ("10 / 0", ErrorKind::DivisionByZero, "division by zero"),
+            (
+                "let f = fn(x) { x }; f()",
+                ErrorKind::WrongArgumentCount,
+                "wrong number of arguments: want=1, got=0",
+            ),
+            (
+                "[1, 2, 3][3]",
+                ErrorKind::IndexOutOfRange,
+                "index out of range: 3, length is 3",
+            ),
+            (
+                "[1, 2, 3][-1]",
+                ErrorKind::IndexOutOfRange,
+                "index out of range: -1, length is 3",
+            ),
+        ];
+        for (input, kind, expected) in tests {
+            assert_eq!(test_eval(input), Value::error(kind, expected), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_let_statements() {
+        let tests = [
+            ("let a = 5; a;", 5),
+            ("let a = 5 * 5; a;", 25),
+            ("let a = 5; let b = a; let c = a + b + 5; c;", 15),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input), Value::Integer(expected), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_function_application() {
+        let tests = [
+            ("let identity = fn(x) { x; }; identity(5);", 5),
+            ("let identity = fn(x) { return x; }; identity(5);", 5),
+            ("let add = fn(x, y) { x + y; }; add(5 + 5, add(5, 5));", 20),
+            ("fn(x) { x; }(5)", 5),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input), Value::Integer(expected), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_closures() {
+        let input = "let newAdder = fn(x) { fn(y) { x + y } };\
+        let addTwo = newAdder(2);\
+        addTwo(2);";
+        assert_eq!(test_eval(input), Value::Integer(4));
+    }
+
+    #[test]
+    fn test_string_expressions() {
+        let tests = [
+            (
+                r#""Hello World!""#,
+                Value::String("Hello World!".to_string()),
+            ),
+            (
+                r#""Hello" + " " + "World!""#,
+                Value::String("Hello World!".to_string()),
+            ),
+            (r#""a" == "a""#, Value::Boolean(true)),
+            (r#""a" != "a""#, Value::Boolean(false)),
+            (
+                r#""Hello" - "World""#,
+                Value::error(
+                    ErrorKind::UnknownOperator,
+                    "unknown operator: STRING - STRING",
+                ),
+            ),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_array_literals() {
+        assert_eq!(
+            test_eval("[1, 2 * 2, 3 + 3]"),
+            Value::Array(vec![
+                Value::Integer(1),
+                Value::Integer(4),
+                Value::Integer(6)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_array_index_expressions() {
+        let tests = [
+            ("[1, 2, 3][0]", Value::Integer(1)),
+            ("[1, 2, 3][2]", Value::Integer(3)),
+            ("let i = 0; [1][i];", Value::Integer(1)),
+            ("[1, 2, 3][1 + 1];", Value::Integer(3)),
+            ("let myArray = [1, 2, 3]; myArray[2];", Value::Integer(3)),
+            (
+                "1[0]",
+                Value::error(
+                    ErrorKind::TypeMismatch,
+                    "index operator not supported: INTEGER",
+                ),
+            ),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_builtin_functions() {
+        let tests = [
+            (r#"len("")"#, Value::Integer(0)),
+            (r#"len("four")"#, Value::Integer(4)),
+            (r#"len("hello world")"#, Value::Integer(11)),
+            ("len([1, 2, 3])", Value::Integer(3)),
+            (
+                "len(1)",
+                Value::error(
+                    ErrorKind::InvalidArgument,
+                    "argument to `len` must be STRING or ARRAY, got INTEGER",
+                ),
+            ),
+            (
+                r#"len("one", "two")"#,
+                Value::error(
+                    ErrorKind::WrongArgumentCount,
+                    "wrong number of arguments to `len`: want=1, got=2",
+                ),
+            ),
+            ("first([1, 2, 3])", Value::Integer(1)),
+            ("first([])", Value::Null),
+            (
+                "first(1)",
+                Value::error(
+                    ErrorKind::InvalidArgument,
+                    "argument to `first` must be ARRAY, got INTEGER",
+                ),
+            ),
+            ("last([1, 2, 3])", Value::Integer(3)),
+            ("last([])", Value::Null),
+            (
+                "rest([1, 2, 3])",
+                Value::Array(vec![Value::Integer(2), Value::Integer(3)]),
+            ),
+            ("rest([])", Value::Null),
+            ("push([], 1)", Value::Array(vec![Value::Integer(1)])),
+            (
+                "push(1, 1)",
+                Value::error(
+                    ErrorKind::InvalidArgument,
+                    "argument to `push` must be ARRAY, got INTEGER",
+                ),
+            ),
+            (r#"puts("hello", 1)"#, Value::Null),
+            ("let len = fn(x) { 42 }; len([])", Value::Integer(42)),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_hash_literals() {
+        let input = r#"let two = "two";
+        {
+            "one": 10 - 9,
+            two: 1 + 1,
+            "thr" + "ee": 6 / 2,
+            4: 4,
+            true: 5,
+            false: 6
+        }"#;
+        let Value::Hash(pairs) = test_eval(input) else {
+            panic!("not a hash");
+        };
+        let expected = [
+            (HashKey::String("one".to_string()), 1),
+            (HashKey::String("two".to_string()), 2),
+            (HashKey::String("three".to_string()), 3),
+            (HashKey::Integer(4), 4),
+            (HashKey::Boolean(true), 5),
+            (HashKey::Boolean(false), 6),
+        ];
+        assert_eq!(pairs.len(), expected.len());
+        for (key, value) in expected {
+            assert_eq!(pairs[&key], Value::Integer(value));
+        }
+    }
+
+    #[test]
+    fn test_hash_index_expressions() {
+        let tests = [
+            (r#"{"foo": 5}["foo"]"#, Value::Integer(5)),
+            (r#"{"foo": 5}["bar"]"#, Value::Null),
+            (r#"let key = "foo"; {"foo": 5}[key]"#, Value::Integer(5)),
+            (r#"{}["foo"]"#, Value::Null),
+            ("{5: 5}[5]", Value::Integer(5)),
+            ("{true: 5}[true]", Value::Integer(5)),
+            (
+                r#"{"name": "Monkey"}[fn(x) { x }];"#,
+                Value::error(ErrorKind::TypeMismatch, "unusable as hash key: FUNCTION"),
+            ),
+            (
+                "{[1]: 2}",
+                Value::error(ErrorKind::TypeMismatch, "unusable as hash key: ARRAY"),
+            ),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_try_catch() {
+        let tests = [
+            ("try { 1 } catch (e) { 2 }", Value::Integer(1)),
+            ("try { 1 / 0 } catch (e) { 2 }", Value::Integer(2)),
+            (
+                "try { 1 + true } catch (e) { e[\"message\"] }",
+                Value::String("type mismatch: INTEGER + BOOLEAN".to_string()),
+            ),
+            (
+                "try { missing } catch (e) { e[\"kind\"] }",
+                Value::String("UNDEFINED_IDENTIFIER".to_string()),
+            ),
+            (
+                "try { throw \"oops\"; 1 } catch (e) { e[\"message\"] }",
+                Value::String("oops".to_string()),
+            ),
+            (
+                "try { throw {\"code\": 42} } catch (e) { e[\"value\"][\"code\"] }",
+                Value::Integer(42),
+            ),
+            (
+                "try { throw 1 } catch (e) { e[\"kind\"] }",
+                Value::String("CUSTOM".to_string()),
+            ),
+            // the nearest handler wins, and handlers can throw again
+            (
+                "try { try { throw 1 } catch (e) { throw e[\"value\"] + 1 } } catch (e) { e[\"value\"] }",
+                Value::Integer(2),
+            ),
+            // unwinds out of calls to the handler
+            (
+                "let f = fn(n) { if (n == 0) { throw \"bottom\" } else { f(n - 1) } };\
+                 try { f(3) } catch (e) { len(e[\"trace\"]) }",
+                Value::Integer(4),
+            ),
+            // return passes through try
+            (
+                "let f = fn() { try { return 1; 2 } catch (e) { 3 }; 4 }; f()",
+                Value::Integer(1),
+            ),
+            // the caught error is only visible in the handler
+            (
+                "try { throw 1 } catch (e) { 0 }; e",
+                Value::error(ErrorKind::UndefinedIdentifier, "identifier not found: e"),
+            ),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_uncaught_throw() {
+        assert_eq!(
+            test_eval("throw \"bad input\""),
+            Value::error(ErrorKind::Custom, "bad input")
+        );
+        assert_eq!(
+            test_eval("throw [1, 2]"),
+            Value::error(ErrorKind::Custom, "[1, 2]")
+        );
+    }
+}
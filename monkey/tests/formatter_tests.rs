@@ -0,0 +1,156 @@
+#[cfg(test)]
+mod tests {
+    use monkey::format_source;
+    use std::process::Command;
+
+    fn format(input: &str) -> String {
+        let formatted = format_source(input).unwrap();
+        assert_eq!(
+            format_source(&formatted).unwrap(),
+            formatted,
+            "formatting again changed the output"
+        );
+        formatted
+    }
+
+    #[test]
+    fn test_format_statements() {
+        let tests = [
+            ("let x=5", "let x = 5;\n"),
+            ("return   x ;throw \"no\"", "return x;\nthrow \"no\";\n"),
+            ("puts( 1 ,2)", "puts(1, 2);\n"),
+            ("[1,2 ,3][0]", "[1, 2, 3][0];\n"),
+            ("{\"a\":1,true:[]}", "{\"a\": 1, true: []};\n"),
+            ("let x:[int]=[]", "let x: [int] = [];\n"),
+            (
+                "fn(a:int,b)->{string:fn(int)->bool}{b}",
+                "fn(a: int, b) -> {string: fn(int) -> bool} {\n    b\n}\n",
+            ),
+            ("", ""),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(format(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_minimal_parentheses() {
+        let tests = [
+            ("((1 + 2)) * 3", "(1 + 2) * 3;\n"),
+            ("1 + (2 * 3)", "1 + 2 * 3;\n"),
+            ("(a - b) - c", "a - b - c;\n"),
+            ("a - (b - c)", "a - (b - c);\n"),
+            ("-(a + b)", "-(a + b);\n"),
+            ("-(-a)", "-(-a);\n"),
+            ("-(-1)", "-(-1);\n"),
+            ("!(!x)", "!(!x);\n"),
+            ("!!x", "!(!x);\n"),
+            ("-(!x)", "-!x;\n"),
+            ("(-a)[0]", "(-a)[0];\n"),
+            ("-(a[0])", "-a[0];\n"),
+            ("(f(1))[0]", "f(1)[0];\n"),
+            ("(a < b) == (c > d)", "a < b == c > d;\n"),
+            ("(fn(x) { x })(1)", "fn(x) {\n    x\n}(1);\n"),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(format(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_format_blocks() {
+        let input = "let max=fn(a,b){if(a>b){return a;}else{b}};
+try{throw 1}catch(e){e};
+let noop = fn(){};";
+        let expected = "\
+let max = fn(a, b) {
+    if (a > b) {
+        return a;
+    } else {
+        b
+    }
+};
+try {
+    throw 1;
+} catch (e) {
+    e
+}
+let noop = fn() {};
+";
+        assert_eq!(format(input), expected);
+    }
+
+    #[test]
+    fn test_semicolons_keep_statements_apart() {
+        // without the `;` the array would index the `if`
+        assert_eq!(format("if (x) { 1 }; [1]"), "if (x) {\n    1\n};\n[1];\n");
+        assert_eq!(format("if (x) { 1 }; y"), "if (x) {\n    1\n}\ny;\n");
+    }
+
+    #[test]
+    fn test_comments() {
+        let input = "// adds
+let add = fn(a, b) {   // both
+  // the sum
+  a + b
+  // done
+};
+
+
+let x = [1, // one
+2]; // two
+// end
+";
+        let expected = "\
+// adds
+let add = fn(a, b) { // both
+    // the sum
+    a + b
+    // done
+};
+
+let x = [1, 2]; // one // two
+// end
+";
+        assert_eq!(format(input), expected);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(format_source("let = 1;").is_err());
+        assert!(format_source("let s = \"abc").is_err());
+        assert_eq!(
+            format_source("let f = fn(x) { x + 1"),
+            Err(vec!["expected `}`, got EOF instead".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_fmt_command() {
+        let path = std::env::temp_dir().join("monkey_fmt_command.mk");
+        std::fs::write(&path, "let x=1").unwrap();
+
+        let check = Command::new(env!("CARGO_BIN_EXE_monkey"))
+            .args(["fmt", "--check"])
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(!check.status.success());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "let x=1");
+
+        let status = Command::new(env!("CARGO_BIN_EXE_monkey"))
+            .arg("fmt")
+            .arg(&path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "let x = 1;\n");
+
+        let check = Command::new(env!("CARGO_BIN_EXE_monkey"))
+            .args(["fmt", "--check"])
+            .arg(&path)
+            .status()
+            .unwrap();
+        assert!(check.success());
+    }
+}
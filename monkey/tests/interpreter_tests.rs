@@ -0,0 +1,238 @@
+#[cfg(test)]
+mod tests {
+    use monkey::{Error, ErrorKind, Interpreter, Value};
+
+    #[test]
+    fn test_eval_str() {
+        let mut interpreter = Interpreter::new();
+
+        assert_eq!(interpreter.eval_str("1 + 2").unwrap(), Value::Integer(3));
+        assert_eq!(interpreter.eval_str("let x = 10;").unwrap(), Value::Null);
+        assert_eq!(interpreter.eval_str("x * 2").unwrap(), Value::Integer(20));
+    }
+
+    #[test]
+    fn test_eval_str_errors() {
+        let mut interpreter = Interpreter::new();
+
+        match interpreter.eval_str("let = 5;") {
+            Err(Error::Parse(errors)) => {
+                assert_eq!(
+                    errors[0],
+                    "expected next token to be IDENT, got ASSIGN instead"
+                )
+            }
+            other => panic!("expected parse error, got {:?}", other),
+        }
+        match interpreter.eval_str("\"abc") {
+            Err(Error::Parse(errors)) => assert_eq!(errors, ["unterminated string"]),
+            other => panic!("expected parse error, got {:?}", other),
+        }
+        for input in ["if (true) { 1", "let f = fn(x) { x + 1"] {
+            match interpreter.eval_str(input) {
+                Err(Error::Parse(errors)) => assert_eq!(errors, ["expected `}`, got EOF instead"]),
+                other => panic!("expected parse error, got {:?}", other),
+            }
+        }
+        // what the type checker can't see is caught at runtime
+        interpreter.set_global("yes", true);
+        match interpreter.eval_str("1 + yes") {
+            Err(Error::Runtime(err)) => {
+                assert_eq!(err.kind, ErrorKind::TypeMismatch);
+                assert_eq!(err.message, "type mismatch: INTEGER + BOOLEAN");
+            }
+            other => panic!("expected runtime error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_type_errors() {
+        let mut interpreter = Interpreter::new();
+
+        let err = interpreter
+            .eval_str("let before = 1; let x: int = \"a\";")
+            .unwrap_err();
+        match &err {
+            Error::Type { diagnostics, .. } => {
+                assert_eq!(diagnostics.len(), 1);
+                assert_eq!(diagnostics[0].message, "expected `int`, found `string`");
+            }
+            other => panic!("expected type error, got {:?}", other),
+        }
+        assert!(err
+            .render()
+            .starts_with("error[type-mismatch]: expected `int`, found `string`\n --> 1:30"));
+        // none of it ran
+        assert_eq!(interpreter.get_global("before"), None);
+
+        match interpreter.eval_str("1 + true") {
+            Err(Error::Type { diagnostics, .. }) => assert_eq!(
+                diagnostics[0].message,
+                "`+` can't be applied to `int` and `bool`"
+            ),
+            other => panic!("expected type error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_eval_file() {
+        let path = std::env::temp_dir().join("monkey_interpreter_eval_file.mk");
+        std::fs::write(&path, "let greet = fn(name) { \"hello \" + name };").unwrap();
+
+        let mut interpreter = Interpreter::new();
+        interpreter.eval_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            interpreter.eval_str(r#"greet("monkey")"#).unwrap(),
+            Value::String("hello monkey".to_string())
+        );
+        assert!(matches!(
+            interpreter.eval_file(&path),
+            Err(Error::Io { .. })
+        ));
+    }
+
+    #[test]
+    fn test_globals() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_global("limit", Value::Integer(3));
+
+        assert_eq!(
+            interpreter.eval_str("limit + 1").unwrap(),
+            Value::Integer(4)
+        );
+        interpreter.eval_str("let items = [1, 2];").unwrap();
+        assert_eq!(
+            interpreter.get_global("items"),
+            Some(Value::Array(vec![Value::Integer(1), Value::Integer(2)]))
+        );
+        assert_eq!(interpreter.get_global("missing"), None);
+    }
+
+    #[test]
+    fn test_resolved_names() {
+        let mut interpreter = Interpreter::new();
+        let input = "
+            let x = 1;
+            let f = fn(x) { let g = fn() { x }; g() };
+            let h = fn() { later };
+            let later = 3;
+            if (x > 0) { let maybe = 4; };
+            [f(2), x, h(), maybe]
+        ";
+        assert_eq!(
+            interpreter.eval_str(input).unwrap(),
+            Value::Array(vec![
+                Value::Integer(2),
+                Value::Integer(1),
+                Value::Integer(3),
+                Value::Integer(4),
+            ])
+        );
+        // names bound by an earlier input are still found
+        assert_eq!(
+            interpreter.eval_str("f(x) + later").unwrap(),
+            Value::Integer(4)
+        );
+    }
+
+    #[test]
+    fn test_call_function() {
+        let mut interpreter = Interpreter::new();
+        interpreter
+            .eval_str("let max = fn(a, b) { if (a > b) { a } else { b } };")
+            .unwrap();
+
+        assert_eq!(
+            interpreter
+                .call_function("max", vec![Value::Integer(3), Value::Integer(7)])
+                .unwrap(),
+            Value::Integer(7)
+        );
+        assert_eq!(
+            interpreter
+                .call_function("len", vec![Value::String("four".to_string())])
+                .unwrap(),
+            Value::Integer(4)
+        );
+        assert_eq!(
+            interpreter
+                .call_function("nope", vec![])
+                .unwrap_err()
+                .to_string(),
+            "identifier not found: nope"
+        );
+        assert_eq!(
+            interpreter
+                .call_function("max", vec![])
+                .unwrap_err()
+                .to_string(),
+            "wrong number of arguments: want=2, got=0"
+        );
+    }
+
+    #[test]
+    fn test_register_fn() {
+        let mut interpreter = Interpreter::new();
+        interpreter.register_fn("starts_with", |s: String, prefix: String| {
+            s.starts_with(&prefix)
+        });
+        interpreter.register_fn("sum", |values: Vec<i64>| values.iter().sum::<i64>());
+        interpreter.register_fn("answer", || 42);
+        interpreter.register_fn("or_default", |v: Option<i64>| v.unwrap_or(-1));
+        interpreter.register_fn("checked_div", |a: i64, b: i64| {
+            a.checked_div(b).ok_or("cannot divide by zero")
+        });
+
+        let tests = [
+            (r#"starts_with("monkey", "mon")"#, Value::Boolean(true)),
+            ("sum([1, 2, 3])", Value::Integer(6)),
+            ("answer() + 1", Value::Integer(43)),
+            ("or_default(if (false) { 1 })", Value::Integer(-1)),
+            ("or_default(7)", Value::Integer(7)),
+            ("checked_div(9, 3)", Value::Integer(3)),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(interpreter.eval_str(input).unwrap(), expected, "{}", input);
+        }
+
+        let errors = [
+            (
+                r#"starts_with("monkey", 1)"#,
+                "argument to `starts_with` must be STRING, got INTEGER",
+            ),
+            (
+                r#"sum([1, "2"])"#,
+                "argument to `sum` must be ARRAY of INTEGER, got ARRAY",
+            ),
+            (
+                "answer(1)",
+                "wrong number of arguments to `answer`: want=0, got=1",
+            ),
+            ("checked_div(1, 0)", "cannot divide by zero"),
+        ];
+        for (input, expected) in errors {
+            assert_eq!(
+                interpreter.eval_str(input).unwrap_err().to_string(),
+                expected,
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_set_global_converts() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_global("name", "monkey");
+        interpreter.set_global("tags", vec!["a", "b"]);
+        interpreter.set_global("missing", None::<i64>);
+
+        assert_eq!(
+            interpreter.eval_str(r#"name + ":" + tags[1]"#).unwrap(),
+            Value::String("monkey:b".to_string())
+        );
+        assert_eq!(interpreter.get_global("missing"), Some(Value::Null));
+    }
+}
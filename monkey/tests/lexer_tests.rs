@@ -6,46 +6,16 @@ mod tests {
     fn test_next_token_simple() {
         let input = "=+ \n(){},;! ".to_string();
         let hope_test_val = vec![
-            Token {
-                r#type: TokenType::ASSIGN,
-                literal: "=".to_string(),
-            },
-            Token {
-                r#type: TokenType::PLUS,
-                literal: "+".to_string(),
-            },
-            Token {
-                r#type: TokenType::LPAREN,
-                literal: "(".to_string(),
-            },
-            Token {
-                r#type: TokenType::RPAREN,
-                literal: ")".to_string(),
-            },
-            Token {
-                r#type: TokenType::LBRACE,
-                literal: "{".to_string(),
-            },
-            Token {
-                r#type: TokenType::RBRACE,
-                literal: "}".to_string(),
-            },
-            Token {
-                r#type: TokenType::COMMA,
-                literal: ",".to_string(),
-            },
-            Token {
-                r#type: TokenType::SEMICOLON,
-                literal: ";".to_string(),
-            },
-            Token {
-                r#type: TokenType::BANG,
-                literal: "!".to_string(),
-            },
-            Token {
-                r#type: TokenType::EOF,
-                literal: "".to_string(),
-            },
+            Token::new(TokenType::ASSIGN, "=".to_string()),
+            Token::new(TokenType::PLUS, "+".to_string()),
+            Token::new(TokenType::LPAREN, "(".to_string()),
+            Token::new(TokenType::RPAREN, ")".to_string()),
+            Token::new(TokenType::LBRACE, "{".to_string()),
+            Token::new(TokenType::RBRACE, "}".to_string()),
+            Token::new(TokenType::COMMA, ",".to_string()),
+            Token::new(TokenType::SEMICOLON, ";".to_string()),
+            Token::new(TokenType::BANG, "!".to_string()),
+            Token::new(TokenType::EOF, "".to_string()),
         ];
 
         let mut lexer = Lexer::new(input);
@@ -68,130 +38,37 @@ mod tests {
         return true;"
             .to_string();
         let hope_test_val = vec![
-            Token {
-                r#type: TokenType::LET,
-                literal: "let".to_string(),
-            },
-            Token {
-                r#type: TokenType::IDENT,
-                literal: "five".to_string(),
-            },
-            Token {
-                r#type: TokenType::ASSIGN,
-                literal: "=".to_string(),
-            },
-            Token {
-                r#type: TokenType::INT,
-                literal: "5".to_string(),
-            },
-            Token {
-                r#type: TokenType::SEMICOLON,
-                literal: ";".to_string(),
-            },
-            Token {
-                r#type: TokenType::LET,
-                literal: "let".to_string(),
-            },
-            Token {
-                r#type: TokenType::IDENT,
-                literal: "ten".to_string(),
-            },
-            Token {
-                r#type: TokenType::ASSIGN,
-                literal: "=".to_string(),
-            },
-            Token {
-                r#type: TokenType::INT,
-                literal: "10".to_string(),
-            },
-            Token {
-                r#type: TokenType::SEMICOLON,
-                literal: ";".to_string(),
-            },
-            Token {
-                r#type: TokenType::FUNCTION,
-                literal: "fn".to_string(),
-            },
-            Token {
-                r#type: TokenType::LPAREN,
-                literal: "(".to_string(),
-            },
-            Token {
-                r#type: TokenType::IDENT,
-                literal: "x".to_string(),
-            },
-            Token {
-                r#type: TokenType::COMMA,
-                literal: ",".to_string(),
-            },
-            Token {
-                r#type: TokenType::IDENT,
-                literal: "y".to_string(),
-            },
-            Token {
-                r#type: TokenType::RPAREN,
-                literal: ")".to_string(),
-            },
-            Token {
-                r#type: TokenType::LBRACE,
-                literal: "{".to_string(),
-            },
-            Token {
-                r#type: TokenType::IDENT,
-                literal: "x".to_string(),
-            },
-            Token {
-                r#type: TokenType::PLUS,
-                literal: "+".to_string(),
-            },
-            Token {
-                r#type: TokenType::IDENT,
-                literal: "y".to_string(),
-            },
-            Token {
-                r#type: TokenType::SEMICOLON,
-                literal: ";".to_string(),
-            },
-            Token {
-                r#type: TokenType::RBRACE,
-                literal: "}".to_string(),
-            },
-            Token {
-                r#type: TokenType::SEMICOLON,
-                literal: ";".to_string(),
-            },
-            Token {
-                r#type: TokenType::INT,
-                literal: "10".to_string(),
-            },
-            Token {
-                r#type: TokenType::NotEq,
-                literal: "!=".to_string(),
-            },
-            Token {
-                r#type: TokenType::INT,
-                literal: "9".to_string(),
-            },
-            Token {
-                r#type: TokenType::SEMICOLON,
-                literal: ";".to_string(),
-            },
-            Token {
-                r#type: TokenType::RETURN,
-                literal: "return".to_string(),
-            },
-            Token {
-                r#type: TokenType::TRUE,
-                literal: "true".to_string(),
-            },
-            Token {
-                r#type: TokenType::SEMICOLON,
-                literal: ";".to_string(),
-            },
-            Token {
-                r#type: TokenType::EOF,
-                literal: "".to_string(),
-            },
+            Token::new(TokenType::LET, "let".to_string()),
+            Token::new(TokenType::IDENT, "five".to_string()),
+            Token::new(TokenType::ASSIGN, "=".to_string()),
+            Token::new(TokenType::INT, "5".to_string()),
+            Token::new(TokenType::SEMICOLON, ";".to_string()),
+            Token::new(TokenType::LET, "let".to_string()),
+            Token::new(TokenType::IDENT, "ten".to_string()),
+            Token::new(TokenType::ASSIGN, "=".to_string()),
+            Token::new(TokenType::INT, "10".to_string()),
+            Token::new(TokenType::SEMICOLON, ";".to_string()),
+            Token::new(TokenType::FUNCTION, "fn".to_string()),
+            Token::new(TokenType::LPAREN, "(".to_string()),
+            Token::new(TokenType::IDENT, "x".to_string()),
+            Token::new(TokenType::COMMA, ",".to_string()),
+            Token::new(TokenType::IDENT, "y".to_string()),
+            Token::new(TokenType::RPAREN, ")".to_string()),
+            Token::new(TokenType::LBRACE, "{".to_string()),
+            Token::new(TokenType::IDENT, "x".to_string()),
+            Token::new(TokenType::PLUS, "+".to_string()),
+            Token::new(TokenType::IDENT, "y".to_string()),
+            Token::new(TokenType::SEMICOLON, ";".to_string()),
+            Token::new(TokenType::RBRACE, "}".to_string()),
+            Token::new(TokenType::SEMICOLON, ";".to_string()),
+            Token::new(TokenType::INT, "10".to_string()),
+            Token::new(TokenType::NotEq, "!=".to_string()),
+            Token::new(TokenType::INT, "9".to_string()),
+            Token::new(TokenType::SEMICOLON, ";".to_string()),
+            Token::new(TokenType::RETURN, "return".to_string()),
+            Token::new(TokenType::TRUE, "true".to_string()),
+            Token::new(TokenType::SEMICOLON, ";".to_string()),
+            Token::new(TokenType::EOF, "".to_string()),
         ];
 
         let mut lexer = Lexer::new(input);
@@ -202,4 +79,151 @@ mod tests {
             assert_eq!(tok.literal, item.literal);
         }
     }
+
+    #[test]
+    fn test_next_token_at_end_of_input() {
+        let mut lexer = Lexer::new("x + 10".to_string());
+
+        for (token_type, literal) in [
+            (TokenType::IDENT, "x"),
+            (TokenType::PLUS, "+"),
+            (TokenType::INT, "10"),
+            (TokenType::EOF, ""),
+        ] {
+            let tok = lexer.next_token();
+            assert_eq!(tok.r#type, token_type);
+            assert_eq!(tok.literal, literal);
+        }
+    }
+
+    #[test]
+    fn test_next_token_non_ascii() {
+        let mut lexer = Lexer::new("let é = 1;".to_string());
+
+        for (token_type, literal) in [
+            (TokenType::LET, "let"),
+            (TokenType::ILLEGAL, "é"),
+            (TokenType::ASSIGN, "="),
+            (TokenType::INT, "1"),
+            (TokenType::SEMICOLON, ";"),
+            (TokenType::EOF, ""),
+        ] {
+            let tok = lexer.next_token();
+            assert_eq!(tok.r#type, token_type);
+            assert_eq!(tok.literal, literal);
+        }
+    }
+
+    #[test]
+    fn test_next_token_strings_and_arrays() {
+        let mut lexer = Lexer::new(r#""foobar" "foo bar" [1, 2]; "open"#.to_string());
+
+        for (token_type, literal) in [
+            (TokenType::STRING, "foobar"),
+            (TokenType::STRING, "foo bar"),
+            (TokenType::LBRACKET, "["),
+            (TokenType::INT, "1"),
+            (TokenType::COMMA, ","),
+            (TokenType::INT, "2"),
+            (TokenType::RBRACKET, "]"),
+            (TokenType::SEMICOLON, ";"),
+            // no closing quote
+            (TokenType::ILLEGAL, "\"open"),
+            (TokenType::EOF, ""),
+        ] {
+            let tok = lexer.next_token();
+            assert_eq!(tok.r#type, token_type);
+            assert_eq!(tok.literal, literal);
+        }
+    }
+
+    #[test]
+    fn test_token_spans() {
+        let input = "let é = \"hi\";\n  x != 10";
+        let mut lexer = Lexer::new(input.to_string());
+        let mut spans = Vec::new();
+        loop {
+            let tok = lexer.next_token();
+            spans.push((tok.r#type, &input[tok.span.start..tok.span.end]));
+            if tok.r#type == TokenType::EOF {
+                break;
+            }
+        }
+
+        assert_eq!(
+            spans,
+            [
+                (TokenType::LET, "let"),
+                (TokenType::ILLEGAL, "é"),
+                (TokenType::ASSIGN, "="),
+                (TokenType::STRING, "\"hi\""),
+                (TokenType::SEMICOLON, ";"),
+                (TokenType::IDENT, "x"),
+                (TokenType::NotEq, "!="),
+                (TokenType::INT, "10"),
+                (TokenType::EOF, ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_arrow() {
+        let input = "fn(a: int) -> int { a - >b }";
+        let mut lexer = Lexer::new(input.to_string());
+        let mut tokens = Vec::new();
+        loop {
+            let tok = lexer.next_token();
+            if tok.r#type == TokenType::EOF {
+                break;
+            }
+            tokens.push((tok.r#type, tok.literal));
+        }
+
+        let arrow = (TokenType::ARROW, "->".to_string());
+        assert_eq!(tokens[6], arrow);
+        assert_eq!(tokens[10], (TokenType::MINUS, "-".to_string()));
+        assert_eq!(tokens[11], (TokenType::GT, ">".to_string()));
+        assert_eq!(tokens.iter().filter(|&tok| *tok == arrow).count(), 1);
+    }
+
+    #[test]
+    fn test_comments() {
+        let input = "// header\nlet x = 10 / 2; // half\r\n//";
+        let mut lexer = Lexer::new(input.to_string());
+        let mut types = Vec::new();
+        loop {
+            let tok = lexer.next_token();
+            types.push(tok.r#type);
+            if tok.r#type == TokenType::EOF {
+                break;
+            }
+        }
+
+        assert_eq!(
+            types,
+            [
+                TokenType::LET,
+                TokenType::IDENT,
+                TokenType::ASSIGN,
+                TokenType::INT,
+                TokenType::SLASH,
+                TokenType::INT,
+                TokenType::SEMICOLON,
+                TokenType::EOF,
+            ]
+        );
+        let comments: Vec<(&str, &str)> = lexer
+            .comments()
+            .iter()
+            .map(|c| (&*c.text, &input[c.span.start..c.span.end]))
+            .collect();
+        assert_eq!(
+            comments,
+            [
+                ("// header", "// header"),
+                ("// half", "// half"),
+                ("//", "//")
+            ]
+        );
+    }
 }
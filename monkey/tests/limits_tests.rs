@@ -0,0 +1,261 @@
+#[cfg(test)]
+mod tests {
+    use monkey::{
+        format_source, lint, Error, ErrorKind, Interpreter, Limit, LimitExceeded, Limits,
+        LintConfig, Value,
+    };
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// Makes 2^n calls without nesting deeper than n, so it runs for
+    /// ages unless stopped.
+    const RUNAWAY: &str = "let t = fn(n) { if (n == 0) { 0 } else { t(n - 1); t(n - 1) } }; t(40)";
+
+    /// Well below the 2 MiB test threads get by default.
+    const SMALL_STACK: usize = 256 * 1024;
+
+    fn on_small_stack(f: impl FnOnce() + Send + 'static) {
+        thread::Builder::new()
+            .stack_size(SMALL_STACK)
+            .spawn(f)
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    fn limit_error(interpreter: &mut Interpreter, input: &str) -> LimitExceeded {
+        match interpreter.eval_str(input) {
+            Err(Error::LimitExceeded(exceeded)) => exceeded,
+            other => panic!("expected limit exceeded, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_default_depth_limit() {
+        let mut interpreter = Interpreter::new();
+
+        let exceeded = limit_error(&mut interpreter, "let f = fn() { f() }; f()");
+        assert_eq!(
+            exceeded,
+            LimitExceeded {
+                limit: Limit::Depth,
+                max: Limits::DEFAULT_MAX_DEPTH as u64
+            }
+        );
+        assert_eq!(
+            exceeded.to_string(),
+            "limit exceeded: more than 256 nested calls"
+        );
+
+        // recursion within the limit still works, and so does the
+        // interpreter after hitting it
+        let countdown = "let down = fn(n) { if (n == 0) { 0 } else { down(n - 1) } };";
+        interpreter.eval_str(countdown).unwrap();
+        assert_eq!(
+            interpreter.eval_str("down(200)").unwrap(),
+            Value::Integer(0)
+        );
+    }
+
+    #[test]
+    fn test_depth_limit_on_a_small_stack() {
+        on_small_stack(|| {
+            let mut interpreter = Interpreter::new();
+            interpreter
+                .eval_str("let f = fn(n) { if (n == 0) { 0 } else { 1 + f(n - 1) } };")
+                .unwrap();
+
+            assert_eq!(interpreter.eval_str("f(250)").unwrap(), Value::Integer(250));
+            let exceeded = limit_error(&mut interpreter, "f(300)");
+            assert_eq!(exceeded.limit, Limit::Depth);
+
+            // without a limit, recursion goes as deep as memory allows
+            let mut interpreter = Interpreter::with_limits(Limits::none());
+            interpreter
+                .eval_str("let f = fn(n) { if (n == 0) { 0 } else { 1 + f(n - 1) } };")
+                .unwrap();
+            assert_eq!(
+                interpreter.eval_str("f(5000)").unwrap(),
+                Value::Integer(5000)
+            );
+        });
+    }
+
+    #[test]
+    fn test_deeply_nested_input_on_a_small_stack() {
+        on_small_stack(|| {
+            let error = "expressions are nested too deeply, the maximum is 256";
+            let parens = format!("{}1{}", "(".repeat(5000), ")".repeat(5000));
+            let negations = format!("{}1", "-".repeat(5000));
+            let ifs = format!("{}1{}", "if (true) { ".repeat(5000), " }".repeat(5000));
+
+            for input in [&parens, &negations, &ifs] {
+                match Interpreter::new().eval_str(input) {
+                    Err(Error::Parse(errors)) => assert_eq!(errors, [error]),
+                    other => panic!("expected parse error, got {:?}", other),
+                }
+                assert_eq!(format_source(input), Err(vec![error.to_string()]));
+                let diagnostics = lint(input, &LintConfig::default());
+                assert_eq!(diagnostics.len(), 1);
+                assert_eq!(diagnostics[0].message, error);
+            }
+
+            // as deep as allowed still runs
+            let parens = format!("{}1{}", "(".repeat(255), ")".repeat(255));
+            assert_eq!(
+                Interpreter::new().eval_str(&parens).unwrap(),
+                Value::Integer(1)
+            );
+            assert_eq!(format_source(&parens), Ok("1;\n".to_string()));
+            assert!(lint(&parens, &LintConfig::default()).is_empty());
+        });
+    }
+
+    #[test]
+    fn test_long_chains_on_a_small_stack() {
+        on_small_stack(|| {
+            let sum = format!("1{}", " + 1".repeat(999));
+            assert_eq!(
+                Interpreter::new().eval_str(&sum).unwrap(),
+                Value::Integer(1000)
+            );
+            assert!(format_source(&sum).is_ok());
+            assert!(lint(&sum, &LintConfig::default()).is_empty());
+
+            let calls = format!("let f = fn(x) {{ f }}; f{}; 1", "(1)".repeat(1000));
+            assert_eq!(
+                Interpreter::new().eval_str(&calls).unwrap(),
+                Value::Integer(1)
+            );
+        });
+    }
+
+    #[test]
+    fn test_limits_cannot_be_caught() {
+        let mut interpreter = Interpreter::new();
+
+        let exceeded = limit_error(
+            &mut interpreter,
+            "let f = fn() { f() }; try { f() } catch (e) { 0 }",
+        );
+        assert_eq!(exceeded.limit, Limit::Depth);
+    }
+
+    #[test]
+    fn test_step_limit() {
+        let mut interpreter = Interpreter::with_limits(Limits {
+            max_steps: Some(1000),
+            ..Limits::none()
+        });
+        interpreter
+            .eval_str("let loop = fn(n) { if (n == 0) { 0 } else { loop(n - 1) } };")
+            .unwrap();
+
+        assert_eq!(interpreter.eval_str("loop(10)").unwrap(), Value::Integer(0));
+        assert_eq!(
+            limit_error(&mut interpreter, "loop(1000)").limit,
+            Limit::Steps
+        );
+        // the budget is per evaluation
+        assert_eq!(interpreter.eval_str("loop(10)").unwrap(), Value::Integer(0));
+        match interpreter.call_function("loop", vec![Value::Integer(1000)]) {
+            Err(Error::LimitExceeded(exceeded)) => assert_eq!(exceeded.limit, Limit::Steps),
+            other => panic!("expected limit exceeded, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_length_limit() {
+        let mut interpreter = Interpreter::with_limits(Limits {
+            max_length: Some(4),
+            ..Limits::none()
+        });
+
+        assert_eq!(
+            interpreter.eval_str("\"ab\" + \"cd\"").unwrap(),
+            Value::String("abcd".to_string())
+        );
+        assert_eq!(
+            limit_error(&mut interpreter, "\"ab\" + \"cde\"").limit,
+            Limit::Length
+        );
+        assert_eq!(
+            limit_error(&mut interpreter, "push([1, 2, 3, 4], 5)").limit,
+            Limit::Length
+        );
+        assert_eq!(
+            limit_error(&mut interpreter, "{1: 1, 2: 2, 3: 3, 4: 4, 5: 5}").limit,
+            Limit::Length
+        );
+    }
+
+    #[test]
+    fn test_memory_limit() {
+        let mut interpreter = Interpreter::with_limits(Limits {
+            max_memory: Some(1024),
+            ..Limits::none()
+        });
+        interpreter
+            .eval_str("let grow = fn(s, n) { if (n == 0) { s } else { grow(s + s, n - 1) } };")
+            .unwrap();
+
+        assert_eq!(
+            interpreter.eval_str("len(grow(\"a\", 4))").unwrap(),
+            Value::Integer(16)
+        );
+        let exceeded = limit_error(&mut interpreter, "grow(\"a\", 20)");
+        assert_eq!(exceeded.limit, Limit::Memory);
+        assert_eq!(
+            exceeded.to_string(),
+            "limit exceeded: more than 1024 bytes allocated"
+        );
+    }
+
+    #[test]
+    fn test_ordinary_errors_are_not_limits() {
+        let mut interpreter = Interpreter::with_limits(Limits {
+            max_steps: Some(100),
+            ..Limits::default()
+        });
+
+        interpreter.set_global("yes", true);
+        match interpreter.eval_str("1 + yes") {
+            Err(Error::Runtime(err)) => assert_eq!(err.kind, ErrorKind::TypeMismatch),
+            other => panic!("expected runtime error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_timeout() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_timeout(Some(Duration::from_millis(50)));
+
+        let start = Instant::now();
+        match interpreter.eval_str(RUNAWAY) {
+            Err(Error::Timeout(timeout)) => assert_eq!(timeout, Duration::from_millis(50)),
+            other => panic!("expected timeout, got {:?}", other),
+        }
+        assert!(start.elapsed() < Duration::from_secs(5));
+        // each evaluation gets the full timeout again
+        assert_eq!(interpreter.eval_str("t(3)").unwrap(), Value::Integer(0));
+    }
+
+    #[test]
+    fn test_cancel_from_another_thread() {
+        let mut interpreter = Interpreter::new();
+        let token = interpreter.cancel_token();
+        let canceller = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            token.cancel();
+        });
+
+        match interpreter.eval_str(RUNAWAY) {
+            Err(err @ Error::Cancelled) => assert_eq!(err.to_string(), "evaluation cancelled"),
+            other => panic!("expected cancellation, got {:?}", other),
+        }
+        canceller.join().unwrap();
+
+        interpreter.cancel_token().reset();
+        assert_eq!(interpreter.eval_str("t(3)").unwrap(), Value::Integer(0));
+    }
+}
@@ -0,0 +1,262 @@
+#[cfg(test)]
+mod tests {
+    use monkey::{lint, rule, LintConfig, Severity, Span, RULES};
+    use std::process::Command;
+
+    /// Each diagnostic as `code@offset: message`.
+    fn check_with(input: &str, config: &LintConfig) -> Vec<String> {
+        lint(input, config)
+            .into_iter()
+            .map(|diagnostic| {
+                format!(
+                    "{}@{}: {}",
+                    diagnostic.code.unwrap_or("-"),
+                    diagnostic.span.unwrap().start,
+                    diagnostic.message
+                )
+            })
+            .collect()
+    }
+
+    fn check(input: &str) -> Vec<String> {
+        check_with(input, &LintConfig::default())
+    }
+
+    #[test]
+    fn test_constant_condition() {
+        assert_eq!(
+            check("if (true) { 1 }; if (!1) { 2 }; if (\"s\") { 3 }"),
+            [
+                "constant-condition@4: this condition is always true",
+                "constant-condition@21: this condition is always false",
+                "constant-condition@36: this condition is always true",
+            ]
+        );
+        let diagnostics = lint("if (false) { 1 } else { 2 }", &LintConfig::default());
+        assert_eq!(diagnostics[0].notes, ["so the block after it never runs"]);
+        assert!(check("let x = 1; if (x) { 1 }").is_empty());
+    }
+
+    #[test]
+    fn test_bool_comparison() {
+        assert_eq!(
+            check("let x = !0; x == true; false != x; x == !1"),
+            [
+                "bool-comparison@12: comparing with `true` is redundant",
+                "bool-comparison@23: comparing with `false` is redundant",
+            ]
+        );
+        let diagnostics = lint("let x = !0; x == false", &LintConfig::default());
+        assert_eq!(diagnostics[0].span, Some(Span::new(12, 22)));
+        assert_eq!(
+            diagnostics[0].notes,
+            ["negate the other side with `!` instead"]
+        );
+    }
+
+    #[test]
+    fn test_unreachable_code() {
+        let input = "let f = fn() { return 1; puts(2); 3 }; f(); throw 1; 4";
+        assert_eq!(
+            check(input),
+            [
+                "unreachable-code@25: unreachable code",
+                "unreachable-code@53: unreachable code",
+            ]
+        );
+        let diagnostics = lint(input, &LintConfig::default());
+        // from the first statement after the `return` to the end of the last
+        assert_eq!(diagnostics[0].span, Some(Span::new(25, 35)));
+        assert_eq!(diagnostics[0].notes, ["it follows the `return` at 1:16"]);
+    }
+
+    #[test]
+    fn test_self_assignment() {
+        assert_eq!(
+            check("let x = 1; let x = x; x"),
+            ["self-assignment@15: `x` is assigned to itself"]
+        );
+        // an undefined name is reported as such instead
+        assert_eq!(
+            check("let y = y; y"),
+            ["use-before-definition@8: `y` is used before it is defined"]
+        );
+    }
+
+    #[test]
+    fn test_suspicious_negation() {
+        assert_eq!(
+            check("let x = 1; let y = !x; !!!x; !x == y; !(x == 2)"),
+            [
+                "suspicious-negation@23: `!` is applied more than once",
+                "suspicious-negation@29: `!` negates only the left-hand side",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_hash_key() {
+        assert_eq!(
+            check("{1: 1, \"a\": 2, true: 3, 1: 4, \"a\": 5, \"b\": 6}"),
+            [
+                "duplicate-hash-key@24: duplicate key `1` in hash literal",
+                "duplicate-hash-key@30: duplicate key `\"a\"` in hash literal",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolution_rules() {
+        assert_eq!(
+            check("let f = fn(x) { fn() { let x = 2; x } }; f(1); nope"),
+            [
+                "unused-binding@11: `x` is never used",
+                "shadowing@27: `x` shadows an outer binding",
+                "undefined-name@47: `nope` is not defined",
+            ]
+        );
+        let severities: Vec<Severity> = lint("let a = 1; b", &LintConfig::default())
+            .iter()
+            .map(|d| d.severity)
+            .collect();
+        assert_eq!(severities, [Severity::Warning, Severity::Error]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        // only the parse errors, as the rules need a whole program
+        assert_eq!(
+            check("let x = ; if (true) { 1 }"),
+            ["-@8: no prefix parse function for SEMICOLON found"]
+        );
+        assert_eq!(
+            check("if (true) { 1"),
+            ["-@13: expected `}`, got EOF instead"]
+        );
+    }
+
+    #[test]
+    fn test_config() {
+        let config = LintConfig::parse(
+            "# keep scripts short\n\
+             unused-binding = allow\n\n  \
+             constant-condition = error\n",
+        )
+        .unwrap();
+        let diagnostics = lint("let x = 1; if (true) { 2 }", &config);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, Some("constant-condition"));
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+
+        let mut config = LintConfig::default();
+        config.set(rule("undefined-name").unwrap(), Some(Severity::Warning));
+        assert_eq!(
+            config.level(rule("undefined-name").unwrap()),
+            Some(Severity::Warning)
+        );
+        assert_eq!(
+            config.level(rule("shadowing").unwrap()),
+            Some(Severity::Warning)
+        );
+        assert_eq!(lint("nope", &config)[0].severity, Severity::Warning);
+
+        assert_eq!(
+            LintConfig::parse("unused = allow"),
+            Err("line 1: unknown rule `unused`".to_string())
+        );
+        assert_eq!(
+            LintConfig::parse("\nshadowing = off"),
+            Err("line 2: unknown level `off`, expected allow, warning or error".to_string())
+        );
+        assert_eq!(
+            LintConfig::parse("shadowing"),
+            Err("line 1: expected `rule = level`".to_string())
+        );
+        // every rule has a distinct id
+        for (i, rule) in RULES.iter().enumerate() {
+            assert!(RULES[..i].iter().all(|other| other.id != rule.id));
+        }
+    }
+
+    #[test]
+    fn test_allow_comments() {
+        let input = "\
+let a = 1; // monkey-allow(unused-binding)
+// monkey-allow(constant-condition, unused-binding)
+let b = if (true) { 1 };
+let c = 3;
+// monkey-allow(no-such-rule)
+";
+        assert_eq!(
+            check(input),
+            [
+                "unused-binding@124: `c` is never used",
+                "-@131: unknown lint rule `no-such-rule`",
+            ]
+        );
+        // a trailing comment covers only its own line
+        assert_eq!(
+            check(
+                "let a = 1; // monkey-allow(shadowing)\nlet b = 2; // monkey-allow(unused-binding)"
+            ),
+            ["unused-binding@4: `a` is never used"]
+        );
+    }
+
+    #[test]
+    fn test_lint_command() {
+        let dir = std::env::temp_dir();
+        let path = dir.join("monkey_lint_command.mk");
+        let config = dir.join("monkey_lint_command.conf");
+        std::fs::write(&path, "let x = 1;\nif (true) { nope }").unwrap();
+        std::fs::write(&config, "undefined-name = warning\n").unwrap();
+
+        let output = Command::new(env!("CARGO_BIN_EXE_monkey"))
+            .arg("lint")
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("warning[unused-binding]: `x` is never used"));
+        assert!(stderr.contains(&format!("--> {}:2:5", path.display())));
+        assert!(stderr.contains("error[undefined-name]: `nope` is not defined"));
+
+        // with only warnings left, linting succeeds
+        let output = Command::new(env!("CARGO_BIN_EXE_monkey"))
+            .arg("lint")
+            .arg("--config")
+            .arg(&config)
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("warning[undefined-name]: `nope` is not defined"));
+
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&config).unwrap();
+    }
+
+    #[cfg(not(feature = "bigint"))]
+    #[test]
+    fn test_lint_command_rejects_oversized_literals() {
+        let path = std::env::temp_dir().join("monkey_lint_oversized.mk");
+        std::fs::write(&path, "let x = 99999999999999999999999;\nx").unwrap();
+
+        let output = Command::new(env!("CARGO_BIN_EXE_monkey"))
+            .arg("lint")
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(
+            stderr.contains("integer literal 99999999999999999999999 is too large"),
+            "{}",
+            stderr
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,298 @@
+#[cfg(all(test, feature = "lsp"))]
+mod tests {
+    use monkey::{Document, Position, Span, SymbolKind};
+    use serde_json::{json, Value as Json};
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+    const SOURCE: &str = "\
+let add = fn(a, b) {
+    let sum = a + b;
+    sum
+};
+let x = add(1, 2);
+puts(x);
+";
+
+    fn offset_of(source: &str, needle: &str, nth: usize) -> usize {
+        source.match_indices(needle).nth(nth).unwrap().0
+    }
+
+    #[test]
+    fn test_definition_and_references() {
+        let doc = Document::new(SOURCE);
+        let use_of_sum = offset_of(SOURCE, "sum", 1);
+        assert_eq!(doc.definition(use_of_sum + 1), Some(Span::new(29, 32)));
+        let add = offset_of(SOURCE, "add", 1);
+        assert_eq!(doc.references(add, false), [Span::new(add, add + 3)]);
+        assert_eq!(
+            doc.references(add, true),
+            [Span::new(4, 7), Span::new(add, add + 3)]
+        );
+        // builtins and plain values have no definition
+        assert_eq!(doc.definition(offset_of(SOURCE, "puts", 0)), None);
+        assert_eq!(doc.definition(offset_of(SOURCE, "1", 0)), None);
+    }
+
+    #[test]
+    fn test_hover() {
+        let doc = Document::new(SOURCE);
+        let hover = |needle, nth| {
+            doc.hover(offset_of(SOURCE, needle, nth))
+                .map(|hover| hover.contents)
+        };
+        assert_eq!(
+            hover("x", 1).unwrap(),
+            "```monkey\nlet x = add(1, 2)\n```\n`int`"
+        );
+        assert_eq!(
+            hover("add", 0).unwrap(),
+            "```monkey\nlet add = fn(a, b) { …\n```\n`fn('a, 'a) -> 'a`"
+        );
+        assert_eq!(hover("a", 2).unwrap(), "```monkey\nparameter a\n```\n`'a`");
+        assert_eq!(
+            hover("puts", 0).unwrap(),
+            "```monkey\nbuiltin puts\n```\nPrint each argument on its own line."
+        );
+        assert_eq!(hover("1", 0), None);
+
+        let doc = Document::new("let ok = 1 < 2; ok");
+        assert_eq!(
+            doc.hover(16).unwrap().contents,
+            "```monkey\nlet ok = 1 < 2\n```\n`bool`"
+        );
+        let doc = Document::new("let id = fn(v) { v }; try { id(1) } catch (e) { e }");
+        assert_eq!(
+            doc.hover(4).unwrap().contents,
+            "```monkey\nlet id = fn(v) { v }\n```\n`fn('a) -> 'a`"
+        );
+        assert_eq!(
+            doc.hover(49).unwrap().contents,
+            "```monkey\ncatch e\n```\n`{string: any}`"
+        );
+    }
+
+    #[test]
+    fn test_symbols() {
+        let doc = Document::new(SOURCE);
+        let symbols = doc.symbols();
+        let names: Vec<(&str, SymbolKind)> =
+            symbols.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+        assert_eq!(
+            names,
+            [("add", SymbolKind::Function), ("x", SymbolKind::Variable)]
+        );
+        assert_eq!(symbols[0].children[0].name, "sum");
+        assert_eq!(symbols[0].span, Span::new(0, 52));
+        assert_eq!(symbols[0].name_span, Span::new(4, 7));
+    }
+
+    #[test]
+    fn test_diagnostics_and_formatting() {
+        let doc = Document::new("let x 1;");
+        assert_eq!(
+            doc.diagnostics()[0].message,
+            "expected next token to be ASSIGN, got INT instead"
+        );
+        assert_eq!(doc.diagnostics()[0].span, Some(Span::new(6, 7)));
+        assert_eq!(doc.format(), None);
+        assert_eq!(doc.definition(4), None);
+
+        let doc = Document::new("let x=1");
+        assert_eq!(doc.diagnostics()[0].message, "`x` is never used");
+        assert_eq!(doc.diagnostics()[0].span, Some(Span::new(4, 5)));
+        assert_eq!(doc.format().unwrap(), "let x = 1;\n");
+    }
+
+    #[test]
+    fn test_positions() {
+        let doc = Document::new("let s = \"é😀\";\nlet t = s;");
+        let position = |line, character| Position { line, character };
+        // columns count UTF-16 code units
+        assert_eq!(doc.position(16), position(0, 13));
+        assert_eq!(doc.offset(position(0, 13)), 16);
+        assert_eq!(doc.position(18), position(1, 0));
+        assert_eq!(doc.offset(position(1, 8)), 26);
+        // past the end of a line or the document
+        assert_eq!(doc.offset(position(0, 99)), 17);
+        assert_eq!(doc.offset(position(9, 0)), 28);
+    }
+
+    /// Talks to `monkey lsp` the way an editor would.
+    struct Client {
+        child: Child,
+        stdin: ChildStdin,
+        stdout: BufReader<ChildStdout>,
+        next_id: i64,
+    }
+
+    impl Client {
+        fn start() -> Client {
+            let mut child = Command::new(env!("CARGO_BIN_EXE_monkey"))
+                .arg("lsp")
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()
+                .unwrap();
+            Client {
+                stdin: child.stdin.take().unwrap(),
+                stdout: BufReader::new(child.stdout.take().unwrap()),
+                child,
+                next_id: 1,
+            }
+        }
+
+        fn send(&mut self, message: Json) {
+            let body = message.to_string();
+            write!(self.stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+            self.stdin.flush().unwrap();
+        }
+
+        fn receive(&mut self) -> Json {
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                self.stdout.read_line(&mut line).unwrap();
+                match line.trim_end().split_once(": ") {
+                    Some(("Content-Length", value)) => length = value.parse().unwrap(),
+                    _ => break,
+                }
+            }
+            let mut body = vec![0; length];
+            self.stdout.read_exact(&mut body).unwrap();
+            serde_json::from_slice(&body).unwrap()
+        }
+
+        fn notify(&mut self, method: &str, params: Json) {
+            self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+        }
+
+        /// Send a request and wait for its response, which is returned
+        /// whole so errors can be checked too.
+        fn request(&mut self, method: &str, params: Json) -> Json {
+            let id = self.next_id;
+            self.next_id += 1;
+            self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+            let response = self.receive();
+            assert_eq!(response["id"], id);
+            response
+        }
+    }
+
+    fn at(line: u32, character: u32) -> Json {
+        json!({
+            "textDocument": { "uri": "file:///main.mk" },
+            "position": { "line": line, "character": character },
+        })
+    }
+
+    #[test]
+    fn test_server() {
+        let mut client = Client::start();
+        let init = client.request("initialize", json!({ "capabilities": {} }));
+        let capabilities = &init["result"]["capabilities"];
+        assert_eq!(capabilities["hoverProvider"], true);
+        assert_eq!(capabilities["textDocumentSync"], 1);
+        client.notify("initialized", json!({}));
+
+        let document = json!({ "uri": "file:///main.mk", "languageId": "monkey", "version": 1, "text": "let x = ;" });
+        client.notify("textDocument/didOpen", json!({ "textDocument": document }));
+        let published = client.receive();
+        assert_eq!(published["method"], "textDocument/publishDiagnostics");
+        let diagnostics = &published["params"]["diagnostics"];
+        assert_eq!(
+            diagnostics[0]["message"],
+            "no prefix parse function for SEMICOLON found"
+        );
+        assert_eq!(
+            diagnostics[0]["range"],
+            json!({ "start": { "line": 0, "character": 8 }, "end": { "line": 0, "character": 9 } })
+        );
+
+        client.notify(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": "file:///main.mk", "version": 2 },
+                "contentChanges": [{ "text": SOURCE }],
+            }),
+        );
+        let published = client.receive();
+        assert_eq!(published["params"]["diagnostics"], json!([]));
+
+        let definition = client.request("textDocument/definition", at(4, 8));
+        assert_eq!(
+            definition["result"],
+            json!({
+                "uri": "file:///main.mk",
+                "range": { "start": { "line": 0, "character": 4 }, "end": { "line": 0, "character": 7 } },
+            })
+        );
+
+        let mut params = at(0, 5);
+        params["context"] = json!({ "includeDeclaration": true });
+        let references = client.request("textDocument/references", params);
+        let lines: Vec<&Json> = references["result"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|location| &location["range"]["start"]["line"])
+            .collect();
+        assert_eq!(lines, [0, 4]);
+
+        let hover = client.request("textDocument/hover", at(5, 6));
+        assert_eq!(hover["result"]["contents"]["kind"], "markdown");
+        assert_eq!(
+            hover["result"]["contents"]["value"],
+            "```monkey\nlet x = add(1, 2)\n```\n`int`"
+        );
+
+        let symbols = client.request(
+            "textDocument/documentSymbol",
+            json!({ "textDocument": { "uri": "file:///main.mk" } }),
+        );
+        assert_eq!(symbols["result"][0]["name"], "add");
+        assert_eq!(symbols["result"][0]["kind"], 12);
+        assert_eq!(symbols["result"][0]["children"][0]["name"], "sum");
+        assert_eq!(symbols["result"][1]["name"], "x");
+
+        let formatting = json!({
+            "textDocument": { "uri": "file:///main.mk" },
+            "options": { "tabSize": 4, "insertSpaces": true },
+        });
+        let edits = client.request("textDocument/formatting", formatting.clone());
+        assert_eq!(edits["result"], json!([]));
+        client.notify(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": "file:///main.mk", "version": 3 },
+                "contentChanges": [{ "text": "let  x=1" }],
+            }),
+        );
+        let published = client.receive();
+        assert_eq!(
+            published["params"]["diagnostics"][0]["code"],
+            "unused-binding"
+        );
+        let edits = client.request("textDocument/formatting", formatting);
+        assert_eq!(edits["result"][0]["newText"], "let x = 1;\n");
+
+        let unknown = client.request("textDocument/rename", at(0, 0));
+        assert_eq!(unknown["error"]["code"], -32601);
+        let unopened = client.request(
+            "textDocument/documentSymbol",
+            json!({ "textDocument": { "uri": "file:///other.mk" } }),
+        );
+        assert_eq!(unopened["error"]["code"], -32602);
+
+        client.notify(
+            "textDocument/didClose",
+            json!({ "textDocument": { "uri": "file:///main.mk" } }),
+        );
+        assert_eq!(client.receive()["params"]["diagnostics"], json!([]));
+
+        let shutdown = client.request("shutdown", Json::Null);
+        assert_eq!(shutdown["result"], Json::Null);
+        client.notify("exit", Json::Null);
+        assert!(client.child.wait().unwrap().success());
+    }
+}
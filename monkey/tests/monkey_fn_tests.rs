@@ -0,0 +1,107 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::runtime_error;
+    use monkey::{monkey_fn, Arity, Interpreter, Session, Value};
+
+    /// Add two integers.
+    ///
+    /// Wraps around on overflow.
+    #[monkey_fn]
+    fn add(a: i64, b: i64) -> i64 {
+        a.wrapping_add(b)
+    }
+
+    #[monkey_fn(name = "div")]
+    fn checked_div(a: i64, b: i64) -> Result<i64, String> {
+        a.checked_div(b)
+            .ok_or_else(|| format!("cannot divide {} by {}", a, b))
+    }
+
+    #[monkey_fn]
+    fn shout(words: Vec<String>) -> String {
+        words.join(" ").to_uppercase()
+    }
+
+    #[test]
+    fn test_generated_builtin() {
+        let builtin = add_builtin();
+        assert_eq!(builtin.name, "add");
+        assert_eq!(builtin.arity, Arity::Exact(2));
+        assert_eq!(
+            builtin.doc,
+            "Add two integers.\n\nWraps around on overflow."
+        );
+        assert_eq!(checked_div_builtin().name, "div");
+        assert_eq!(checked_div_builtin().doc, "");
+
+        // the original function is still callable from Rust
+        assert_eq!(add(1, 2), 3);
+    }
+
+    #[test]
+    fn test_call_from_script() {
+        let mut interpreter = Interpreter::new();
+        interpreter.register_builtin(add_builtin());
+        interpreter.register_builtin(checked_div_builtin());
+        interpreter.register_builtin(shout_builtin());
+
+        assert_eq!(
+            interpreter.eval_str("add(2, 3)").unwrap(),
+            Value::Integer(5)
+        );
+        assert_eq!(
+            interpreter.eval_str("div(7, 2)").unwrap(),
+            Value::Integer(3)
+        );
+        assert_eq!(
+            interpreter.eval_str("shout([\"hi\", \"there\"])").unwrap(),
+            Value::String("HI THERE".to_string())
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut interpreter = Interpreter::new();
+        interpreter.register_builtin(add_builtin());
+        interpreter.register_builtin(checked_div_builtin());
+
+        assert_eq!(
+            runtime_error(&mut interpreter, "add(1)").message,
+            "wrong number of arguments to `add`: want=2, got=1"
+        );
+        assert_eq!(
+            runtime_error(&mut interpreter, "add(1, true)").message,
+            "argument to `add` must be INTEGER, got BOOLEAN"
+        );
+        assert_eq!(
+            runtime_error(&mut interpreter, "div(1, 0)").message,
+            "cannot divide 1 by 0"
+        );
+    }
+
+    #[test]
+    fn test_doc_command() {
+        let mut session = Session::new();
+        session.interpreter.register_builtin(add_builtin());
+        session.interpreter.register_builtin(checked_div_builtin());
+
+        assert_eq!(
+            session.execute(":doc add"),
+            Ok("Add two integers.\n\nWraps around on overflow.".to_string())
+        );
+        assert_eq!(
+            session.execute(":doc div"),
+            Ok("div has no documentation".to_string())
+        );
+        assert!(session
+            .execute(":doc len")
+            .unwrap()
+            .starts_with("Number of"));
+        assert_eq!(
+            session.execute(":doc nope"),
+            Err("no builtin named nope".to_string())
+        );
+    }
+}
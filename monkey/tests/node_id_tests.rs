@@ -0,0 +1,107 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::parse;
+    use monkey::{Expression, NodeId, NodeRef};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_nodes_are_numbered_in_preorder() {
+        let program = parse("let x = 1 + y; f(x);");
+        let nodes: Vec<String> = (0..program.node_count() as u32)
+            .map(|id| program.node(NodeId(id)).unwrap().to_string())
+            .collect();
+        assert_eq!(
+            nodes,
+            [
+                "let x = (1 + y);",
+                "x",
+                "(1 + y)",
+                "1",
+                "y",
+                "f(x)",
+                "f(x)",
+                "f",
+                "x"
+            ]
+        );
+        assert_eq!(program.node(NodeId(9)), None);
+        assert_eq!(program.node(NodeId::DUMMY), None);
+    }
+
+    #[test]
+    fn test_parent_links() {
+        let program = parse("let f = fn(a) { if (a) { a } };");
+        let parents: Vec<Option<u32>> = (0..program.node_count() as u32)
+            .map(|id| program.parent(NodeId(id)).map(|parent| parent.0))
+            .collect();
+        // let, f, fn, a, body, expression statement, if, a, block,
+        // expression statement, a
+        assert_eq!(
+            parents,
+            [
+                None,
+                Some(0),
+                Some(0),
+                Some(2),
+                Some(2),
+                Some(4),
+                Some(5),
+                Some(6),
+                Some(6),
+                Some(8),
+                Some(9),
+            ]
+        );
+        assert!(matches!(
+            program.node(NodeId(3)),
+            Some(NodeRef::Identifier(param)) if param.value == "a"
+        ));
+        assert!(matches!(program.node(NodeId(8)), Some(NodeRef::Block(_))));
+    }
+
+    #[test]
+    fn test_ids_are_stable_and_key_side_tables() {
+        let input = "let a = 1; let b = a * 2; b - a";
+        let first = parse(input);
+        let second = parse(input);
+
+        // record where each identifier is used
+        let mut uses: HashMap<NodeId, String> = HashMap::new();
+        for id in (0..first.node_count() as u32).map(NodeId) {
+            if let Some(NodeRef::Expression(Expression::Identifier(ident))) = first.node(id) {
+                uses.insert(id, ident.value.clone());
+            }
+        }
+        assert_eq!(uses.len(), 3);
+        for (id, name) in &uses {
+            let Some(NodeRef::Expression(exp)) = second.node(*id) else {
+                panic!("{} is not an expression", id);
+            };
+            assert_eq!(&exp.to_string(), name);
+            assert_eq!(exp.id(), *id);
+        }
+    }
+
+    #[test]
+    fn test_index() {
+        let program = parse("let f = fn(x) { if (x) { [x, 1] } }; f(2)[0]");
+        let index = program.index();
+        for id in (0..program.node_count() as u32).map(NodeId) {
+            assert_eq!(index.get(id), program.node(id), "{}", id);
+        }
+        assert_eq!(index.get(NodeId(program.node_count() as u32)), None);
+        assert_eq!(index.get(NodeId::DUMMY), None);
+    }
+
+    #[test]
+    fn test_renumber_after_rewrite() {
+        let mut program = parse("1; 2;");
+        program.statements.remove(0);
+        program.renumber();
+        assert_eq!(program.node_count(), 2);
+        assert_eq!(program.statements[0].id(), NodeId(0));
+        assert_eq!(program.node(NodeId(1)).unwrap().to_string(), "2");
+    }
+}
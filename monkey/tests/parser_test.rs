@@ -1,10 +1,7 @@
 #[cfg(test)]
 mod tests {
 
-    use monkey::{
-        parser::parser::Parser, Expression, ExpressionStatement, Identifier, InfixExpression,
-        IntegerLiteral, LetStatement, Lexer, Node, PrefixExpression, ReturnStatement, Statement,
-    };
+    use monkey::{parser::parser::Parser, Expression, Lexer, Node, Statement};
 
     #[test]
     fn test_let_statements() {
@@ -25,7 +22,7 @@ mod tests {
                 panic!("hope 3 statements ,get {}", statelen)
             }
 
-            let tests = vec!["x", "y", "foo"];
+            let tests = ["x", "y", "foo"];
             for (key, test) in tests.iter().enumerate() {
                 let stmt = &prog.statements[key];
                 assert!(test_let_statement(stmt, test.to_string()));
@@ -57,10 +54,9 @@ mod tests {
             );
 
             for stmt in &prog.statements {
-                let return_stmt = stmt
-                    .as_any()
-                    .downcast_ref::<ReturnStatement>()
-                    .expect("stmt not ReturnStatement");
+                let Statement::Return(return_stmt) = stmt else {
+                    panic!("stmt not ReturnStatement");
+                };
                 assert_eq!(
                     return_stmt.token_literal(),
                     "return",
@@ -89,18 +85,13 @@ mod tests {
                 prog.statements.len()
             );
             for stmt in &prog.statements {
-                let ident_stmt = stmt
-                    .as_any()
-                    .downcast_ref::<ExpressionStatement>()
-                    .expect("stmt not ExpressionStatement");
-
-                let ident = ident_stmt
-                    .expression
-                    .as_ref()
-                    .unwrap()
-                    .as_any()
-                    .downcast_ref::<Identifier>()
-                    .expect("ident_stmt not Identifier");
+                let Statement::Expression(ident_stmt) = stmt else {
+                    panic!("stmt not ExpressionStatement");
+                };
+
+                let Expression::Identifier(ident) = &ident_stmt.expression else {
+                    panic!("ident_stmt not Identifier");
+                };
 
                 assert_eq!(ident.token_literal(), "foobar");
                 assert_eq!(ident.value, "foobar");
@@ -126,16 +117,9 @@ mod tests {
                 prog.statements.len()
             );
             for stmt in &prog.statements {
-                let int_stmt = stmt
-                    .as_any()
-                    .downcast_ref::<ExpressionStatement>()
-                    .unwrap()
-                    .expression
-                    .as_ref()
-                    .unwrap()
-                    .as_any()
-                    .downcast_ref::<IntegerLiteral>()
-                    .expect("int_stmt not IntegerLiteral");
+                let Expression::Integer(int_stmt) = expression(stmt) else {
+                    panic!("int_stmt not IntegerLiteral");
+                };
 
                 assert_eq!(int_stmt.token_literal(), "5");
                 assert_eq!(int_stmt.value, 5);
@@ -143,6 +127,82 @@ mod tests {
         }
     }
 
+    #[test]
+    #[cfg(not(feature = "bigint"))]
+    fn test_integer_literal_too_large() {
+        let input = "9223372036854775808;".to_string();
+
+        let mut l = Lexer::new(input);
+        let mut p = Parser::new(&mut l);
+        p.parse_program();
+
+        assert_eq!(
+            p.errors(),
+            ["integer literal 9223372036854775808 is too large, the maximum is 9223372036854775807"]
+        );
+    }
+
+    #[test]
+    fn test_unclosed_block() {
+        for input in [
+            "if (true) { 1",
+            "let f = fn(x) { x + 1",
+            "try { 1 } catch (e) {",
+        ] {
+            let mut l = Lexer::new(input.to_string());
+            let mut p = Parser::new(&mut l);
+            p.parse_program();
+
+            assert_eq!(p.errors(), ["expected `}`, got EOF instead"], "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_unterminated_string() {
+        let mut l = Lexer::new("let s = \"abc".to_string());
+        let mut p = Parser::new(&mut l);
+        p.parse_program();
+
+        assert_eq!(p.errors(), ["unterminated string"]);
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        let deep = |open: &str, close: &str, depth| {
+            format!("{}1{}", open.repeat(depth), close.repeat(depth))
+        };
+        for (input, ok) in [
+            (deep("(", ")", 255), true),
+            (deep("(", ")", 256), false),
+            (deep("-", "", 300), false),
+            (deep("[", "]", 300), false),
+            (deep("fn() { ", " }", 300), false),
+            // chains of operators, calls and indexes don't nest
+            (format!("1{}", " + 1".repeat(1000)), true),
+            (format!("f{}", "(1)".repeat(1000)), true),
+            (format!("a{}", "[0]".repeat(1000)), true),
+            (
+                format!("let x: {}int{} = 1;", "[".repeat(300), "]".repeat(300)),
+                false,
+            ),
+        ] {
+            let mut l = Lexer::new(input.clone());
+            let mut p = Parser::new(&mut l);
+            p.parse_program();
+
+            if ok {
+                assert!(p.errors().is_empty(), "{:?}", p.errors());
+            } else {
+                assert_eq!(
+                    p.errors(),
+                    ["expressions are nested too deeply, the maximum is 256"],
+                    "{}",
+                    input
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_parsing_infix_expressions() {
         let infix_tests = [
@@ -171,18 +231,11 @@ mod tests {
                     prog.statements.len()
                 );
                 for stmt in &prog.statements {
-                    let exp = stmt
-                        .as_any()
-                        .downcast_ref::<ExpressionStatement>()
-                        .unwrap()
-                        .expression
-                        .as_ref()
-                        .unwrap()
-                        .as_any()
-                        .downcast_ref::<InfixExpression>()
-                        .expect("exp not InfixExpression");
-
-                    if !test_integer_literal(exp.left.as_ref().map(|x| x.as_ref()).unwrap(), tt.1) {
+                    let Expression::Infix(exp) = expression(stmt) else {
+                        panic!("exp not InfixExpression");
+                    };
+
+                    if !test_integer_literal(&exp.left, tt.1) {
                         return;
                     }
 
@@ -192,8 +245,7 @@ mod tests {
                         tt.2, exp.operator
                     );
 
-                    if !test_integer_literal(exp.right.as_ref().map(|x| x.as_ref()).unwrap(), tt.3)
-                    {
+                    if !test_integer_literal(&exp.right, tt.3) {
                         return;
                     }
                 }
@@ -220,29 +272,274 @@ mod tests {
                     prog.statements.len()
                 );
                 for stmt in &prog.statements {
-                    let prefix_stmt = stmt
-                        .as_any()
-                        .downcast_ref::<ExpressionStatement>()
-                        .unwrap()
-                        .expression
-                        .as_ref()
-                        .unwrap()
-                        .as_any()
-                        .downcast_ref::<PrefixExpression>()
-                        .expect("prefix_stmt not PrefixExpression");
+                    let Expression::Prefix(prefix_stmt) = expression(stmt) else {
+                        panic!("prefix_stmt not PrefixExpression");
+                    };
 
                     assert_eq!(prefix_stmt.operator, tt.1);
-                    assert!(test_integer_literal(
-                        prefix_stmt.right.as_ref().map(|x| x.as_ref()).unwrap(),
-                        tt.2,
-                    ));
+                    assert!(test_integer_literal(&prefix_stmt.right, tt.2,));
                 }
             }
         }
     }
 
-    fn test_integer_literal(il: &dyn Expression, value: i64) -> bool {
-        if let Some(integ) = il.as_any().downcast_ref::<IntegerLiteral>() {
+    #[test]
+    fn test_operator_precedence_parsing() {
+        let tests = [
+            ("-a * b", "((-a) * b)"),
+            ("!-a", "(!(-a))"),
+            ("a * b * c", "((a * b) * c)"),
+            ("a + b + c", "((a + b) + c)"),
+            ("a + b * c + d / e - f", "(((a + (b * c)) + (d / e)) - f)"),
+            ("5 > 4 == 3 < 4", "((5 > 4) == (3 < 4))"),
+            ("3 < 5 == true", "((3 < 5) == true)"),
+            ("1 + (2 + 3) + 4", "((1 + (2 + 3)) + 4)"),
+            ("(5 + 5) * 2", "((5 + 5) * 2)"),
+            ("-(5 + 5)", "(-(5 + 5))"),
+            ("!(true == true)", "(!(true == true))"),
+            ("a * add(b * c) * d", "((a * add((b * c))) * d)"),
+            ("add(a, b, 1, 2 * 3)", "add(a, b, 1, (2 * 3))"),
+            (
+                "a * [1, 2, 3, 4][b * c] * d",
+                "((a * ([1, 2, 3, 4][(b * c)])) * d)",
+            ),
+            (
+                "add(a * b[2], b[1], 2 * [1, 2][1])",
+                "add((a * (b[2])), (b[1]), (2 * ([1, 2][1])))",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut l = Lexer::new(input.to_string());
+            let mut p = Parser::new(&mut l);
+
+            let program = p.parse_program();
+            check_parser_error(p);
+            assert_eq!(program.unwrap().to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_if_else_expression() {
+        let input = "if (x < y) { x } else { y }".to_string();
+
+        let mut l = Lexer::new(input);
+        let mut p = Parser::new(&mut l);
+
+        let program = p.parse_program().unwrap();
+        check_parser_error(p);
+
+        assert_eq!(program.statements.len(), 1);
+        let Expression::If(exp) = expression(&program.statements[0]) else {
+            panic!("exp not IfExpression");
+        };
+
+        assert_eq!(exp.condition.to_string(), "(x < y)");
+        assert_eq!(exp.consequence.to_string(), "x");
+        assert_eq!(exp.alternative.as_ref().unwrap().to_string(), "y");
+    }
+
+    #[test]
+    fn test_try_catch_expression() {
+        let input = "try { risky(x) } catch (err) { err[\"message\"] }".to_string();
+
+        let mut l = Lexer::new(input);
+        let mut p = Parser::new(&mut l);
+
+        let program = p.parse_program().unwrap();
+        check_parser_error(p);
+
+        assert_eq!(program.statements.len(), 1);
+        let Expression::Try(exp) = expression(&program.statements[0]) else {
+            panic!("exp not TryExpression");
+        };
+
+        assert_eq!(exp.body.to_string(), "risky(x)");
+        assert_eq!(exp.parameter.value, "err");
+        assert_eq!(exp.handler.to_string(), "(err[message])");
+    }
+
+    #[test]
+    fn test_throw_statement() {
+        let input = "throw \"bad\" + x;".to_string();
+
+        let mut l = Lexer::new(input);
+        let mut p = Parser::new(&mut l);
+
+        let program = p.parse_program().unwrap();
+        check_parser_error(p);
+
+        let Statement::Throw(stmt) = &program.statements[0] else {
+            panic!("stmt not ThrowStatement");
+        };
+        assert_eq!(stmt.token_literal(), "throw");
+        assert_eq!(stmt.to_string(), "throw (bad + x);");
+    }
+
+    #[test]
+    fn test_function_literal_parsing() {
+        let input = "fn(x, y) { x + y; }".to_string();
+
+        let mut l = Lexer::new(input);
+        let mut p = Parser::new(&mut l);
+
+        let program = p.parse_program().unwrap();
+        check_parser_error(p);
+
+        let Expression::Function(function) = expression(&program.statements[0]) else {
+            panic!("exp not FunctionLiteral");
+        };
+
+        let params: Vec<&str> = function
+            .parameters
+            .iter()
+            .map(|p| p.value.as_str())
+            .collect();
+        assert_eq!(params, ["x", "y"]);
+        assert_eq!(function.body.to_string(), "(x + y)");
+    }
+
+    #[test]
+    fn test_type_annotations() {
+        let input = "let x: [int] = []; fn(a: string, b) -> {string: fn(int) -> bool} { b }";
+
+        let mut l = Lexer::new(input.to_string());
+        let mut p = Parser::new(&mut l);
+
+        let program = p.parse_program().unwrap();
+        check_parser_error(p);
+
+        let Statement::Let(stmt) = &program.statements[0] else {
+            panic!("stmt not LetStatement");
+        };
+        let annotation = stmt.name.annotation.as_ref().unwrap();
+        assert_eq!(annotation.to_string(), "[int]");
+        assert_eq!(&input[annotation.span.start..annotation.span.end], "[int]");
+        assert_eq!(stmt.to_string(), "let x: [int] = [];");
+
+        let Expression::Function(function) = expression(&program.statements[1]) else {
+            panic!("exp not FunctionLiteral");
+        };
+        let annotations: Vec<Option<String>> = function
+            .parameters
+            .iter()
+            .map(|p| p.annotation.as_ref().map(|a| a.to_string()))
+            .collect();
+        assert_eq!(annotations, [Some("string".to_string()), None]);
+        let return_type = function.return_type.as_ref().unwrap();
+        assert_eq!(return_type.to_string(), "{string: fn(int) -> bool}");
+        assert_eq!(
+            function.to_string(),
+            "fn(a: string, b) -> {string: fn(int) -> bool} b"
+        );
+
+        let mut l = Lexer::new("fn(a) -> 5 { a }".to_string());
+        let mut p = Parser::new(&mut l);
+        p.parse_program().unwrap();
+        assert_eq!(p.errors()[0], "expected a type, got INT instead");
+    }
+
+    #[test]
+    fn test_call_expression_parsing() {
+        let input = "add(1, 2 * 3, 4 + 5);".to_string();
+
+        let mut l = Lexer::new(input);
+        let mut p = Parser::new(&mut l);
+
+        let program = p.parse_program().unwrap();
+        check_parser_error(p);
+
+        let Expression::Call(call) = expression(&program.statements[0]) else {
+            panic!("exp not CallExpression");
+        };
+
+        assert_eq!(call.function.to_string(), "add");
+        assert_eq!(call.arguments.len(), 3);
+        assert!(test_integer_literal(&call.arguments[0], 1));
+        assert_eq!(call.arguments[1].to_string(), "(2 * 3)");
+        assert_eq!(call.arguments[2].to_string(), "(4 + 5)");
+    }
+
+    #[test]
+    fn test_string_literal_expression() {
+        let mut l = Lexer::new(r#""hello world";"#.to_string());
+        let mut p = Parser::new(&mut l);
+
+        let program = p.parse_program().unwrap();
+        check_parser_error(p);
+
+        let Expression::String(literal) = expression(&program.statements[0]) else {
+            panic!("exp not StringLiteral");
+        };
+        assert_eq!(literal.value, "hello world");
+    }
+
+    #[test]
+    fn test_parsing_array_literals() {
+        let mut l = Lexer::new("[1, 2 * 2, 3 + 3]".to_string());
+        let mut p = Parser::new(&mut l);
+
+        let program = p.parse_program().unwrap();
+        check_parser_error(p);
+
+        let Expression::Array(array) = expression(&program.statements[0]) else {
+            panic!("exp not ArrayLiteral");
+        };
+        assert_eq!(array.elements.len(), 3);
+        assert!(test_integer_literal(&array.elements[0], 1));
+        assert_eq!(array.elements[1].to_string(), "(2 * 2)");
+        assert_eq!(array.elements[2].to_string(), "(3 + 3)");
+    }
+
+    #[test]
+    fn test_parsing_index_expressions() {
+        let mut l = Lexer::new("myArray[1 + 1]".to_string());
+        let mut p = Parser::new(&mut l);
+
+        let program = p.parse_program().unwrap();
+        check_parser_error(p);
+
+        let Expression::Index(index) = expression(&program.statements[0]) else {
+            panic!("exp not IndexExpression");
+        };
+        assert_eq!(index.left.to_string(), "myArray");
+        assert_eq!(index.index.to_string(), "(1 + 1)");
+    }
+
+    #[test]
+    fn test_parsing_hash_literals() {
+        let tests = [
+            (r#"{"one": 1, "two": 2}"#, "{one:1, two:2}"),
+            ("{}", "{}"),
+            (
+                r#"{"one": 0 + 1, true: 10 - 8}"#,
+                "{one:(0 + 1), true:(10 - 8)}",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut l = Lexer::new(input.to_string());
+            let mut p = Parser::new(&mut l);
+
+            let program = p.parse_program().unwrap();
+            check_parser_error(p);
+
+            let Expression::Hash(hash) = expression(&program.statements[0]) else {
+                panic!("exp not HashLiteral");
+            };
+            assert_eq!(hash.to_string(), expected);
+        }
+    }
+
+    fn expression(stmt: &Statement) -> &Expression {
+        match stmt {
+            Statement::Expression(stmt) => &stmt.expression,
+            other => panic!("stmt not ExpressionStatement. got={:?}", other),
+        }
+    }
+
+    fn test_integer_literal(il: &Expression, value: i64) -> bool {
+        if let Expression::Integer(integ) = il {
             if integ.value != value {
                 eprintln!("integ.value not {}. got={}", value, integ.value);
                 return false;
@@ -269,41 +566,34 @@ mod tests {
             return;
         }
         for msg in errors {
-            panic!("parser error: {:?}", msg);
+            eprintln!("parser error: {:?}", msg);
         }
         panic!("parser has {} errors", errors.len());
     }
 
-    fn test_let_statement<S>(s: &Box<S>, name: String) -> bool
-    where
-        S: Statement + ?Sized,
-    {
+    fn test_let_statement(s: &Statement, name: String) -> bool {
         if s.token_literal() != "let" {
             eprintln!("s.TokenLiteral not 'let'. got={}", s.token_literal());
             return false;
         }
-        let let_stmt = match s.as_any().downcast_ref::<LetStatement>() {
-            Some(stmt) => stmt,
-            None => {
-                eprintln!("s not LetStatement. got={}", std::any::type_name::<S>());
-                return false;
-            }
+        let Statement::Let(let_stmt) = s else {
+            eprintln!("s not LetStatement. got={:?}", s);
+            return false;
         };
 
-        if let Some(ident) = let_stmt.name.as_ref().map(|ident| ident) {
-            if ident.value != name {
-                eprintln!("letStmt.Name.Value not '{}'. got={}", name, ident.value);
-                return false;
-            }
+        let ident = &let_stmt.name;
+        if ident.value != name {
+            eprintln!("letStmt.Name.Value not '{}'. got={}", name, ident.value);
+            return false;
+        }
 
-            if ident.token_literal() != name {
-                eprintln!(
-                    "letStmt.Name.TokenLiteral() not '{}'. got={}",
-                    name,
-                    ident.token_literal()
-                );
-                return false;
-            }
+        if ident.token_literal() != name {
+            eprintln!(
+                "letStmt.Name.TokenLiteral() not '{}'. got={}",
+                name,
+                ident.token_literal()
+            );
+            return false;
         }
 
         true
@@ -0,0 +1,168 @@
+#[cfg(test)]
+mod tests {
+    use colored::Colorize;
+    use monkey::{highlight, is_incomplete, Command, ReplHelper, Session};
+
+    #[test]
+    fn test_is_incomplete() {
+        let tests = [
+            ("let x = 5;", false),
+            ("let add = fn(x, y) {", true),
+            ("let add = fn(x, y) {\n x + y;", true),
+            ("let add = fn(x, y) {\n x + y;\n};", false),
+            ("add(1,", true),
+            ("add(1,\n 2)", false),
+            ("fn(x) { if (x) {", true),
+            ("[1, 2,", true),
+            ("[1, 2,\n 3]", false),
+            ("}", false),
+            ("", false),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(
+                is_incomplete(input),
+                expected,
+                "is_incomplete({:?}) wrong",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_session_eval() {
+        let mut session = Session::new();
+
+        assert_eq!(session.execute("let a = 5;"), Ok(String::new()));
+        assert_eq!(session.execute("a * 2"), Ok("10".to_string()));
+        assert_eq!(
+            session.execute("a + b"),
+            Err("error: identifier not found: b\n --> 1:5\n  |\n1 | a + b\n  |     ^".to_string())
+        );
+    }
+
+    #[test]
+    fn test_session_commands() {
+        let mut session = Session::new();
+        session.execute("let b = true; let a = 1;").unwrap();
+
+        assert_eq!(session.execute(":env"), Ok("a = 1\nb = true".to_string()));
+        assert_eq!(session.execute(":type a + 1"), Ok("INTEGER".to_string()));
+        assert_eq!(
+            session.execute(":type fn(x) { x }"),
+            Ok("FUNCTION".to_string())
+        );
+        assert!(session
+            .execute(":time a + 1")
+            .unwrap()
+            .starts_with("2\ntime: "));
+        assert_eq!(
+            session.execute(":reset"),
+            Ok("environment cleared".to_string())
+        );
+        assert_eq!(session.execute(":env"), Ok(String::new()));
+        assert!(session.execute(":help").unwrap().contains(":load <file>"));
+        assert_eq!(
+            session.execute(":nope"),
+            Err("unknown command :nope, try :help".to_string())
+        );
+    }
+
+    #[test]
+    fn test_session_load() {
+        let path = std::env::temp_dir().join("monkey_repl_load_test.mk");
+        std::fs::write(&path, "let double = fn(x) {\n  x * 2\n};\n").unwrap();
+
+        let mut session = Session::new();
+        let output = session.execute(&format!(":load {}", path.display()));
+        assert_eq!(output, Ok(format!("loaded {}", path.display())));
+        assert_eq!(session.execute("double(21)"), Ok("42".to_string()));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_register_command() {
+        fn answer(_: &mut Session, _: &str) -> Result<String, String> {
+            Ok("42".to_string())
+        }
+
+        let mut session = Session::new();
+        session.register(Command {
+            name: "answer",
+            args: "",
+            help: "print the answer",
+            run: answer,
+        });
+        assert_eq!(session.execute(":answer"), Ok("42".to_string()));
+    }
+
+    #[test]
+    fn test_completions() {
+        let mut session = Session::new();
+        session.execute("let length = 1; let lettuce = 2;").unwrap();
+        let helper = ReplHelper::new(
+            session.interpreter.env().clone(),
+            session.interpreter.evaluator.builtins.names(),
+        );
+
+        assert_eq!(
+            helper.completions("le", 2),
+            (
+                0,
+                vec![
+                    "len".to_string(),
+                    "length".to_string(),
+                    "let".to_string(),
+                    "lettuce".to_string()
+                ]
+            )
+        );
+        assert_eq!(
+            helper.completions("1 + ret", 7),
+            (4, vec!["return".to_string()])
+        );
+        assert_eq!(
+            helper.completions("x(fa", 4),
+            (2, vec!["false".to_string()])
+        );
+        assert_eq!(helper.completions("length + ", 9), (9, vec![]));
+
+        session.execute(":reset").unwrap();
+        assert_eq!(helper.completions("len", 3), (0, vec!["len".to_string()]));
+        assert_eq!(
+            helper.completions("p", 1),
+            (0, vec!["push".to_string(), "puts".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_highlight() {
+        colored::control::set_override(true);
+
+        let highlighted = highlight("let x = 5 @ true;");
+        assert_eq!(
+            highlighted,
+            format!(
+                "{} x {} {} {} {};",
+                "let".magenta().bold(),
+                "=".bright_white(),
+                "5".cyan(),
+                "@".red().bold(),
+                "true".yellow()
+            )
+        );
+
+        assert_eq!(
+            highlight("x // note"),
+            format!("{} {}", "x".normal(), "// note".bright_black())
+        );
+
+        // a string still being typed is shown as one
+        assert_eq!(highlight("\"ab"), format!("{}", "\"ab".green()));
+
+        // whitespace and non-ascii input survive untouched
+        colored::control::set_override(false);
+        assert_eq!(highlight("  fn(é)\n{ }  "), "  fn(é)\n{ }  ");
+    }
+}
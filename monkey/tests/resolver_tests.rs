@@ -0,0 +1,221 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::parse;
+    use monkey::{
+        annotate, resolve, BindingKind, Builtins, Expression, Identifier, Resolution, Severity,
+        Slot, Visitor,
+    };
+
+    /// Each use in the program as `name@offset -> binding offset`, or
+    /// `name@offset -> ?` if nothing binds it.
+    fn uses(input: &str) -> Vec<String> {
+        let program = parse(input);
+        let resolution = resolve(&program);
+        let mut uses: Vec<(usize, String)> = Vec::new();
+        for (i, binding) in resolution.bindings().iter().enumerate() {
+            for &id in resolution.references(monkey::BindingId(i as u32)) {
+                let span = program.node(id).unwrap().span();
+                uses.push((
+                    span.start,
+                    format!("{}@{} -> {}", binding.name, span.start, binding.span.start),
+                ));
+            }
+        }
+        for unresolved in resolution.unresolved() {
+            let start = unresolved.span.start;
+            uses.push((start, format!("{}@{} -> ?", unresolved.name, start)));
+        }
+        uses.sort();
+        uses.into_iter().map(|(_, s)| s).collect()
+    }
+
+    #[test]
+    fn test_let_bindings() {
+        assert_eq!(
+            uses("let x = 1; let y = x + z; x"),
+            ["x@19 -> 4", "z@23 -> ?", "x@26 -> 4"]
+        );
+        // a name is bound after its value is evaluated
+        assert_eq!(uses("let x = x;"), ["x@8 -> ?"]);
+        // a later `let` shadows an earlier one
+        assert_eq!(uses("let x = 1; let x = x; x"), ["x@19 -> 4", "x@22 -> 15"]);
+    }
+
+    #[test]
+    fn test_scopes() {
+        assert_eq!(
+            uses("let a = 1; fn(a, b) { a + b }; a"),
+            ["a@22 -> 14", "b@26 -> 17", "a@31 -> 4"]
+        );
+        // blocks don't open a scope
+        assert_eq!(uses("if (true) { let x = 1; }; x"), ["x@26 -> 16"]);
+        // a function's `let`s stay inside it
+        assert_eq!(uses("fn() { let x = 1; }; x"), ["x@21 -> ?"]);
+        assert_eq!(
+            uses("try { 1 } catch (e) { let m = e; m }; e"),
+            ["e@30 -> 17", "m@33 -> 26", "e@38 -> ?"]
+        );
+    }
+
+    #[test]
+    fn test_functions_see_later_bindings() {
+        // recursion
+        assert_eq!(uses("let f = fn(n) { f(n) };"), ["f@16 -> 4", "n@18 -> 11"]);
+        // mutual recursion
+        assert_eq!(
+            uses("let even = fn() { odd() }; let odd = fn() { even() };"),
+            ["odd@18 -> 31", "even@44 -> 4"]
+        );
+        // but not the function's own later bindings
+        assert_eq!(uses("fn() { x; let x = 1; }"), ["x@7 -> ?"]);
+        // nor bindings in a sibling function
+        assert_eq!(
+            uses("fn() { fn() { x } }; fn() { let x = 1 }"),
+            ["x@14 -> ?"]
+        );
+    }
+
+    #[test]
+    fn test_bindings() {
+        let program = parse("let f = fn(a) { try { a } catch (e) { e } };");
+        let resolution: Resolution = resolve(&program);
+        let kinds: Vec<(&str, BindingKind)> = resolution
+            .bindings()
+            .iter()
+            .map(|b| (b.name.as_str(), b.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("a", BindingKind::Parameter),
+                ("e", BindingKind::Catch),
+                ("f", BindingKind::Let),
+            ]
+        );
+        for binding in resolution.bindings() {
+            assert_eq!(
+                resolution.binding_of(binding.ident),
+                resolution
+                    .bindings()
+                    .iter()
+                    .position(|b| b == binding)
+                    .map(|i| monkey::BindingId(i as u32))
+            );
+            assert_eq!(program.node(binding.ident).unwrap().span(), binding.span);
+        }
+    }
+
+    /// The resolver's diagnostics, each as `severity@offset: message`
+    /// followed by its notes.
+    fn diagnostics(input: &str) -> Vec<String> {
+        let program = parse(input);
+        resolve(&program)
+            .diagnostics(&program, &Builtins::new())
+            .into_iter()
+            .map(|diagnostic| {
+                let mut line = format!(
+                    "{}@{}: {}",
+                    diagnostic.severity,
+                    diagnostic.span.unwrap().start,
+                    diagnostic.message
+                );
+                for note in &diagnostic.notes {
+                    line += &format!(" ({})", note);
+                }
+                line
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        assert!(diagnostics("let x = 1; puts(x);").is_empty());
+        assert_eq!(
+            diagnostics("puts(y); let y = 1; y"),
+            ["error@5: `y` is used before it is defined (`y` is defined at 1:14)"]
+        );
+        assert_eq!(
+            diagnostics("let f = f; f"),
+            ["error@8: `f` is used before it is defined (`f` is defined at 1:5)"]
+        );
+        assert_eq!(diagnostics("nope(1)"), ["error@0: `nope` is not defined"]);
+        // a later binding in another function doesn't count
+        assert_eq!(
+            diagnostics("fn() { x }; let g = fn() { let x = 1; x }; g"),
+            ["error@7: `x` is not defined"]
+        );
+        assert_eq!(
+            diagnostics("let x = 1;\nlet f = fn(x) { x }; f(x)"),
+            ["warning@22: `x` shadows an outer binding (the outer `x` is defined at 1:5)"]
+        );
+        assert_eq!(
+            diagnostics("let f = fn(a, _b) { 1 }; f(1, 2); let unused = 2;"),
+            [
+                "warning@11: `a` is never used (name it `_a` if that is intended)",
+                "warning@38: `unused` is never used (name it `_unused` if that is intended)",
+            ]
+        );
+        // binding a name again in the same scope isn't shadowing
+        assert_eq!(
+            diagnostics("let x = 1; let x = x + 1; x"),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_severities() {
+        let program = parse("let x = 1; y");
+        let diagnostics = resolve(&program).diagnostics(&program, &Builtins::new());
+        let severities: Vec<Severity> = diagnostics.iter().map(|d| d.severity).collect();
+        assert_eq!(severities, [Severity::Warning, Severity::Error]);
+    }
+
+    /// Each identifier in the program with the slot `annotate` gave it.
+    fn slots(input: &str) -> Vec<(String, Option<Slot>)> {
+        struct Slots(Vec<(String, Option<Slot>)>);
+
+        impl<'a> Visitor<'a> for Slots {
+            fn visit_expression(&mut self, exp: &'a Expression) {
+                if let Expression::Identifier(Identifier { value, slot, .. }) = exp {
+                    self.0.push((value.clone(), *slot));
+                }
+                monkey::walk_expression(self, exp);
+            }
+        }
+
+        let mut program = parse(input);
+        let resolution = resolve(&program);
+        annotate(&mut program, &resolution);
+        let mut slots = Slots(Vec::new());
+        slots.visit_program(&program);
+        slots.0
+    }
+
+    #[test]
+    fn test_slots() {
+        let slot = |depth| Some(Slot { depth });
+        assert_eq!(
+            slots("let a = 1; let b = 2; let f = fn(c) { fn(d) { a + b + c + d } }; puts(a)"),
+            [
+                ("a".to_string(), slot(2)),
+                ("b".to_string(), slot(2)),
+                ("c".to_string(), slot(1)),
+                ("d".to_string(), slot(0)),
+                ("puts".to_string(), None),
+                ("a".to_string(), slot(0)),
+            ]
+        );
+        // a catch opens a scope
+        assert_eq!(
+            slots("let x = 1; let x = x; try { x } catch (e) { e + x }"),
+            [
+                ("x".to_string(), slot(0)),
+                ("x".to_string(), slot(0)),
+                ("e".to_string(), slot(0)),
+                ("x".to_string(), slot(1)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,217 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::parse;
+    use monkey::{parse_syntax, GreenToken, Span, SyntaxKind, SyntaxNode, TokenType};
+
+    #[test]
+    fn test_round_trip() {
+        let tests = [
+            "",
+            "let x = 5;",
+            "  let   x=5 ;\n\n// trailing\n",
+            "// only a comment",
+            "let s = \"é\"; // é\r\nputs(s)\r\n",
+            "fn(a, b) { a + b }(1, 2)",
+            "let f = fn(a: [int] , b)->{string: any} { b };",
+            "try { throw 1 } catch (e) { e }",
+            "{\"a\": [1, 2][0], true: !-x,}",
+            "let s = \"unterminated",
+            "let = 5; ) if (",
+            "let x = fn(a, { b",
+        ];
+        for input in tests {
+            let tree = parse_syntax(input);
+            assert_eq!(tree.root().text(), input);
+            assert_eq!(tree.root().span().end, input.len());
+        }
+    }
+
+    #[test]
+    fn test_lowers_to_the_parser_ast() {
+        let tests = [
+            "let x = 5; return x; throw \"no\";",
+            "1 + 2 * 3 - -4 / (5 + 6) == 7 != !true < 8 > 9",
+            "let add = fn(a, b) { a + b }; add(1, add(2, 3));",
+            "if (x < y) { x } else { y }; if (x) { 1 }",
+            "try { f() } catch (err) { puts(err) }",
+            "[1, \"two\", [3]][1 + 1]; {\"a\": 1, 2: fn() {}}[\"a\"]",
+            "if (true) { let x = 1; x } // a block\n",
+            "let f = fn() {}; f()()",
+            "let s = \"a // not a comment\";",
+            "let x: int = 5; let f = fn(a: [int], b) -> {string: fn(int) -> bool} { b };",
+            "fn(g: fn() -> null) -> any { g() }",
+        ];
+        for input in tests {
+            let program = parse_syntax(input).to_program().unwrap();
+            assert_eq!(program, parse(input), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let tests = [
+            (
+                "let = 5;",
+                "expected next token to be IDENT, got ASSIGN instead",
+                Span::new(4, 5),
+            ),
+            (
+                "let x  5;",
+                "expected next token to be ASSIGN, got INT instead",
+                Span::new(7, 8),
+            ),
+            (
+                ")",
+                "no prefix parse function for RPAREN found",
+                Span::new(0, 1),
+            ),
+            (
+                "let x: = 5;",
+                "expected a type, got ASSIGN instead",
+                Span::new(7, 8),
+            ),
+            ("let s = \"abc", "unterminated string", Span::new(8, 12)),
+            (
+                "if (x) { 1",
+                "expected `}`, got EOF instead",
+                Span::new(10, 10),
+            ),
+            (
+                "let x =",
+                "no prefix parse function for EOF found",
+                Span::new(7, 7),
+            ),
+        ];
+        for (input, message, span) in tests {
+            let tree = parse_syntax(input);
+            let err = &tree.errors()[0];
+            assert_eq!(err.message, message, "{}", input);
+            assert_eq!(err.span, Some(span), "{}", input);
+            let messages: Vec<String> = tree.errors().iter().map(|e| e.message.clone()).collect();
+            assert_eq!(tree.to_program().unwrap_err(), messages);
+        }
+    }
+
+    #[cfg(not(feature = "bigint"))]
+    #[test]
+    fn test_integer_out_of_range() {
+        let input = "let x = 99999999999999999999; let y = 1;";
+        let tree = parse_syntax(input);
+        let messages: Vec<String> = tree.errors().iter().map(|e| e.message.clone()).collect();
+        assert_eq!(
+            messages,
+            ["integer literal 99999999999999999999 is too large, the maximum is 9223372036854775807"]
+        );
+        assert_eq!(tree.errors()[0].span, Some(Span::new(8, 28)));
+
+        let mut lexer = monkey::Lexer::new(input.to_string());
+        let mut parser = monkey::Parser::new(&mut lexer);
+        parser.parse_program();
+        assert_eq!(parser.errors(), messages);
+    }
+
+    #[test]
+    fn test_error_nodes_keep_tokens() {
+        let tree = parse_syntax(") let x = 1;");
+        let kinds: Vec<SyntaxKind> = tree.root().children().iter().map(|n| n.kind()).collect();
+        assert_eq!(kinds, [SyntaxKind::Error, SyntaxKind::LetStatement]);
+        assert_eq!(tree.root().children()[0].text(), ")");
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        let input = format!("{}1{}", "(".repeat(300), ")".repeat(300));
+        let tree = parse_syntax(&input);
+        assert_eq!(tree.root().text(), input);
+        assert_eq!(
+            tree.to_program().unwrap_err(),
+            ["expressions are nested too deeply, the maximum is 256"]
+        );
+        // reported at the first parenthesis past the limit
+        assert_eq!(tree.errors()[0].span, Some(Span::new(256, 257)));
+
+        let types = format!("let x: {}int{} = [];", "[".repeat(300), "]".repeat(300));
+        assert_eq!(parse_syntax(&types).errors().len(), 1);
+    }
+
+    #[test]
+    fn test_tree_dump() {
+        let tree = parse_syntax("let x = 1; // one");
+        assert_eq!(
+            format!("{:#?}", tree.root()),
+            r#"Program@0..17
+  LetStatement@0..10
+    LET@0..3 "let"
+    Whitespace@3..4 " "
+    Name@4..5
+      IDENT@4..5 "x"
+    Whitespace@5..6 " "
+    ASSIGN@6..7 "="
+    Whitespace@7..8 " "
+    IntegerLiteral@8..9
+      INT@8..9 "1"
+    SEMICOLON@9..10 ";"
+  Whitespace@10..11 " "
+  Comment@11..17 "// one""#
+        );
+    }
+
+    #[test]
+    fn test_rename() {
+        let input = "let n = 1; // n\nfn(x) { n + x }(n)";
+        let mut root = parse_syntax(input).root().clone();
+        while let Some(token) = root.tokens().into_iter().find(|token| {
+            token.kind() == SyntaxKind::Token(TokenType::IDENT) && token.text() == "n"
+        }) {
+            let ident = GreenToken::new(SyntaxKind::Token(TokenType::IDENT), "count");
+            root = SyntaxNode::new_root(token.replace_with(ident));
+        }
+        let output = "let count = 1; // n\nfn(x) { count + x }(count)";
+        assert_eq!(root.text(), output);
+        assert_eq!(monkey::lower(&root).unwrap(), parse(output));
+    }
+
+    #[test]
+    fn test_edits_share_unchanged_subtrees() {
+        let tree = parse_syntax("let a = 1; let b = 2;");
+        let root = tree.root();
+        let token = root.children()[1].first_token().unwrap();
+        let edited = SyntaxNode::new_root(
+            token.replace_with(GreenToken::new(SyntaxKind::Token(TokenType::LET), "let")),
+        );
+        assert!(std::rc::Rc::ptr_eq(
+            root.children()[0].green(),
+            edited.children()[0].green()
+        ));
+        assert!(!std::rc::Rc::ptr_eq(
+            root.children()[1].green(),
+            edited.children()[1].green()
+        ));
+    }
+
+    #[test]
+    fn test_parents() {
+        let tree = parse_syntax("f(1 + 2)");
+        let int = tree
+            .root()
+            .descendants()
+            .into_iter()
+            .find(|node| node.kind() == SyntaxKind::IntegerLiteral)
+            .unwrap();
+        let ancestors: Vec<SyntaxKind> = std::iter::successors(int.parent(), |node| node.parent())
+            .map(|node| node.kind())
+            .collect();
+        assert_eq!(
+            ancestors,
+            [
+                SyntaxKind::InfixExpression,
+                SyntaxKind::ArgumentList,
+                SyntaxKind::CallExpression,
+                SyntaxKind::ExpressionStatement,
+                SyntaxKind::Program,
+            ]
+        );
+    }
+}
@@ -0,0 +1,283 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::parse;
+    use monkey::{check_types, lint, resolve, LintConfig, Program, Statement, Type, TypeCheck};
+
+    fn check(input: &str) -> (Program, TypeCheck) {
+        let program = parse(input);
+        let check = check_types(&program, &resolve(&program));
+        (program, check)
+    }
+
+    /// The type of each top-level `let` as `name: type`.
+    fn types(input: &str) -> Vec<String> {
+        let (program, check) = check(input);
+        program
+            .statements
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Statement::Let(stmt) => Some(format!(
+                    "{}: {}",
+                    stmt.name.value,
+                    check.type_of(stmt.name.id).unwrap()
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Each type error as `offset: message`.
+    fn errors(input: &str) -> Vec<String> {
+        check(input)
+            .1
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| format!("{}: {}", diagnostic.span.unwrap().start, diagnostic.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_inference() {
+        let input = r#"
+let a = 1;
+let b = "s" + "t";
+let c = [1, 2];
+let d = {"k": true};
+let add = fn(x, y) { x + y * 2 };
+let id = fn(x) { x };
+let twice = fn(f, x) { f(f(x)) };
+let e = [];
+let less = fn(x, y) { x < y };
+"#;
+        assert_eq!(
+            types(input),
+            [
+                "a: int",
+                "b: string",
+                "c: [int]",
+                "d: {string: bool}",
+                "add: fn(int, int) -> int",
+                "id: fn('a) -> 'a",
+                "twice: fn(fn('a) -> 'a, 'a) -> 'a",
+                "e: ['a]",
+                "less: fn(int, int) -> bool",
+            ]
+        );
+        assert!(errors(input).is_empty());
+    }
+
+    #[test]
+    fn test_generalization() {
+        let input = r#"
+let id = fn(x) { x };
+let a = id(1);
+let b = id("s");
+let xs = push([], 1);
+let h = first(["a"]);
+let pair = fn(x) { fn(y) { [x, y] } };
+let p = pair(1)(2);
+"#;
+        assert_eq!(
+            types(input),
+            [
+                "id: fn('a) -> 'a",
+                "a: int",
+                "b: string",
+                "xs: [int]",
+                "h: string",
+                "pair: fn('a) -> fn('a) -> ['a]",
+                "p: [int]",
+            ]
+        );
+        assert!(errors(input).is_empty());
+        // parameters are not generalized inside their function
+        assert_eq!(
+            errors("fn(f) { f(1); f(\"s\") }"),
+            ["16: expected `int`, found `string`"]
+        );
+    }
+
+    #[test]
+    fn test_recursion() {
+        let input = "let fact = fn(n) { if (n < 2) { 1 } else { n * fact(n - 1) } };";
+        assert_eq!(types(input), ["fact: fn(int) -> int"]);
+        let input = "let f = fn(x) { if (x) { return 1; } 2 };";
+        assert_eq!(types(input), ["f: fn('a) -> int"]);
+
+        // a recursive call that doesn't fit the function
+        let input = "let f = fn(x) { if (x) { f(1, 2) } else { 0 } }; f(1);";
+        assert_eq!(
+            errors(input),
+            ["8: expected `fn(int, int) -> int`, found `fn('a) -> int`"]
+        );
+    }
+
+    #[test]
+    fn test_annotations() {
+        assert_eq!(
+            types("let x: int = 5; let f = fn(a: string, b) -> bool { b }; let g: any = 1;"),
+            ["x: int", "f: fn(string, bool) -> bool", "g: any"]
+        );
+        assert_eq!(
+            errors("let x: int = \"five\";"),
+            ["13: expected `int`, found `string`"]
+        );
+        assert_eq!(
+            errors("let f = fn(a: string) -> bool { a };"),
+            ["32: expected `bool`, found `string`"]
+        );
+        assert_eq!(
+            errors("let f = fn() -> int { if (true) { return \"s\"; } 1 };"),
+            ["41: expected `int`, found `string`"]
+        );
+        assert_eq!(
+            errors("let f = fn(a: [int]) { a }; f([\"s\"]);"),
+            ["31: expected `int`, found `string`"]
+        );
+        // each element is checked, not the `[any]` they'd infer to
+        assert_eq!(
+            errors("let a: [int] = [1, \"a\"]; let b: [[int]] = [[1], [true]];"),
+            [
+                "19: expected `int`, found `string`",
+                "49: expected `int`, found `bool`",
+            ]
+        );
+        assert_eq!(
+            errors("let h: {string: int} = {\"a\": 1, 2: \"b\"}; let m: [any] = [1, \"a\"];"),
+            [
+                "32: expected `string`, found `int`",
+                "35: expected `int`, found `string`",
+            ]
+        );
+        assert_eq!(types("let e: [int] = [];"), ["e: [int]"]);
+        // without an annotation, mixed elements are still fine
+        assert_eq!(
+            errors("len([1, \"a\"]); first([1, \"a\"]);"),
+            Vec::<String>::new()
+        );
+        // a declared type is used as it is, even where `any` would fit
+        assert_eq!(
+            types("let h: {string: any} = {\"a\": 1};"),
+            ["h: {string: any}"]
+        );
+    }
+
+    #[test]
+    fn test_unknown_type() {
+        let (_, check) = check("let x: integer = 1; let f = fn(a: [foo]) { a };");
+        let found: Vec<(usize, &str, Option<&str>)> = check
+            .diagnostics()
+            .iter()
+            .map(|d| (d.span.unwrap().start, d.message.as_str(), d.code))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (7, "unknown type `integer`", Some("unknown-type")),
+                (35, "unknown type `foo`", Some("unknown-type")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_operators() {
+        assert_eq!(
+            errors("1 + true; \"a\" - 1; -\"a\"; 1 == \"a\"; true < false; !5"),
+            [
+                "0: `+` can't be applied to `int` and `bool`",
+                "10: `-` can't be applied to `string` and `int`",
+                "19: `-` can't be applied to `string`",
+                "25: `==` can't be applied to `int` and `string`",
+                "35: `<` can't be applied to `bool` and `bool`",
+            ]
+        );
+        // `+` on unknown operands must come out as ints or strings
+        assert_eq!(
+            errors("let add = fn(a, b) { a + b }; add(1, 2); add(\"a\", \"b\")"),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            errors("fn(a) { a + a }(true)"),
+            ["8: `+` can't be applied to `bool`"]
+        );
+    }
+
+    #[test]
+    fn test_calls() {
+        assert_eq!(
+            errors("let f = fn(x) { x }; f(1, 2); 1(2); len(\"s\", 1)"),
+            [
+                "21: expected 1 argument, found 2",
+                "30: `int` is not a function",
+                "36: expected 1 argument, found 2",
+            ]
+        );
+        assert_eq!(errors("first(1)"), ["6: expected `['a]`, found `int`"]);
+    }
+
+    #[test]
+    fn test_indexing() {
+        assert_eq!(
+            errors("[1][\"a\"]; {\"a\": 1}[1]; \"s\"[0]; {[1]: 2}"),
+            [
+                "4: expected `int`, found `string`",
+                "19: expected `string`, found `int`",
+                "23: `string` can't be indexed",
+                "32: `[int]` can't be used as a hash key",
+            ]
+        );
+        assert_eq!(types("let v = {\"a\": [1]}[\"a\"][0];"), ["v: int"]);
+    }
+
+    #[test]
+    fn test_gradual() {
+        // unknown names and branches of different types are `any`
+        let input = r#"
+let x = nope;
+let y = x + 1;
+x(1)[0];
+let v = if (y > 0) { 1 } else { "s" };
+let w = if (true) { 1 };
+let m = try { throw 1 } catch (e) { e["message"] + "!" };
+let mixed = [1, "a"];
+"#;
+        assert_eq!(
+            types(input),
+            [
+                "x: any",
+                "y: int",
+                "v: any",
+                "w: any",
+                "m: string",
+                "mixed: [any]",
+            ]
+        );
+        assert!(errors(input).is_empty());
+        // a later `let` isn't known yet where a function uses it
+        assert!(errors("let f = fn() { g(1) }; let g = fn(s: string) { s };").is_empty());
+    }
+
+    #[test]
+    fn test_type_display() {
+        let ty = Type::Function(
+            vec![
+                Type::Var(7),
+                Type::Hash(Box::new(Type::String), Box::new(Type::Var(3))),
+            ],
+            Box::new(Type::Array(Box::new(Type::Var(7)))),
+        );
+        assert_eq!(ty.to_string(), "fn('a, {string: 'b}) -> ['a]");
+        assert_eq!(ty.vars(), [7, 3]);
+    }
+
+    #[test]
+    fn test_lint_reports_type_errors() {
+        let diagnostics = lint("let x: int = true; x", &LintConfig::default());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, Some("type-mismatch"));
+        assert_eq!(diagnostics[0].message, "expected `int`, found `bool`");
+        assert_eq!(diagnostics[0].severity, monkey::Severity::Error);
+    }
+}
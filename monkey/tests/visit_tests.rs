@@ -0,0 +1,77 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::parse;
+    use monkey::{
+        walk_expression_mut, walk_function_literal, Expression, FunctionLiteral, Identifier,
+        IntegerLiteral, Visitor, VisitorMut,
+    };
+
+    #[derive(Default)]
+    struct Identifiers<'ast> {
+        names: Vec<&'ast str>,
+        functions: usize,
+    }
+
+    impl<'ast> Visitor<'ast> for Identifiers<'ast> {
+        fn visit_identifier(&mut self, ident: &'ast Identifier) {
+            self.names.push(&ident.value);
+        }
+
+        fn visit_function_literal(&mut self, func: &'ast FunctionLiteral) {
+            self.functions += 1;
+            walk_function_literal(self, func);
+        }
+    }
+
+    #[test]
+    fn test_visitor_reaches_every_node() {
+        let program = parse(
+            r#"
+            let add = fn(a, b) { a + b };
+            if (x > 1) { add(y, [z][0]) } else { {"k": w}["k"] };
+            try { throw -v; } catch (err) { return err; }
+            "#,
+        );
+        let mut visitor = Identifiers::default();
+        visitor.visit_program(&program);
+        assert_eq!(
+            visitor.names,
+            ["add", "a", "b", "a", "b", "x", "add", "y", "z", "w", "v", "err", "err"]
+        );
+        assert_eq!(visitor.functions, 1);
+    }
+
+    struct Double;
+
+    impl VisitorMut for Double {
+        fn visit_integer_literal(&mut self, lit: &mut IntegerLiteral) {
+            lit.value *= 2;
+        }
+
+        fn visit_expression(&mut self, exp: &mut Expression) {
+            walk_expression_mut(self, exp);
+            if let Expression::Identifier(ident) = exp {
+                ident.value = ident.value.to_uppercase();
+            }
+        }
+    }
+
+    #[test]
+    fn test_visitor_mut_rewrites_in_place() {
+        let mut program = parse("let f = fn(x) { x * 3 }; f(1 + [2][0]);");
+        let original = program.clone();
+        Double.visit_program(&mut program);
+
+        assert_eq!(
+            program.to_string(),
+            "let f = fn(x) (X * 6);F((2 + ([4][0])))"
+        );
+        // the function body is shared with the clone until it is rewritten
+        assert_eq!(
+            original.to_string(),
+            "let f = fn(x) (x * 3);f((1 + ([2][0])))"
+        );
+    }
+}